@@ -0,0 +1,63 @@
+//! Throughput of the hot conversions. Run with `cargo bench`; see the
+//! README for comparing against a saved baseline.
+use std::path::PathBuf;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use file_url::{file_url_to_pathbuf, file_urls_to_paths, paths_to_file_urls, PathFileUrlExt};
+
+const BATCH: usize = 1000;
+
+fn clean_paths() -> Vec<PathBuf> {
+    (0..BATCH)
+        .map(|i| PathBuf::from(format!("/home/user/projects/src/file_{}.rs", i)))
+        .collect()
+}
+
+fn escaped_paths() -> Vec<PathBuf> {
+    (0..BATCH)
+        .map(|i| PathBuf::from(format!("/home/my user/Projekte/Grüße/file {}.rs", i)))
+        .collect()
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    group.throughput(Throughput::Elements(BATCH as u64));
+    for (name, paths) in [("clean", clean_paths()), ("escaped", escaped_paths())].iter() {
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                for p in paths.iter() {
+                    black_box(p.to_file_url().unwrap());
+                }
+            })
+        });
+    }
+    let paths = clean_paths();
+    group.bench_function("batch", |b| {
+        b.iter(|| black_box(paths_to_file_urls(&paths)))
+    });
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Elements(BATCH as u64));
+    for (name, paths) in [("clean", clean_paths()), ("escaped", escaped_paths())].iter() {
+        let urls: Vec<String> = paths.iter().map(|p| p.to_file_url().unwrap()).collect();
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                for u in urls.iter() {
+                    black_box(file_url_to_pathbuf(u).unwrap());
+                }
+            })
+        });
+    }
+    let urls: Vec<String> = clean_paths()
+        .iter()
+        .map(|p| p.to_file_url().unwrap())
+        .collect();
+    group.bench_function("batch", |b| b.iter(|| black_box(file_urls_to_paths(&urls))));
+    group.finish();
+}
+
+criterion_group!(benches, encode, decode);
+criterion_main!(benches);
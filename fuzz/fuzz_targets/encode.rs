@@ -0,0 +1,22 @@
+//! Feeds arbitrary paths to everything that builds a file URL.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use std::path::Path;
+
+use file_url::{encode_file_component, FileUrlBuf, PathFileUrlExt, Platform};
+
+fuzz_target!(|input: &str| {
+    let _ = encode_file_component(input);
+    let _ = Platform::Posix.path_to_file_url(input);
+    let _ = Platform::Posix.is_absolute(input);
+    let _ = Platform::Windows.path_to_file_url(input);
+    let _ = Platform::Windows.is_absolute(input);
+
+    let path = Path::new(input);
+    if let Ok(url) = path.to_file_url() {
+        let _ = file_url::file_url_to_pathbuf(&url);
+    }
+    let _ = path.to_file_url_relative_to(input);
+    let _ = FileUrlBuf::from_path(path);
+});
@@ -0,0 +1,39 @@
+//! Feeds arbitrary strings to everything that parses a file URL.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use std::path::Path;
+
+use file_url::{
+    contains_traversal, decode_file_url_in_place, file_url_to_pathbuf, relative_url, FileUrl,
+    FileUrlGlob, Platform, SandboxedResolver, UrlOrdering,
+};
+
+fuzz_target!(|input: &str| {
+    let _ = file_url_to_pathbuf(input);
+    let _ = decode_file_url_in_place(input.to_string());
+    let _ = contains_traversal(input);
+    let _ = Platform::Posix.file_url_to_path(input);
+    let _ = Platform::Windows.file_url_to_path(input);
+    let _ = SandboxedResolver::new(Path::new("/srv")).resolve(input);
+
+    // Split the input to get a second URL to pair it with.
+    let mid = input.len() / 2;
+    let (a, b) = match (input.get(..mid), input.get(mid..)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => (input, input),
+    };
+    let _ = relative_url(a, b);
+    if let (Ok(glob), Ok(url)) = (FileUrlGlob::new(a), FileUrl::new(b)) {
+        let _ = glob.matches(url);
+    }
+
+    if let Ok(url) = FileUrl::new(input) {
+        let _ = url.to_path_buf();
+        let _ = url.components().count();
+        let _ = url.is_within(url);
+        let _ = UrlOrdering::default().compare(url, url);
+        let mut buf = url.to_file_url_buf();
+        buf.push(a);
+    }
+});
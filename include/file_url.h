@@ -0,0 +1,25 @@
+/* C interface to the file_url crate, built with `--features capi`.
+ *
+ * Strings are NUL-terminated UTF-8. Release every non-NULL return
+ * value with file_url_free. NULL means the conversion failed. */
+#ifndef FILE_URL_H
+#define FILE_URL_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Converts a path to a file URL, or NULL if it isn't UTF-8. */
+char *file_url_from_path(const char *path);
+
+/* Converts a file URL to a path, or NULL if it doesn't decode to UTF-8. */
+char *file_url_to_path(const char *url);
+
+/* Frees a string returned by the functions above. NULL is ignored. */
+void file_url_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
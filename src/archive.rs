@@ -0,0 +1,146 @@
+//! `jar:` URLs, which name an entry inside a zip archive, like
+//! `jar:file:///libs/app.jar!/com/Foo.class`.
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::string::FromUtf8Error;
+
+use crate::percent::decode;
+use crate::{encode_file_component, file_url_to_pathbuf, FileUrl, PathFileUrlExt, UTFDecodeError};
+
+/// Error for URLs `ArchiveUrl::parse` can't read.
+#[derive(Debug)]
+pub enum ArchiveUrlError {
+    /// The scheme isn't `jar:`.
+    NotArchiveUrl,
+    /// There's no `!/` between the archive and the entry.
+    MissingSeparator,
+    /// The archive isn't named by a file URL. Archives inside
+    /// archives, `jar:jar:file:...`, are among these.
+    NotFileUrl,
+    /// The archive's path or the entry doesn't decode to UTF-8.
+    Decode(FromUtf8Error),
+}
+
+impl fmt::Display for ArchiveUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArchiveUrlError::NotArchiveUrl => write!(f, "URL is not a jar: URL!"),
+            ArchiveUrlError::MissingSeparator => write!(f, "Archive URL has no !/ separator!"),
+            ArchiveUrlError::NotFileUrl => write!(f, "Archive is not named by a file URL!"),
+            ArchiveUrlError::Decode(_) => write!(f, "Archive URL does not decode to UTF-8!"),
+        }
+    }
+}
+
+impl Error for ArchiveUrlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ArchiveUrlError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// An entry in a zip archive on disk, as a `jar:` URL names it. JVM
+/// tooling writes these for classes and resources loaded from jars,
+/// and they often pass through code that otherwise handles file URLs.
+///
+/// # Example:
+/// ```
+/// use std::path::PathBuf;
+/// use file_url::ArchiveUrl;
+///
+/// let url = ArchiveUrl::parse("jar:file:///libs/my%20app.jar!/com/Foo.class").unwrap();
+/// assert_eq!(url.archive, PathBuf::from("/libs/my app.jar"));
+/// assert_eq!(url.entry, "com/Foo.class");
+/// assert_eq!(url.to_url().unwrap(), "jar:file:///libs/my%20app.jar!/com/Foo.class");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ArchiveUrl {
+    /// The archive's path.
+    pub archive: PathBuf,
+    /// The entry's path inside the archive, decoded, with `/`
+    /// separators and no leading one. Empty for the archive's root.
+    pub entry: String,
+}
+
+impl ArchiveUrl {
+    /// Reads a `jar:` URL, splitting it at the first `!/` as the JVM
+    /// does, so an archive whose own name has `!/` in it can't be
+    /// named.
+    pub fn parse(url: &str) -> Result<ArchiveUrl, ArchiveUrlError> {
+        let rest = match url.get(..4) {
+            Some(scheme) if scheme.eq_ignore_ascii_case("jar:") => &url[4..],
+            _ => return Err(ArchiveUrlError::NotArchiveUrl),
+        };
+        let i = rest.find("!/").ok_or(ArchiveUrlError::MissingSeparator)?;
+        let (outer, entry) = (&rest[..i], &rest[i + 2..]);
+        FileUrl::new(outer).map_err(|_| ArchiveUrlError::NotFileUrl)?;
+        Ok(ArchiveUrl {
+            archive: file_url_to_pathbuf(outer).map_err(ArchiveUrlError::Decode)?,
+            entry: decode(entry).map_err(ArchiveUrlError::Decode)?.into_owned(),
+        })
+    }
+
+    /// The `jar:` URL for this entry, with the archive's path encoded as
+    /// `to_file_url` does, and each segment of the entry encoded the
+    /// same way.
+    pub fn to_url(&self) -> Result<String, UTFDecodeError> {
+        let mut url = format!("jar:{}!/", self.archive.to_file_url()?);
+        for (i, segment) in self.entry.split('/').enumerate() {
+            if i > 0 {
+                url.push('/');
+            }
+            url.push_str(&encode_file_component(segment));
+        }
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_entries() {
+        let cases = [
+            ("jar:file:///a.jar!/", "/a.jar", ""),
+            ("jar:file:///a.jar!/META-INF/", "/a.jar", "META-INF/"),
+            ("jar:file:///a%21/b.jar!/x%21/y%20z", "/a!/b.jar", "x!/y z"),
+        ];
+        for (url, archive, entry) in cases.iter() {
+            let parsed = ArchiveUrl::parse(url).unwrap();
+            assert_eq!(parsed.archive, PathBuf::from(archive), "{}", url);
+            assert_eq!(parsed.entry, *entry, "{}", url);
+            assert_eq!(parsed.to_url().unwrap(), *url);
+        }
+        let upper = ArchiveUrl::parse("JAR:file:/a.jar!/b").unwrap();
+        assert_eq!(upper.to_url().unwrap(), "jar:file:///a.jar!/b");
+    }
+
+    #[test]
+    fn rejects_other_forms() {
+        let err = |url| ArchiveUrl::parse(url).unwrap_err();
+        assert!(matches!(
+            err("file:///a.jar!/b"),
+            ArchiveUrlError::NotArchiveUrl
+        ));
+        assert!(matches!(
+            err("jar:file:///a.jar"),
+            ArchiveUrlError::MissingSeparator
+        ));
+        assert!(matches!(
+            err("jar:jar:file:///a.jar!/b.jar!/c"),
+            ArchiveUrlError::NotFileUrl
+        ));
+        assert!(matches!(
+            err("jar:https://example.com/a.jar!/b"),
+            ArchiveUrlError::NotFileUrl
+        ));
+        assert!(matches!(
+            err("jar:file:///a.jar!/%FF"),
+            ArchiveUrlError::Decode(_)
+        ));
+    }
+}
@@ -0,0 +1,403 @@
+//! The `file-url` command line tool, built with the `cli` feature.
+//!
+//! ```text
+//! $ file-url encode "/tmp/a b.txt"
+//! file:///tmp/a%20b.txt
+//! $ file-url decode file:///tmp/a%20b.txt
+//! /tmp/a b.txt
+//! $ find /tmp -name '*.txt' | file-url encode -
+//! $ file-url --json encode rel.txt
+//! {"input":"rel.txt","output":"file:///home/me/rel.txt","warnings":["Relative path resolved against the current directory."]}
+//! $ file-url --platform windows encode 'C:\Build\out.zip'
+//! file:///C:/Build/out.zip
+//! $ file-url --profile localhost,lower-drive --platform windows encode 'C:\Build\out.zip'
+//! file://localhost/c:/Build/out.zip
+//! $ file-url --strict decode 'file:///tmp/a b.txt'
+//! file-url: file:///tmp/a b.txt: File URL contains an unencoded ' ' at byte 13!
+//! ```
+use std::env;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str;
+
+use clap::{Parser, Subcommand};
+use file_url::{
+    FileUrl, OutputProfile, ParseOptions, ParseProfileError, Platform, Strictness, UrlComponent,
+};
+use serde_json::json;
+
+#[derive(Parser)]
+#[command(
+    name = "file-url",
+    version,
+    about = "Converts between paths and file URLs."
+)]
+struct Cli {
+    /// Write one JSON record per conversion, with any warnings.
+    #[arg(long, global = true)]
+    json: bool,
+    /// Convert using `windows` or `unix` path syntax rather than the
+    /// host's. Relative paths can't be resolved for another platform,
+    /// so they're taken relative to the root.
+    #[arg(long, global = true, value_parser = parse_platform)]
+    platform: Option<Platform>,
+    /// How `encode` writes its URLs, as a comma-separated list of
+    /// `localhost`, `omit-authority`, `keep-escapes`, `lower-hex`,
+    /// `upper-drive`, `lower-drive`, `trim-slash`, `keep=BYTES` and
+    /// `escape=BYTES`.
+    #[arg(long, global = true, value_parser = parse_profile, default_value = "")]
+    profile: OutputProfile,
+    /// Have `decode` refuse URLs with unencoded characters or the
+    /// legacy UNC form, rather than reading them as pasted.
+    #[arg(long, global = true)]
+    strict: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Converts a path to a file URL. Relative paths are resolved
+    /// against the current directory. Pass `-` to convert each line
+    /// of stdin instead.
+    Encode { path: PathBuf },
+    /// Converts a file URL to a path. Pass `-` to convert each line
+    /// of stdin instead.
+    Decode { url: String },
+}
+
+fn parse_platform(name: &str) -> Result<Platform, String> {
+    Platform::from_name(name).ok_or_else(|| format!("expected windows or unix, not {}", name))
+}
+
+fn parse_profile(names: &str) -> Result<OutputProfile, String> {
+    names.parse().map_err(|e: ParseProfileError| e.to_string())
+}
+
+impl Cli {
+    fn parse_options(&self) -> ParseOptions {
+        let strictness = if self.strict {
+            Strictness::Strict
+        } else {
+            Strictness::Lenient
+        };
+        ParseOptions {
+            strictness,
+            ..ParseOptions::default()
+        }
+    }
+}
+
+impl Command {
+    fn reads_stdin(&self) -> bool {
+        match self {
+            Command::Encode { path } => path == Path::new("-"),
+            Command::Decode { url } => url == "-",
+        }
+    }
+
+    fn convert(&self, cli: &Cli, input: &str) -> Result<Conversion, String> {
+        match (self, cli.platform) {
+            (Command::Encode { .. }, None) => encode(&cli.profile, Path::new(input)),
+            (Command::Encode { .. }, Some(platform)) => encode_for(&cli.profile, platform, input),
+            (Command::Decode { .. }, None) => decode(cli.parse_options(), input),
+            (Command::Decode { .. }, Some(platform)) => {
+                decode_for(cli.parse_options(), platform, input)
+            }
+        }
+    }
+}
+
+/// A successful conversion, plus anything the user might not expect
+/// about it.
+struct Conversion {
+    output: String,
+    warnings: Vec<String>,
+}
+
+fn encode(profile: &OutputProfile, path: &Path) -> Result<Conversion, String> {
+    let mut warnings = Vec::new();
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        warnings.push(String::from(
+            "Relative path resolved against the current directory.",
+        ));
+        env::current_dir().map_err(|e| e.to_string())?.join(path)
+    };
+    let output = profile.to_file_url(&absolute).map_err(|e| e.to_string())?;
+    Ok(Conversion { output, warnings })
+}
+
+fn decode(options: ParseOptions, url: &str) -> Result<Conversion, String> {
+    let url = FileUrl::parse_with(url, options).map_err(|e| e.to_string())?;
+    let mut warnings = Vec::new();
+    if let Some(UrlComponent::Host(host)) = url.components().nth(1) {
+        warnings.push(format!(
+            "URL has host {}, which was kept as the first path segment.",
+            host
+        ));
+    }
+    // A validated URL decodes to UTF-8, so nothing is lost here.
+    let output = url.to_path_buf().display().to_string();
+    Ok(Conversion { output, warnings })
+}
+
+fn encode_for(
+    profile: &OutputProfile,
+    platform: Platform,
+    path: &str,
+) -> Result<Conversion, String> {
+    let mut warnings = Vec::new();
+    if !platform.is_absolute(path) {
+        warnings.push(String::from(
+            "Path is not absolute, so it was taken relative to the root.",
+        ));
+    }
+    let output = profile.to_file_url_on(path, platform);
+    Ok(Conversion { output, warnings })
+}
+
+fn decode_for(options: ParseOptions, platform: Platform, url: &str) -> Result<Conversion, String> {
+    FileUrl::parse_with(url, options).map_err(|e| e.to_string())?;
+    let output = platform.file_url_to_path(url).map_err(|e| e.to_string())?;
+    Ok(Conversion {
+        output,
+        warnings: Vec::new(),
+    })
+}
+
+/// Writes the result of converting `input`. As text, failures go to
+/// stderr and warnings are left out; as JSON, every input gets a
+/// record on `output`.
+fn emit<W: Write>(
+    output: &mut W,
+    json: bool,
+    input: &str,
+    result: Result<Conversion, String>,
+) -> io::Result<()> {
+    match (json, result) {
+        (false, Ok(conversion)) => writeln!(output, "{}", conversion.output)?,
+        (false, Err(e)) => eprintln!("file-url: {}: {}", input, e),
+        (true, Ok(conversion)) => writeln!(
+            output,
+            "{}",
+            json!({
+                "input": input,
+                "output": conversion.output,
+                "warnings": conversion.warnings,
+            })
+        )?,
+        (true, Err(e)) => writeln!(
+            output,
+            "{}",
+            json!({
+                "input": input,
+                "output": null,
+                "warnings": [],
+                "error": e,
+            })
+        )?,
+    }
+    Ok(())
+}
+
+/// Converts `input` line by line. Lines that fail are reported on
+/// stderr and skipped, and blank lines are ignored. Returns whether
+/// every line converted.
+fn batch<R: BufRead, W: Write>(cli: &Cli, mut input: R, output: W) -> io::Result<bool> {
+    let mut output = BufWriter::new(output);
+    let mut line = Vec::new();
+    let mut all_ok = true;
+    loop {
+        line.clear();
+        if input.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        let trimmed = line
+            .strip_suffix(b"\n")
+            .map_or(&line[..], |l| l.strip_suffix(b"\r").unwrap_or(l));
+        if trimmed.is_empty() {
+            continue;
+        }
+        let result = str::from_utf8(trimmed)
+            .map_err(|_| String::from("Input line is not UTF-8!"))
+            .and_then(|l| cli.command.convert(cli, l));
+        let ok = result.is_ok();
+        emit(
+            &mut output,
+            cli.json,
+            &String::from_utf8_lossy(trimmed),
+            result,
+        )?;
+        all_ok &= ok;
+    }
+    output.flush()?;
+    Ok(all_ok)
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let ok = if cli.command.reads_stdin() {
+        let stdin = io::stdin();
+        let stdout = io::stdout();
+        match batch(&cli, stdin.lock(), stdout.lock()) {
+            Ok(ok) => ok,
+            // The reader went away, e.g. `| head`; that's not an error.
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => true,
+            Err(e) => {
+                eprintln!("file-url: {}", e);
+                false
+            }
+        }
+    } else {
+        let (input, result) = match (&cli.command, cli.platform) {
+            (Command::Encode { path }, None) => {
+                (path.to_string_lossy(), encode(&cli.profile, path))
+            }
+            (Command::Encode { path }, Some(platform)) => match path.to_str() {
+                Some(p) => (p.into(), encode_for(&cli.profile, platform, p)),
+                None => (
+                    path.to_string_lossy(),
+                    Err(String::from("File path not UTF-8 compatible!")),
+                ),
+            },
+            (Command::Decode { url }, _) => (url.into(), cli.command.convert(&cli, url)),
+        };
+        let ok = result.is_ok();
+        let stdout = io::stdout();
+        if let Err(e) = emit(&mut stdout.lock(), cli.json, &input, result) {
+            eprintln!("file-url: {}", e);
+        }
+        ok
+    };
+    if !ok {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use file_url::PathFileUrlExt;
+
+    #[test]
+    fn encodes_relative_to_cwd() {
+        let cwd = env::current_dir().unwrap();
+        assert_eq!(
+            encode(&OutputProfile::DEFAULT, Path::new("a b.txt"))
+                .unwrap()
+                .output,
+            cwd.join("a b.txt").to_file_url().unwrap()
+        );
+    }
+
+    #[test]
+    fn decodes_only_file_urls() {
+        let lenient = ParseOptions::default();
+        assert_eq!(
+            decode(lenient, "file:///tmp/a%20b").unwrap().output,
+            "/tmp/a b"
+        );
+        assert!(decode(lenient, "http://example.com/").is_err());
+        assert!(decode(lenient, "file:///%FF").is_err());
+    }
+
+    #[test]
+    fn strict_decoding_refuses_pasted_urls() {
+        let input: &[u8] = b"file:///a b\nfile:///a%20b\nfile:////srv/share\n";
+        let cli = Cli::parse_from(["file-url", "decode", "-"]);
+        let mut output = Vec::new();
+        assert!(batch(&cli, input, &mut output).unwrap());
+        assert_eq!(output, b"/a b\n/a b\n/srv/share\n");
+
+        let cli = Cli::parse_from(["file-url", "--strict", "decode", "-"]);
+        let mut output = Vec::new();
+        assert!(!batch(&cli, input, &mut output).unwrap());
+        assert_eq!(output, b"/a b\n");
+
+        let cli = Cli::parse_from([
+            "file-url",
+            "--strict",
+            "--platform",
+            "windows",
+            "decode",
+            "-",
+        ]);
+        let mut output = Vec::new();
+        assert!(!batch(&cli, input, &mut output).unwrap());
+        assert_eq!(output, b"\\a b\n");
+    }
+
+    #[test]
+    fn batch_converts_each_line() {
+        let cli = Cli::parse_from(["file-url", "decode", "-"]);
+        let input: &[u8] = b"file:///a%20b\r\n\nnot a url\nfile:///c\n";
+        let mut output = Vec::new();
+        let ok = batch(&cli, input, &mut output).unwrap();
+        assert!(!ok);
+        assert_eq!(output, b"/a b\n/c\n");
+    }
+
+    #[test]
+    fn json_records() {
+        let cli = Cli::parse_from(["file-url", "decode", "--json", "-"]);
+        let input: &[u8] = b"file://server/a\nhttp://x/\n";
+        let mut output = Vec::new();
+        batch(&cli, input, &mut output).unwrap();
+
+        let records: Vec<serde_json::Value> = output
+            .split(|b| *b == b'\n')
+            .filter(|l| !l.is_empty())
+            .map(|l| serde_json::from_slice(l).unwrap())
+            .collect();
+        assert_eq!(records[0]["output"], "/server/a");
+        assert_eq!(records[0]["warnings"].as_array().unwrap().len(), 1);
+        assert_eq!(records[1]["input"], "http://x/");
+        assert!(records[1]["output"].is_null());
+        assert!(records[1]["error"].is_string());
+    }
+
+    #[test]
+    fn foreign_platform() {
+        let cli = Cli::parse_from(["file-url", "--platform", "windows", "encode", "-"]);
+        let input: &[u8] = b"C:\\Build\\my app.zip\n\\\\ci\\drop\\x\n";
+        let mut output = Vec::new();
+        assert!(batch(&cli, input, &mut output).unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "file:///C:/Build/my%20app.zip\nfile://ci/drop/x\n"
+        );
+
+        let cli = Cli::parse_from(["file-url", "--platform", "windows", "decode", "-"]);
+        let mut output = Vec::new();
+        batch(&cli, &b"file:///C:/a%20b\n"[..], &mut output).unwrap();
+        assert_eq!(output, b"C:\\a b\n");
+
+        assert!(Cli::try_parse_from(["file-url", "--platform", "beos", "decode", "-"]).is_err());
+    }
+
+    #[test]
+    fn profiles_shape_encoded_urls() {
+        let cli = Cli::parse_from([
+            "file-url",
+            "--platform",
+            "windows",
+            "--profile",
+            "localhost,lower-drive,lower-hex,trim-slash",
+            "encode",
+            "-",
+        ]);
+        let mut output = Vec::new();
+        assert!(batch(&cli, &b"C:\\Users\\Zo\xc3\xab\\\n"[..], &mut output).unwrap());
+        assert_eq!(output, b"file://localhost/c:/Users/Zo%c3%ab\n");
+
+        let cli = Cli::parse_from(["file-url", "--profile", "omit-authority", "encode", "-"]);
+        let mut output = Vec::new();
+        assert!(batch(&cli, &b"/a b\n"[..], &mut output).unwrap());
+        assert_eq!(output, b"file:/a%20b\n");
+
+        assert!(Cli::try_parse_from(["file-url", "--profile", "loud", "encode", "-"]).is_err());
+    }
+}
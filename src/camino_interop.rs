@@ -0,0 +1,55 @@
+//! Conversions for `camino::Utf8Path`, behind the `camino` feature.
+use std::string::FromUtf8Error;
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::{file_url_to_pathbuf, PathFileUrlExt, PathFromFileUrlExt, UTFDecodeError};
+
+/// Like `file_url_to_pathbuf`, but returns a `Utf8PathBuf`. Any
+/// segment that doesn't decode to UTF-8 is an error, rather than
+/// something callers have to handle later.
+///
+/// # Example:
+/// ```
+/// use camino::Utf8PathBuf;
+/// use file_url::file_url_to_utf8_pathbuf;
+///
+/// let p_buf = file_url_to_utf8_pathbuf("file:///foo/bar%20baz.txt").unwrap();
+/// assert_eq!(p_buf, Utf8PathBuf::from("/foo/bar baz.txt"));
+///
+/// assert!(file_url_to_utf8_pathbuf("file:///foo/%FF").is_err());
+/// ```
+pub fn file_url_to_utf8_pathbuf(file_url: &str) -> Result<Utf8PathBuf, FromUtf8Error> {
+    let path = file_url_to_pathbuf(file_url)?;
+    // Every segment was decoded into a String, so this can't fail.
+    Ok(Utf8PathBuf::from_path_buf(path).unwrap())
+}
+
+impl PathFileUrlExt for Utf8Path {
+    fn to_file_url(&self) -> Result<String, UTFDecodeError> {
+        self.as_std_path().to_file_url()
+    }
+
+    fn to_file_url_relative_to(&self, base_url: &str) -> Result<String, UTFDecodeError> {
+        self.as_std_path().to_file_url_relative_to(base_url)
+    }
+}
+
+impl PathFromFileUrlExt<Utf8PathBuf> for Utf8PathBuf {
+    fn from_file_url(file_url: &str) -> Result<Utf8PathBuf, FromUtf8Error> {
+        file_url_to_utf8_pathbuf(file_url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf8_path_round_trip() {
+        let path = Utf8PathBuf::from("/gi>/some & what.whtvr");
+        let url = path.to_file_url().unwrap();
+        assert_eq!(url, "file:///gi%3E/some%20%26%20what.whtvr");
+        assert_eq!(Utf8PathBuf::from_file_url(&url).unwrap(), path);
+    }
+}
@@ -0,0 +1,129 @@
+//! Capability-based file access by URL, behind the `cap-std` feature.
+//! A `SandboxedResolver` does the lexical containment check and then
+//! `cap_std` does the actual open relative to its `Dir`, so symlinks
+//! that escape the directory are refused by the OS-level sandbox too.
+use std::io;
+use std::path::{Path, PathBuf};
+
+use cap_std::fs::{Dir, File, Metadata};
+
+use crate::SandboxedResolver;
+
+/// A capability `Dir` together with the absolute path it was opened
+/// at, so that absolute file URLs can be mapped into it. URLs that
+/// don't decode come back as `io::ErrorKind::InvalidInput` errors,
+/// and ones that land outside the directory as
+/// `io::ErrorKind::PermissionDenied`.
+///
+/// # Example:
+/// ```no_run
+/// use std::path::Path;
+/// use cap_std::{ambient_authority, fs::Dir};
+/// use file_url::SandboxedDir;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let root = Path::new("/srv/files");
+/// let dir = Dir::open_ambient_dir(root, ambient_authority())?;
+/// let sandbox = SandboxedDir::new(dir, root);
+///
+/// let page = sandbox.read_to_string_from_url("file:///srv/files/index.html")?;
+/// assert!(sandbox.open_file_url("file:///srv/files/../../etc/passwd").is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SandboxedDir {
+    dir: Dir,
+    resolver: SandboxedResolver,
+}
+
+impl SandboxedDir {
+    /// Wraps `dir`, which should have been opened at the absolute
+    /// path `root`.
+    pub fn new(dir: Dir, root: &Path) -> SandboxedDir {
+        SandboxedDir {
+            dir,
+            resolver: SandboxedResolver::new(root),
+        }
+    }
+
+    /// The underlying capability directory.
+    pub fn dir(&self) -> &Dir {
+        &self.dir
+    }
+
+    /// Opens the file a URL points to in read-only mode.
+    pub fn open_file_url(&self, file_url: &str) -> io::Result<File> {
+        self.dir.open(self.relative_path(file_url)?)
+    }
+
+    /// Opens the directory a URL points to as another capability `Dir`.
+    pub fn open_dir_file_url(&self, file_url: &str) -> io::Result<Dir> {
+        self.dir.open_dir(self.relative_path(file_url)?)
+    }
+
+    /// Reads the whole file a URL points to as a string.
+    pub fn read_to_string_from_url(&self, file_url: &str) -> io::Result<String> {
+        self.dir.read_to_string(self.relative_path(file_url)?)
+    }
+
+    /// Queries metadata for the file or directory a URL points to.
+    pub fn metadata_from_url(&self, file_url: &str) -> io::Result<Metadata> {
+        self.dir.metadata(self.relative_path(file_url)?)
+    }
+
+    /// Maps a URL to a path relative to the directory, as `cap_std`
+    /// requires.
+    fn relative_path(&self, file_url: &str) -> io::Result<PathBuf> {
+        let path = self.resolver.resolve(file_url)?;
+        let rel = path.strip_prefix(self.resolver.root()).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{} is outside the sandbox root!", path.display()),
+            )
+        })?;
+        if rel.as_os_str().is_empty() {
+            Ok(PathBuf::from("."))
+        } else {
+            Ok(rel.to_path_buf())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PathFileUrlExt;
+    use cap_std::ambient_authority;
+
+    #[test]
+    fn opens_inside_refuses_outside() {
+        let base = std::env::temp_dir().join(format!("file_url cap {}", std::process::id()));
+        let root = base.join("root");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub/a b.txt"), "inside").unwrap();
+        std::fs::write(base.join("secret.txt"), "outside").unwrap();
+
+        let dir = Dir::open_ambient_dir(&root, ambient_authority()).unwrap();
+        let sandbox = SandboxedDir::new(dir, &root);
+
+        let inside = root.join("sub/a b.txt").to_file_url().unwrap();
+        assert_eq!(sandbox.read_to_string_from_url(&inside).unwrap(), "inside");
+        assert!(sandbox
+            .metadata_from_url(&root.to_file_url().unwrap())
+            .unwrap()
+            .is_dir());
+        assert!(sandbox
+            .open_dir_file_url(&root.join("sub").to_file_url().unwrap())
+            .is_ok());
+
+        let escape = format!("{}/..%2Fsecret.txt", root.to_file_url().unwrap());
+        let err = sandbox.open_file_url(&escape).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+        let err = sandbox.open_file_url("file:///%FF").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
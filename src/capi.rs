@@ -0,0 +1,105 @@
+//! C ABI, behind the `capi` feature. The crate builds as a `cdylib`
+//! too, and `include/file_url.h` declares these functions.
+//!
+//! Strings going in and out are NUL-terminated UTF-8. Every non-null
+//! string returned must be released with `file_url_free`, and a null
+//! return means the conversion failed.
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::Path;
+use std::ptr;
+
+use crate::{file_url_to_pathbuf, PathFileUrlExt};
+
+/// Borrows a C string as UTF-8, or `None` for null or invalid input.
+unsafe fn input<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+/// Hands a string to the caller, or null if it has an interior NUL.
+fn output(s: String) -> *mut c_char {
+    match CString::new(s) {
+        Ok(s) => s.into_raw(),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Converts a path to a file URL. Returns null if `path` is null or
+/// not UTF-8.
+///
+/// # Safety
+/// `path` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn file_url_from_path(path: *const c_char) -> *mut c_char {
+    match input(path).map(|p| Path::new(p).to_file_url()) {
+        Some(Ok(url)) => output(url),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Converts a file URL to a path. Returns null if `url` is null or
+/// doesn't decode to UTF-8.
+///
+/// # Safety
+/// `url` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn file_url_to_path(url: *const c_char) -> *mut c_char {
+    let path = input(url).map(file_url_to_pathbuf);
+    match path {
+        // The path was built from UTF-8 pieces, so `to_str` can't fail.
+        Some(Ok(path)) => output(path.to_str().unwrap().to_string()),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Frees a string returned by this library. Null is ignored.
+///
+/// # Safety
+/// `s` must be null or a pointer returned by one of the functions
+/// above that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn file_url_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(s: *mut c_char) -> Option<String> {
+        if s.is_null() {
+            return None;
+        }
+        let owned = CStr::from_ptr(s).to_str().unwrap().to_string();
+        file_url_free(s);
+        Some(owned)
+    }
+
+    #[test]
+    fn round_trip_through_c_strings() {
+        let path = CString::new("/tmp/a b.txt").unwrap();
+        unsafe {
+            let url = take(file_url_from_path(path.as_ptr())).unwrap();
+            assert_eq!(url, "file:///tmp/a%20b.txt");
+            let url = CString::new(url).unwrap();
+            let back = take(file_url_to_path(url.as_ptr())).unwrap();
+            assert_eq!(back, "/tmp/a b.txt");
+        }
+    }
+
+    #[test]
+    fn failures_are_null() {
+        let bad = CString::new("file:///%FF").unwrap();
+        unsafe {
+            assert!(file_url_to_path(bad.as_ptr()).is_null());
+            assert!(file_url_to_path(ptr::null()).is_null());
+            assert!(file_url_from_path(ptr::null()).is_null());
+            file_url_free(ptr::null_mut());
+        }
+    }
+}
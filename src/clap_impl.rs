@@ -0,0 +1,137 @@
+//! `clap` value parsers, behind the `clap` feature.
+use std::ffi::OsStr;
+use std::path::PathBuf;
+
+use clap::builder::{TypedValueParser, ValueParserFactory};
+use clap::error::ErrorKind;
+use clap::{Arg, Command, Error};
+
+use crate::{file_url_to_pathbuf, FileUrlBuf};
+
+fn invalid(
+    cmd: &Command,
+    arg: Option<&Arg>,
+    value: &OsStr,
+    reason: &dyn std::fmt::Display,
+) -> Error {
+    let arg = arg.map_or_else(|| String::from("..."), |a| a.to_string());
+    let msg = format!(
+        "invalid value '{}' for '{}': {}\n",
+        value.to_string_lossy(),
+        arg,
+        reason
+    );
+    Error::raw(ErrorKind::ValueValidation, msg).with_cmd(cmd)
+}
+
+/// Parses an argument as a validated `FileUrlBuf`. This is what
+/// `clap::value_parser!(FileUrlBuf)` uses.
+///
+/// # Example:
+/// ```
+/// use clap::{Arg, Command};
+/// use file_url::{FileUrlBuf, FileUrlValueParser};
+///
+/// let cmd = Command::new("app").arg(Arg::new("url").value_parser(FileUrlValueParser));
+/// let m = cmd.try_get_matches_from(["app", "file:///tmp/x"]).unwrap();
+/// assert_eq!(m.get_one::<FileUrlBuf>("url").unwrap().as_str(), "file:///tmp/x");
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FileUrlValueParser;
+
+impl TypedValueParser for FileUrlValueParser {
+    type Value = FileUrlBuf;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<FileUrlBuf, Error> {
+        let s = value
+            .to_str()
+            .ok_or_else(|| invalid(cmd, arg, value, &"File URLs must be valid UTF-8!"))?;
+        s.parse().map_err(|e| invalid(cmd, arg, value, &e))
+    }
+}
+
+impl ValueParserFactory for FileUrlBuf {
+    type Parser = FileUrlValueParser;
+
+    fn value_parser() -> FileUrlValueParser {
+        FileUrlValueParser
+    }
+}
+
+/// Parses an argument that may be either a file URL or a plain path,
+/// yielding a `PathBuf` either way. Anything starting with `file:` is
+/// decoded; everything else is taken as a path verbatim, so non-UTF-8
+/// paths still work.
+///
+/// # Example:
+/// ```
+/// use std::path::PathBuf;
+/// use clap::{Arg, Command};
+/// use file_url::PathOrFileUrlValueParser;
+///
+/// let cmd = Command::new("app").arg(Arg::new("input").value_parser(PathOrFileUrlValueParser));
+///
+/// let m = cmd.clone().try_get_matches_from(["app", "file:///tmp/a%20b"]).unwrap();
+/// assert_eq!(m.get_one::<PathBuf>("input").unwrap(), &PathBuf::from("/tmp/a b"));
+///
+/// let m = cmd.try_get_matches_from(["app", "relative/a%20b"]).unwrap();
+/// assert_eq!(m.get_one::<PathBuf>("input").unwrap(), &PathBuf::from("relative/a%20b"));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PathOrFileUrlValueParser;
+
+impl TypedValueParser for PathOrFileUrlValueParser {
+    type Value = PathBuf;
+
+    fn parse_ref(&self, cmd: &Command, arg: Option<&Arg>, value: &OsStr) -> Result<PathBuf, Error> {
+        match value.to_str() {
+            Some(s) if s.get(..5).is_some_and(|p| p.eq_ignore_ascii_case("file:")) => {
+                file_url_to_pathbuf(s).map_err(|e| invalid(cmd, arg, value, &e))
+            }
+            _ => Ok(PathBuf::from(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmd() -> Command {
+        Command::new("app").arg(Arg::new("url").value_parser(clap::value_parser!(FileUrlBuf)))
+    }
+
+    #[test]
+    fn value_parser_macro_picks_up_factory() {
+        let m = cmd()
+            .try_get_matches_from(["app", "file:///a%20b"])
+            .unwrap();
+        assert_eq!(
+            m.get_one::<FileUrlBuf>("url").unwrap().as_str(),
+            "file:///a%20b"
+        );
+    }
+
+    #[test]
+    fn error_names_value_and_reason() {
+        let err = cmd().try_get_matches_from(["app", "http://x"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+        let msg = err.to_string();
+        assert!(msg.contains("'http://x'"), "{}", msg);
+        assert!(msg.contains("Not an absolute file URL"), "{}", msg);
+    }
+
+    #[test]
+    fn bad_url_input_is_an_error() {
+        let cmd = Command::new("app").arg(Arg::new("in").value_parser(PathOrFileUrlValueParser));
+        let err = cmd
+            .try_get_matches_from(["app", "file:///%FF"])
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+    }
+}
@@ -0,0 +1,182 @@
+//! Telling apart the kinds of input a user might paste where a file
+//! URL or path is expected.
+
+/// What `classify` took an input to be.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InputKind {
+    /// A `file:` URL with an absolute path and no host, like
+    /// `file:///tmp/x`. A `localhost` host counts as none.
+    AbsoluteFileUrl,
+    /// A `file:` URL without an absolute path, like `file:a/b`, or
+    /// with a `.` or `..` host, like the `file://./a` that
+    /// `to_file_url` gives a relative path.
+    RelativeFileUrl,
+    /// A `file:` URL naming a host, like `file://server/share/x`,
+    /// which Windows maps to a UNC path. The legacy `file:////server`
+    /// and `file://///server` forms count too.
+    UncFileUrl,
+    /// A `file:` URL whose path starts with a drive letter, like
+    /// `file:///C:/x`, or `file:///c%3A/x` as VS Code writes it.
+    DriveLetterFileUrl,
+    /// A Windows path: one starting with a drive letter or `\\`, or
+    /// using only `\` as a separator.
+    WindowsPath,
+    /// Anything else without a scheme, taken as a POSIX path. That
+    /// includes relative paths and the empty string.
+    PosixPath,
+    /// A URL with a scheme other than `file:`.
+    OtherUrl,
+}
+
+/// Works out what kind of input `input` is, so that tools accepting
+/// whatever the user pasted can handle each kind deliberately. It
+/// only looks at the input's shape: nothing is decoded or validated,
+/// so an `AbsoluteFileUrl` can still fail `FileUrl::new`.
+///
+/// # Example:
+/// ```
+/// use file_url::{classify, InputKind};
+///
+/// assert_eq!(classify("file:///tmp/x"), InputKind::AbsoluteFileUrl);
+/// assert_eq!(classify("file://srv/share"), InputKind::UncFileUrl);
+/// assert_eq!(classify("file:///C:/x"), InputKind::DriveLetterFileUrl);
+/// assert_eq!(classify(r"C:\Users"), InputKind::WindowsPath);
+/// assert_eq!(classify("/tmp/x"), InputKind::PosixPath);
+/// assert_eq!(classify("https://example.com"), InputKind::OtherUrl);
+/// ```
+pub fn classify(input: &str) -> InputKind {
+    if let Some(scheme) = scheme(input) {
+        if !scheme.eq_ignore_ascii_case("file") {
+            return InputKind::OtherUrl;
+        }
+        return classify_file_url(&input[5..]);
+    }
+    if starts_with_drive(input) || input.starts_with("\\\\") {
+        return InputKind::WindowsPath;
+    }
+    if input.contains('\\') && !input.contains('/') {
+        return InputKind::WindowsPath;
+    }
+    InputKind::PosixPath
+}
+
+/// Classifies the part of a `file:` URL after the scheme.
+fn classify_file_url(rest: &str) -> InputKind {
+    if legacy_unc(rest).is_some() {
+        return InputKind::UncFileUrl;
+    }
+    let path = match rest.strip_prefix("//") {
+        Some(auth_and_path) => {
+            let end = auth_and_path.find('/').unwrap_or(auth_and_path.len());
+            match &auth_and_path[..end] {
+                "" => &auth_and_path[end..],
+                "." | ".." => return InputKind::RelativeFileUrl,
+                host if host.eq_ignore_ascii_case("localhost") => &auth_and_path[end..],
+                _ => return InputKind::UncFileUrl,
+            }
+        }
+        None if rest.starts_with('/') => rest,
+        None => return InputKind::RelativeFileUrl,
+    };
+    match path.strip_prefix('/') {
+        Some(path) if starts_with_drive(path) => InputKind::DriveLetterFileUrl,
+        Some(path) if is_encoded_drive(path.split('/').next().unwrap_or_default()) => {
+            InputKind::DriveLetterFileUrl
+        }
+        Some(_) => InputKind::AbsoluteFileUrl,
+        // `file://` or `file://localhost`, the root.
+        None => InputKind::AbsoluteFileUrl,
+    }
+}
+
+/// Splits the part of a legacy UNC file URL after the scheme into its
+/// host and path. Old Firefox and some Java code write
+/// `\\server\share\x` as `file:////server/share/x`, or with five
+/// slashes, leaving the authority empty and the host in the path. A
+/// drive in the host's place, as in `file:////C:/x`, isn't a host.
+pub(crate) fn legacy_unc(rest: &str) -> Option<(&str, &str)> {
+    let after = rest.strip_prefix("////")?;
+    let after = after.strip_prefix('/').unwrap_or(after);
+    let end = after.find('/').unwrap_or(after.len());
+    let host = &after[..end];
+    if host.is_empty() || starts_with_drive(after) {
+        return None;
+    }
+    Some((host, &after[end..]))
+}
+
+/// The URL scheme `input` starts with, if any. A single letter
+/// followed by `:` is a drive letter instead.
+pub(crate) fn scheme(input: &str) -> Option<&str> {
+    let end = input.find(':')?;
+    let scheme = &input[..end];
+    let mut bytes = scheme.bytes();
+    let first = bytes.next()?;
+    let valid = first.is_ascii_alphabetic()
+        && bytes.all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'-' || b == b'.');
+    if valid && scheme.len() > 1 {
+        Some(scheme)
+    } else {
+        None
+    }
+}
+
+/// Whether `s` starts with a drive letter, like `C:` or `c:\x`.
+fn starts_with_drive(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() >= 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes.len() == 2 || bytes[2] == b'/' || bytes[2] == b'\\')
+}
+
+/// Whether the URL path segment `segment` is a drive with its colon
+/// percent-encoded, like the `c%3A` VS Code and some LSP clients
+/// write. It decodes to a drive like any other.
+pub(crate) fn is_encoded_drive(segment: &str) -> bool {
+    let bytes = segment.as_bytes();
+    bytes.len() == 4 && bytes[0].is_ascii_alphabetic() && bytes[1..].eq_ignore_ascii_case(b"%3a")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_inputs_apart() {
+        let cases = [
+            ("file:///", InputKind::AbsoluteFileUrl),
+            ("FILE://LOCALHOST/a", InputKind::AbsoluteFileUrl),
+            ("file:/a/b", InputKind::AbsoluteFileUrl),
+            ("file://", InputKind::AbsoluteFileUrl),
+            ("file:a/b", InputKind::RelativeFileUrl),
+            ("file://./rel/x", InputKind::RelativeFileUrl),
+            ("file://srv/share/x", InputKind::UncFileUrl),
+            ("file://[::1]/x", InputKind::UncFileUrl),
+            ("file:////srv/share/x", InputKind::UncFileUrl),
+            ("file://///srv/share", InputKind::UncFileUrl),
+            ("file:////C:/x", InputKind::AbsoluteFileUrl),
+            ("file://////x", InputKind::AbsoluteFileUrl),
+            ("file:///c:", InputKind::DriveLetterFileUrl),
+            ("file://localhost/D:/x", InputKind::DriveLetterFileUrl),
+            ("file:///C:x", InputKind::AbsoluteFileUrl),
+            ("file:///c%3A/Users", InputKind::DriveLetterFileUrl),
+            ("file:///c%3a", InputKind::DriveLetterFileUrl),
+            ("file:///c%3Ax", InputKind::AbsoluteFileUrl),
+            (r"C:\x", InputKind::WindowsPath),
+            ("d:/x", InputKind::WindowsPath),
+            (r"\\srv\share", InputKind::WindowsPath),
+            (r"rel\x", InputKind::WindowsPath),
+            ("/tmp/a b", InputKind::PosixPath),
+            ("rel/x", InputKind::PosixPath),
+            ("10:30.txt", InputKind::PosixPath),
+            ("", InputKind::PosixPath),
+            ("https://example.com", InputKind::OtherUrl),
+            ("mailto:a@b", InputKind::OtherUrl),
+            ("git+ssh://host/repo", InputKind::OtherUrl),
+        ];
+        for (input, kind) in cases.iter() {
+            assert_eq!(classify(input), *kind, "{}", input);
+        }
+    }
+}
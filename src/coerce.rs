@@ -0,0 +1,131 @@
+//! Turning input that may be either a path or a file URL into a path,
+//! as a CLI taking "a file" from its user wants.
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::string::FromUtf8Error;
+
+use crate::percent::decode;
+use crate::platform::split_authority;
+use crate::{classify, file_url_to_pathbuf, InputKind};
+
+/// Error for input `coerce_to_pathbuf` can't make a path of.
+#[derive(Debug)]
+pub enum CoerceError {
+    /// The input is a file URL that doesn't decode to UTF-8.
+    Decode(FromUtf8Error),
+    /// The input is a URL with another scheme, which is kept here.
+    NotFileScheme(String),
+    /// The input is a file URL naming another machine, whose host is
+    /// kept here. Only Windows can reach one, as a UNC path.
+    RemoteHost(String),
+}
+
+impl fmt::Display for CoerceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CoerceError::Decode(_) => write!(f, "File URL does not decode to UTF-8!"),
+            CoerceError::NotFileScheme(scheme) => {
+                write!(f, "Expected a path or file URL, got {}:", scheme)
+            }
+            CoerceError::RemoteHost(host) => write!(f, "File URL names the remote host {}!", host),
+        }
+    }
+}
+
+impl Error for CoerceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CoerceError::Decode(e) => Some(e),
+            CoerceError::NotFileScheme(_) | CoerceError::RemoteHost(_) => None,
+        }
+    }
+}
+
+/// Converts `input` to a path whether it's already a path or a file
+/// URL, deciding which with `classify`. Paths are taken as they are,
+/// with nothing decoded. Relative file URLs like `file:a/b` and
+/// `file://./a` give relative paths. A `localhost` host is dropped;
+/// any other is an error except on Windows, where it names a UNC
+/// share.
+///
+/// # Example:
+/// ```
+/// use std::path::Path;
+/// use file_url::coerce_to_pathbuf;
+///
+/// assert_eq!(coerce_to_pathbuf("/srv/a b.txt").unwrap(), Path::new("/srv/a b.txt"));
+/// assert_eq!(coerce_to_pathbuf("file:///srv/a%20b.txt").unwrap(), Path::new("/srv/a b.txt"));
+/// assert_eq!(coerce_to_pathbuf("file:docs/a%20b.md").unwrap(), Path::new("docs/a b.md"));
+/// assert!(coerce_to_pathbuf("https://example.com/a").is_err());
+/// ```
+pub fn coerce_to_pathbuf(input: &str) -> Result<PathBuf, CoerceError> {
+    match classify(input) {
+        InputKind::PosixPath | InputKind::WindowsPath => Ok(PathBuf::from(input)),
+        InputKind::AbsoluteFileUrl | InputKind::UncFileUrl | InputKind::DriveLetterFileUrl => {
+            let (host, path) = split_authority(input);
+            if !host.is_empty() {
+                if !cfg!(windows) {
+                    return Err(CoerceError::RemoteHost(host.to_string()));
+                }
+                return file_url_to_pathbuf(input).map_err(CoerceError::Decode);
+            }
+            // Rebuilt without the host, so `file://localhost/x` is `/x`.
+            let local = format!("file://{}", if path.is_empty() { "/" } else { path });
+            file_url_to_pathbuf(&local).map_err(CoerceError::Decode)
+        }
+        InputKind::RelativeFileUrl => {
+            let rest = &input[5..];
+            let rest = rest.strip_prefix("//").unwrap_or(rest);
+            let mut path = PathBuf::new();
+            for segment in rest.split('/') {
+                path.push(&*decode(segment).map_err(CoerceError::Decode)?);
+            }
+            Ok(path)
+        }
+        InputKind::OtherUrl => {
+            let scheme = &input[..input.find(':').unwrap_or(input.len())];
+            Err(CoerceError::NotFileScheme(scheme.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn paths_and_urls_alike() {
+        let cases = [
+            ("rel/x", "rel/x"),
+            ("a%20b", "a%20b"),
+            ("file:///a/b%20c", "/a/b c"),
+            ("file://./rel/x", "./rel/x"),
+            ("file:../up", "../up"),
+            ("file://localhost/etc/hosts", "/etc/hosts"),
+            ("FILE://LOCALHOST/a%20b", "/a b"),
+            ("file:/a", "/a"),
+        ];
+        for (input, path) in cases.iter() {
+            assert_eq!(
+                coerce_to_pathbuf(input).unwrap(),
+                Path::new(path),
+                "{}",
+                input
+            );
+        }
+        assert!(matches!(
+            coerce_to_pathbuf("file:///%FF"),
+            Err(CoerceError::Decode(_))
+        ));
+        let err = coerce_to_pathbuf("ftp://host/x").unwrap_err();
+        assert_eq!(err.to_string(), "Expected a path or file URL, got ftp:");
+        let remote = coerce_to_pathbuf("file://server/share/x");
+        if cfg!(windows) {
+            assert!(remote.is_ok());
+        } else {
+            assert!(matches!(remote, Err(CoerceError::RemoteHost(host)) if host == "server"));
+        }
+    }
+}
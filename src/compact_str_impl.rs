@@ -0,0 +1,71 @@
+//! `CompactString` output for file URLs, behind the `compact_str`
+//! feature. Most file URLs are short enough to be stored inline, which
+//! adds up for tools that keep millions of them.
+use std::path::Path;
+
+use compact_str::CompactString;
+
+use crate::{push_file_url, UTFDecodeError, UrlBuf};
+
+impl UrlBuf for CompactString {
+    fn reserve_exact(&mut self, additional: usize) {
+        // CompactString has no exact variant.
+        self.reserve(additional)
+    }
+
+    fn push(&mut self, c: char) {
+        CompactString::push(self, c)
+    }
+
+    fn push_str(&mut self, s: &str) {
+        CompactString::push_str(self, s)
+    }
+}
+
+/// Method for converting a path to a file URL as a `CompactString`.
+pub trait PathCompactFileUrlExt {
+    /// Like `PathFileUrlExt::to_file_url`, but URLs up to 24 bytes long
+    /// don't allocate.
+    ///
+    /// # Example:
+    /// ```
+    /// use std::path::Path;
+    /// use file_url::PathCompactFileUrlExt;
+    ///
+    /// let url = Path::new("/tmp/a b").to_compact_file_url().unwrap();
+    /// assert_eq!(url, "file:///tmp/a%20b");
+    /// assert!(!url.is_heap_allocated());
+    /// ```
+    fn to_compact_file_url(&self) -> Result<CompactString, UTFDecodeError>;
+}
+
+impl PathCompactFileUrlExt for Path {
+    fn to_compact_file_url(&self) -> Result<CompactString, UTFDecodeError> {
+        let mut url = CompactString::default();
+        push_file_url(self, &mut url)?;
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PathFileUrlExt;
+
+    #[test]
+    fn matches_to_file_url() {
+        for p in [
+            "/",
+            "/a b/c",
+            "/gi>/some & what.whtvr/and/a/much/longer/tail",
+        ]
+        .iter()
+        {
+            let path = Path::new(p);
+            assert_eq!(
+                path.to_compact_file_url().unwrap().as_str(),
+                path.to_file_url().unwrap()
+            );
+        }
+    }
+}
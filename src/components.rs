@@ -0,0 +1,179 @@
+//! Structural view of a file URL, analogous to `Path::components`.
+use std::borrow::Cow;
+
+use crate::percent::decode;
+
+use crate::FileUrl;
+
+/// A single piece of a file URL, as yielded by `FileUrl::components`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UrlComponent<'a> {
+    /// The `file:` scheme.
+    Scheme,
+    /// A non-empty authority, e.g. `server` in `file://server/share`.
+    Host(&'a str),
+    /// A Windows drive letter, e.g. `C` in `file:///C:/Windows`.
+    Drive(char),
+    /// The root directory.
+    RootDir,
+    /// A path segment with percent-encoding decoded.
+    Segment(Cow<'a, str>),
+}
+
+/// Iterator over the components of a `FileUrl`. Empty segments, as
+/// from a trailing or doubled slash, are skipped, while dot segments
+/// are yielded as-is.
+#[derive(Clone, Debug)]
+pub struct UrlComponents<'a> {
+    scheme: bool,
+    host: Option<&'a str>,
+    drive: Option<char>,
+    root: bool,
+    rest: &'a str,
+}
+
+impl<'a> UrlComponents<'a> {
+    pub(crate) fn new(url: &'a FileUrl) -> UrlComponents<'a> {
+        // Validation guarantees the scheme is there.
+        let mut rest = &url.as_str()[5..];
+
+        let mut host = None;
+        if let Some(auth_and_path) = rest.strip_prefix("//") {
+            let end = auth_and_path.find('/').unwrap_or(auth_and_path.len());
+            if end > 0 {
+                host = Some(&auth_and_path[..end]);
+            }
+            rest = &auth_and_path[end..];
+        }
+
+        let mut root = rest.starts_with('/');
+        if root {
+            rest = &rest[1..];
+        }
+
+        let mut drive = None;
+        let first_len = rest.find('/').unwrap_or(rest.len());
+        let mut chars = rest[..first_len].chars();
+        if let (Some(letter), Some(':'), None) = (chars.next(), chars.next(), chars.next()) {
+            if letter.is_ascii_alphabetic() {
+                drive = Some(letter);
+                rest = &rest[first_len..];
+                root = rest.starts_with('/');
+            }
+        }
+
+        UrlComponents {
+            scheme: true,
+            host,
+            drive,
+            root,
+            rest,
+        }
+    }
+}
+
+impl<'a> Iterator for UrlComponents<'a> {
+    type Item = UrlComponent<'a>;
+
+    fn next(&mut self) -> Option<UrlComponent<'a>> {
+        if self.scheme {
+            self.scheme = false;
+            return Some(UrlComponent::Scheme);
+        }
+        if let Some(host) = self.host.take() {
+            return Some(UrlComponent::Host(host));
+        }
+        if let Some(drive) = self.drive.take() {
+            return Some(UrlComponent::Drive(drive));
+        }
+        if self.root {
+            self.root = false;
+            return Some(UrlComponent::RootDir);
+        }
+
+        let rest = self.rest.trim_start_matches('/');
+        if rest.is_empty() {
+            self.rest = rest;
+            return None;
+        }
+        let end = rest.find('/').unwrap_or(rest.len());
+        let (seg, remaining) = rest.split_at(end);
+        self.rest = remaining;
+        // Validation already checked every segment decodes.
+        Some(UrlComponent::Segment(decode(seg).unwrap()))
+    }
+}
+
+impl FileUrl {
+    /// Iterates over the structural pieces of the URL.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::{FileUrl, UrlComponent};
+    ///
+    /// let url = FileUrl::new("file:///C:/My%20Files/a.txt").unwrap();
+    /// let parts: Vec<UrlComponent> = url.components().collect();
+    /// assert_eq!(parts, vec![
+    ///     UrlComponent::Scheme,
+    ///     UrlComponent::Drive('C'),
+    ///     UrlComponent::RootDir,
+    ///     UrlComponent::Segment("My Files".into()),
+    ///     UrlComponent::Segment("a.txt".into()),
+    /// ]);
+    /// ```
+    pub fn components(&self) -> UrlComponents<'_> {
+        UrlComponents::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use UrlComponent::*;
+
+    fn components(s: &str) -> Vec<UrlComponent<'_>> {
+        FileUrl::new(s).unwrap().components().collect()
+    }
+
+    #[test]
+    fn unix_style() {
+        assert_eq!(
+            components("file:///a//b/"),
+            vec![Scheme, RootDir, Segment("a".into()), Segment("b".into())]
+        );
+        assert_eq!(components("file:///"), vec![Scheme, RootDir]);
+    }
+
+    #[test]
+    fn host_and_drive() {
+        assert_eq!(
+            components("file://server/share/x"),
+            vec![
+                Scheme,
+                Host("server"),
+                RootDir,
+                Segment("share".into()),
+                Segment("x".into())
+            ]
+        );
+        assert_eq!(components("file:///d:"), vec![Scheme, Drive('d')]);
+        assert_eq!(
+            components("file:///CC:/x"),
+            vec![Scheme, RootDir, Segment("CC:".into()), Segment("x".into())]
+        );
+    }
+
+    #[test]
+    fn dot_segments_are_kept() {
+        assert_eq!(
+            components("file:///a/../%2E"),
+            vec![
+                Scheme,
+                RootDir,
+                Segment("a".into()),
+                Segment("..".into()),
+                Segment(".".into())
+            ]
+        );
+    }
+}
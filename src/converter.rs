@@ -0,0 +1,254 @@
+//! A reusable converter that keeps its output buffers between calls.
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::string::FromUtf8Error;
+
+use crate::decode_options::decode_into_with;
+use crate::{
+    decode_into, push_file_url, DecodeError, DecodeOptions, OutputProfile, UTFDecodeError,
+};
+
+/// Converts paths to file URLs and back into buffers it owns, so a
+/// hot loop stops allocating once the buffers have grown to fit. The
+/// results borrow the converter and are overwritten by the next call.
+///
+/// `encode_absolute` resolves relative paths against the current
+/// directory, which it looks up once and then caches; call
+/// `invalidate_cwd` after changing directory.
+///
+/// A converter from `with_options` writes every URL in an
+/// `OutputProfile` and decodes with `DecodeOptions`; the others use
+/// the defaults, and so match `to_file_url` and `file_url_to_pathbuf`.
+///
+/// # Example:
+/// ```
+/// use std::path::Path;
+/// use file_url::Converter;
+///
+/// let mut converter = Converter::new();
+/// for name in ["a b.txt", "c.txt"].iter() {
+///     let url = converter.encode(&Path::new("/srv").join(name)).unwrap();
+///     assert!(url.starts_with("file:///srv/"));
+/// }
+/// assert_eq!(
+///     converter.decode("file:///srv/a%20b.txt").unwrap(),
+///     Path::new("/srv/a b.txt")
+/// );
+/// ```
+#[derive(Debug, Default)]
+pub struct Converter {
+    url: String,
+    path: PathBuf,
+    cwd: Option<PathBuf>,
+    profile: OutputProfile,
+    decode_options: DecodeOptions,
+}
+
+impl Converter {
+    /// A converter with empty buffers.
+    pub fn new() -> Converter {
+        Converter::default()
+    }
+
+    /// A converter whose buffers can hold `capacity` bytes each
+    /// before they need to grow.
+    pub fn with_capacity(capacity: usize) -> Converter {
+        Converter {
+            url: String::with_capacity(capacity),
+            path: PathBuf::with_capacity(capacity),
+            ..Converter::default()
+        }
+    }
+
+    /// A converter with empty buffers that encodes in `profile` and
+    /// decodes with `decode_options`.
+    ///
+    /// # Example:
+    /// ```
+    /// use std::path::Path;
+    /// use file_url::{Converter, DecodeError, DecodeOptions, OutputProfile};
+    ///
+    /// let profile: OutputProfile = "localhost,lower-hex".parse().unwrap();
+    /// let decode_options = DecodeOptions {
+    ///     reject_dot_segments: true,
+    ///     ..DecodeOptions::default()
+    /// };
+    /// let mut converter = Converter::with_options(profile, decode_options);
+    /// assert_eq!(
+    ///     converter.encode(Path::new("/srv/Zoë")).unwrap(),
+    ///     "file://localhost/srv/Zo%c3%ab"
+    /// );
+    /// assert!(matches!(
+    ///     converter.decode("file:///srv/%2E%2E/etc"),
+    ///     Err(DecodeError::DotSegment { index: 1 })
+    /// ));
+    /// ```
+    pub fn with_options(profile: OutputProfile, decode_options: DecodeOptions) -> Converter {
+        Converter {
+            profile,
+            decode_options,
+            ..Converter::default()
+        }
+    }
+
+    /// Converts `path` to a file URL, like `PathFileUrlExt::to_file_url`
+    /// or, with a profile, `OutputProfile::to_file_url`.
+    pub fn encode(&mut self, path: &Path) -> Result<&str, UTFDecodeError> {
+        self.url.clear();
+        push_file_url(path, &mut self.url)?;
+        self.apply_profile();
+        Ok(&self.url)
+    }
+
+    /// Converts `path` to a file URL, first resolving it against the
+    /// current directory if it's relative. Fails if the current
+    /// directory can't be read, or as `io::ErrorKind::InvalidInput`
+    /// if the path isn't UTF-8.
+    ///
+    /// # Example:
+    /// ```
+    /// use std::env;
+    /// use std::path::Path;
+    /// use file_url::{Converter, PathFileUrlExt};
+    ///
+    /// let mut converter = Converter::new();
+    /// let expected = env::current_dir().unwrap().join("a.txt").to_file_url().unwrap();
+    /// assert_eq!(converter.encode_absolute(Path::new("a.txt")).unwrap(), expected);
+    /// ```
+    pub fn encode_absolute(&mut self, path: &Path) -> io::Result<&str> {
+        self.url.clear();
+        let result = if path.is_absolute() {
+            push_file_url(path, &mut self.url)
+        } else {
+            let cwd = match &self.cwd {
+                Some(cwd) => cwd,
+                None => self.cwd.insert(env::current_dir()?),
+            };
+            // The decode buffer is free to hold the joined path.
+            self.path.clone_from(cwd);
+            self.path.push(path);
+            push_file_url(&self.path, &mut self.url)
+        };
+        result.map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        self.apply_profile();
+        Ok(&self.url)
+    }
+
+    fn apply_profile(&mut self) {
+        if self.profile != OutputProfile::DEFAULT {
+            let url = std::mem::take(&mut self.url);
+            self.url = self.profile.apply(url);
+        }
+    }
+
+    /// Forgets the cached current directory, so the next relative
+    /// path passed to `encode_absolute` looks it up again.
+    pub fn invalidate_cwd(&mut self) {
+        self.cwd = None;
+    }
+
+    /// Converts `file_url` to a path, like `file_url_to_pathbuf_with`
+    /// with the converter's decode options.
+    pub fn decode(&mut self, file_url: &str) -> Result<&Path, DecodeError> {
+        decode_into_with(file_url, self.decode_options, &mut self.path)?;
+        Ok(&self.path)
+    }
+
+    /// Like `decode`, ignoring the decode options, for the bulk
+    /// helpers, whose converters never have any.
+    pub(crate) fn decode_plain(&mut self, file_url: &str) -> Result<&Path, FromUtf8Error> {
+        decode_into(file_url, &mut self.path)?;
+        Ok(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{file_url_to_pathbuf, PathFileUrlExt};
+
+    #[test]
+    fn matches_one_off_conversions() {
+        let mut converter = Converter::with_capacity(64);
+        let paths = [
+            "/a/b c",
+            "/gi>/some & what.whtvr",
+            "rel/ative",
+            "/",
+            "/x/y/",
+        ];
+        for p in paths.iter() {
+            let path = Path::new(p);
+            let url = path.to_file_url().unwrap();
+            assert_eq!(converter.encode(path).unwrap(), url);
+            assert_eq!(
+                converter.decode(&url).unwrap(),
+                file_url_to_pathbuf(&url).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn reuses_buffers() {
+        let mut converter = Converter::new();
+        converter.encode(Path::new("/a/long/enough/path")).unwrap();
+        let capacity = converter.url.capacity();
+        converter.encode(Path::new("/short")).unwrap();
+        assert_eq!(converter.url.capacity(), capacity);
+        assert!(converter.decode("file:///%FF").is_err());
+        assert_eq!(converter.decode("file:///ok").unwrap(), Path::new("/ok"));
+    }
+
+    #[test]
+    fn caches_the_current_directory() {
+        let mut converter = Converter::new();
+        converter.cwd = Some(PathBuf::from("/cached"));
+        assert_eq!(
+            converter.encode_absolute(Path::new("a b")).unwrap(),
+            "file:///cached/a%20b"
+        );
+        assert_eq!(
+            converter.encode_absolute(Path::new("/x")).unwrap(),
+            "file:///x"
+        );
+        converter.invalidate_cwd();
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            converter.encode_absolute(Path::new("a")).unwrap(),
+            cwd.join("a").to_file_url().unwrap()
+        );
+        assert_eq!(converter.cwd, Some(cwd));
+    }
+
+    #[test]
+    fn applies_its_options() {
+        let profile = OutputProfile {
+            trim_trailing_slash: true,
+            ..OutputProfile::DEFAULT
+        };
+        let decode_options = DecodeOptions {
+            slash_runs: crate::SlashRuns::Collapse,
+            ..DecodeOptions::default()
+        };
+        let mut converter = Converter::with_options(profile, decode_options);
+        converter.cwd = Some(PathBuf::from("/cached"));
+        assert_eq!(
+            converter.encode(Path::new("/a b/")).unwrap(),
+            "file:///a%20b"
+        );
+        assert_eq!(
+            converter.encode_absolute(Path::new("c/")).unwrap(),
+            "file:///cached/c"
+        );
+        assert_eq!(converter.decode("file:///a//b").unwrap(), Path::new("/a/b"));
+        assert!(matches!(
+            converter.decode("file:///%FF"),
+            Err(DecodeError::Utf8(_))
+        ));
+        assert_eq!(
+            Converter::new().decode("file:///a//b").unwrap(),
+            file_url_to_pathbuf("file:///a//b").unwrap()
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Canonicalizing the file references found in source maps and in
+//! DWARF and PDB debug info.
+use crate::relative::UrlParts;
+use crate::{
+    classify, encode_file_component, FileUrl, FileUrlBuf, FileUrlParseError, InputKind, Platform,
+};
+
+/// Turns a source file reference from a source map's `sources` or a
+/// debug-info record into one canonical file URL, so references to the
+/// same file compare equal. The reference may be a file URL or a bare
+/// path, and either may use `\` and `/` interchangeably, as toolchains
+/// that ran on Windows tend to. Relative references are resolved
+/// against `comp_dir`, the compilation directory, which is taken as a
+/// directory whether or not it ends in `/`. Dot segments are resolved,
+/// the bogus `file://C:/x` is read as `file:///C:/x`, and the result is
+/// in the form `FileUrl::as_normalized_str` gives.
+///
+/// This fails for references with a scheme other than `file:`, like
+/// `webpack:///src/a.ts`, which only the bundler that wrote them can
+/// map to a file.
+///
+/// # Example:
+/// ```
+/// use file_url::{normalize_debug_url, FileUrl};
+///
+/// let comp_dir = FileUrl::new("file:///C:/build/obj").unwrap();
+/// let cases = [
+///     (r"..\src\main.c", "file:///C:/build/src/main.c"),
+///     (r"C:\build\src/main.c", "file:///C:/build/src/main.c"),
+///     (r"file:///C:\build\obj\..\src\main.c", "file:///C:/build/src/main.c"),
+///     ("file://c:/build/src/main.c", "file:///c:/build/src/main.c"),
+///     ("gen/a%20b.c", "file:///C:/build/obj/gen/a%2520b.c"),
+/// ];
+/// for (reference, url) in cases.iter() {
+///     assert_eq!(normalize_debug_url(reference, comp_dir).unwrap().as_str(), *url);
+/// }
+/// assert!(normalize_debug_url("webpack:///src/a.ts", comp_dir).is_err());
+/// ```
+pub fn normalize_debug_url(
+    reference: &str,
+    comp_dir: &FileUrl,
+) -> Result<FileUrlBuf, FileUrlParseError> {
+    let absolute = match classify(reference) {
+        InputKind::OtherUrl => {
+            return Err(FileUrlParseError::new(
+                "Reference is not a file URL or path!",
+            ))
+        }
+        InputKind::AbsoluteFileUrl | InputKind::UncFileUrl | InputKind::DriveLetterFileUrl => {
+            let mut url = reference.replace('\\', "/");
+            // `file://C:/x` puts the drive where the host goes.
+            if url[5..].starts_with("//") && is_drive_prefix(&url[7..]) {
+                url.insert(7, '/');
+            }
+            url
+        }
+        InputKind::RelativeFileUrl => {
+            let rest = &reference[5..];
+            join(
+                comp_dir,
+                &rest.strip_prefix("//").unwrap_or(rest).replace('\\', "/"),
+            )
+        }
+        InputKind::WindowsPath | InputKind::PosixPath => {
+            let path = reference.replace('\\', "/");
+            if path.starts_with('/') && !path.starts_with("//") {
+                Platform::Posix.path_to_file_url(&path)
+            } else if path.starts_with("//") || is_drive_prefix(&path) {
+                Platform::Windows.path_to_file_url(&path)
+            } else {
+                let encoded: Vec<_> = path.split('/').map(encode_file_component).collect();
+                join(comp_dir, &encoded.join("/"))
+            }
+        }
+    };
+
+    // Rebuilding from the parts resolves the dot segments.
+    let parts = UrlParts::parse(&absolute)
+        .ok_or_else(|| FileUrlParseError::new("Reference is not an absolute file URL!"))?;
+    let rebuilt = format!("file://{}/{}", parts.host, parts.segments.join("/"));
+    let url = FileUrlBuf::new(rebuilt)?;
+    let normal = url.as_normalized_str().into_owned();
+    Ok(FileUrlBuf::from_string_unchecked(normal))
+}
+
+/// The URL of the already-encoded relative reference `relative`, taken
+/// from the directory `dir`.
+fn join(dir: &FileUrl, relative: &str) -> String {
+    let mut url = dir.as_str().trim_end_matches('/').to_string();
+    url.push('/');
+    url.push_str(relative);
+    url
+}
+
+/// Whether `s` starts with a drive, like `C:` or `c:/x`.
+fn is_drive_prefix(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() >= 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes.len() == 2 || bytes[2] == b'/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixed_references_agree() {
+        let comp_dir = FileUrl::new("file:///home/me/proj/").unwrap();
+        let references = [
+            "src/lib.rs",
+            "./src/./lib.rs",
+            r"src\lib.rs",
+            "target/../src/lib.rs",
+            "/home/me/proj/src/lib.rs",
+            "file:///home/me/proj/src/lib.rs",
+            "file:src/lib.rs",
+            "file://./src/lib.rs",
+            "FILE://localhost/home/me/proj/src/lib.rs",
+            "file:///home/me/proj/src/%6cib.rs",
+        ];
+        for reference in references.iter() {
+            let url = normalize_debug_url(reference, comp_dir).unwrap();
+            assert_eq!(
+                url.as_str(),
+                "file:///home/me/proj/src/lib.rs",
+                "{}",
+                reference
+            );
+        }
+        let unc = normalize_debug_url(r"\\srv\share\a.c", comp_dir).unwrap();
+        assert_eq!(unc.as_str(), "file://srv/share/a.c");
+        let up = normalize_debug_url("../../../../x.c", comp_dir).unwrap();
+        assert_eq!(up.as_str(), "file:///x.c");
+        assert!(normalize_debug_url("https://example.com/a.js", comp_dir).is_err());
+    }
+}
@@ -0,0 +1,516 @@
+//! `DecodeOptions`, and the decoders that take them.
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::string::FromUtf8Error;
+
+use crate::long_path::extended_length;
+use crate::platform::split_authority;
+use crate::{classify, decode_into, percent, InputKind, Platform};
+
+/// Options for `file_url_to_pathbuf_with` and
+/// `Platform::file_url_to_path_with`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DecodeOptions {
+    /// On Windows, turn an absolute path too long for `MAX_PATH` into
+    /// its `\\?\` form, so Win32 APIs accept it. Dot segments are
+    /// resolved and `/` becomes `\` first, since neither is allowed
+    /// in that form. Shorter paths and relative ones are unchanged.
+    pub extended_length: bool,
+    /// Fail with `DecodeError::DotSegment` if any path segment is `.`
+    /// or `..`, for servers that must never let a client's URL climb
+    /// out of a mount point. Segments are checked after decoding and
+    /// split on both `/` and `\`, so `%2E%2E` and `..%2F` are caught
+    /// too, on every platform.
+    pub reject_dot_segments: bool,
+    /// Fail with `DecodeError::Relative` if the URL is relative, like
+    /// `file:a/b` or `file://./a`, or decodes to a path that isn't
+    /// absolute for the platform, like `file:///a` on Windows, which
+    /// has neither a drive nor a host. For deployments where every URL
+    /// accepted must name a definite location.
+    pub require_absolute: bool,
+    /// What to do with runs of slashes in the path, as in
+    /// `file:///a////b`.
+    pub slash_runs: SlashRuns,
+    /// Fail with `DecodeError::DevicePath` if Windows would read the
+    /// URL as a path in the device namespace, like `\\.\COM1` or
+    /// `\\.\PhysicalDrive0`, which opens hardware rather than a
+    /// file. That covers a `.` host, as in `file://./COM1`, and
+    /// encoded backslashes that decode to such a path. Checked on
+    /// every platform, since the URL may be handed to Windows later.
+    /// `\\?\` paths to a drive or UNC share are still files and
+    /// are allowed.
+    pub reject_device_paths: bool,
+}
+
+/// How `DecodeOptions` treats a run of slashes in a URL's path, the
+/// empty segments between them. Implementations disagree here, so
+/// a URL like `file:///a////b` is best given an explicit meaning.
+/// Only the path counts: the `//` before an authority isn't a run,
+/// and legacy UNC URLs like `file:////server/share` are read as
+/// naming a host first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SlashRuns {
+    /// Keep the empty segments, as `file_url_to_pathbuf` does. On
+    /// Unix the path keeps its doubled slashes, which the OS treats
+    /// as one.
+    Preserve,
+    /// Merge each run into one slash before decoding, so
+    /// `file:///a////b` becomes `/a/b` on every platform.
+    Collapse,
+    /// Fail with `DecodeError::EmptySegment`.
+    Reject,
+}
+
+impl Default for SlashRuns {
+    /// Preserve, like `file_url_to_pathbuf`.
+    fn default() -> SlashRuns {
+        SlashRuns::Preserve
+    }
+}
+
+/// Error for file URLs that `DecodeOptions` rule out.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// A segment didn't decode to UTF-8.
+    Utf8(FromUtf8Error),
+    /// The path segment at `index`, counting from zero, is or contains
+    /// a dot segment, and `reject_dot_segments` is set.
+    DotSegment {
+        /// Which segment.
+        index: usize,
+    },
+    /// The URL decodes to a relative path, and `require_absolute` is
+    /// set.
+    Relative,
+    /// The path segment at `index`, counting from zero, is empty,
+    /// between two slashes, and `slash_runs` is `SlashRuns::Reject`.
+    EmptySegment {
+        /// Which segment.
+        index: usize,
+    },
+    /// The URL names a Windows device rather than a file, and
+    /// `reject_device_paths` is set.
+    DevicePath,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Utf8(_) => write!(f, "File URL does not decode to UTF-8!"),
+            DecodeError::DotSegment { index } => {
+                write!(f, "File URL has a dot segment at segment {}!", index)
+            }
+            DecodeError::Relative => write!(f, "File URL does not decode to an absolute path!"),
+            DecodeError::EmptySegment { index } => {
+                write!(f, "File URL has an empty segment at segment {}!", index)
+            }
+            DecodeError::DevicePath => write!(f, "File URL names a Windows device!"),
+        }
+    }
+}
+
+impl Error for DecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DecodeError::Utf8(e) => Some(e),
+            DecodeError::DotSegment { .. }
+            | DecodeError::Relative
+            | DecodeError::EmptySegment { .. }
+            | DecodeError::DevicePath => None,
+        }
+    }
+}
+
+impl From<FromUtf8Error> for DecodeError {
+    fn from(err: FromUtf8Error) -> DecodeError {
+        DecodeError::Utf8(err)
+    }
+}
+
+/// Like `file_url_to_pathbuf`, with `options` applied.
+///
+/// # Example:
+/// ```
+/// use file_url::{file_url_to_pathbuf_with, DecodeError, DecodeOptions, SlashRuns};
+///
+/// let options = DecodeOptions {
+///     reject_dot_segments: true,
+///     ..DecodeOptions::default()
+/// };
+/// assert!(file_url_to_pathbuf_with("file:///srv/a.txt", options).is_ok());
+/// let err = file_url_to_pathbuf_with("file:///srv/%2E%2E/etc", options).unwrap_err();
+/// assert!(matches!(err, DecodeError::DotSegment { index: 1 }));
+///
+/// let options = DecodeOptions {
+///     slash_runs: SlashRuns::Collapse,
+///     ..DecodeOptions::default()
+/// };
+/// let path = file_url_to_pathbuf_with("file:///srv//a///b.txt", options).unwrap();
+/// assert_eq!(path.to_str(), Some("/srv/a/b.txt"));
+/// ```
+pub fn file_url_to_pathbuf_with(
+    file_url: &str,
+    options: DecodeOptions,
+) -> Result<PathBuf, DecodeError> {
+    let mut path = PathBuf::new();
+    decode_into_with(file_url, options, &mut path)?;
+    Ok(path)
+}
+
+/// Like `file_url_to_pathbuf_with`, but replaces the contents of
+/// `path`, reusing its buffer.
+pub(crate) fn decode_into_with(
+    file_url: &str,
+    options: DecodeOptions,
+    path: &mut PathBuf,
+) -> Result<(), DecodeError> {
+    let file_url = &*check(file_url, options)?;
+    decode_into(file_url, path)?;
+    if options.require_absolute && !path.is_absolute() {
+        return Err(DecodeError::Relative);
+    }
+    if !cfg!(windows) || !options.extended_length {
+        return Ok(());
+    }
+    if let Some(long) = path.to_str().and_then(extended_length) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            file_url,
+            path = long.as_str(),
+            "used an extended-length path"
+        );
+        *path = PathBuf::from(long);
+    }
+    Ok(())
+}
+
+impl Platform {
+    /// Like `file_url_to_path`, with `options` applied. The
+    /// extended-length option only takes effect for
+    /// `Platform::Windows`.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::{DecodeOptions, Platform};
+    ///
+    /// let options = DecodeOptions { extended_length: true, ..DecodeOptions::default() };
+    /// let url = format!("file:///C:/{}/x.txt", "d".repeat(300));
+    /// let path = Platform::Windows.file_url_to_path_with(&url, options).unwrap();
+    /// assert!(path.starts_with(r"\\?\C:\ddd"));
+    ///
+    /// let path = Platform::Windows.file_url_to_path_with("file:///C:/a", options).unwrap();
+    /// assert_eq!(path, r"C:\a");
+    /// ```
+    pub fn file_url_to_path_with(
+        self,
+        file_url: &str,
+        options: DecodeOptions,
+    ) -> Result<String, DecodeError> {
+        let file_url = &*check(file_url, options)?;
+        let path = self.file_url_to_path(file_url)?;
+        if options.require_absolute && !self.is_absolute(&path) {
+            return Err(DecodeError::Relative);
+        }
+        if self != Platform::Windows || !options.extended_length {
+            return Ok(path);
+        }
+        match extended_length(&path) {
+            Some(long) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    file_url,
+                    path = long.as_str(),
+                    "used an extended-length path"
+                );
+                Ok(long)
+            }
+            None => Ok(path),
+        }
+    }
+}
+
+/// Applies the checks `options` asks for before decoding, returning
+/// the URL to decode.
+fn check(file_url: &str, options: DecodeOptions) -> Result<Cow<'_, str>, DecodeError> {
+    let mut file_url = Cow::Borrowed(file_url);
+    if let Some(index) = empty_segment(&file_url) {
+        match options.slash_runs {
+            SlashRuns::Preserve => {}
+            SlashRuns::Collapse => file_url = Cow::Owned(collapse_slashes(&file_url)),
+            SlashRuns::Reject => return Err(DecodeError::EmptySegment { index }),
+        }
+    }
+    if options.reject_dot_segments {
+        if let Some(index) = dot_segment(&file_url) {
+            return Err(DecodeError::DotSegment { index });
+        }
+    }
+    if options.require_absolute && classify(&file_url) == InputKind::RelativeFileUrl {
+        return Err(DecodeError::Relative);
+    }
+    if options.reject_device_paths {
+        let windows = Platform::Windows.file_url_to_path(&file_url)?;
+        // Pushing `\\.\x` onto the root replaces it, so an encoded
+        // device path after the first slash counts as well.
+        if is_device_path(&windows) || windows.get(1..).is_some_and(is_device_path) {
+            return Err(DecodeError::DevicePath);
+        }
+    }
+    Ok(file_url)
+}
+
+/// Whether the Windows path `path` is in the device namespace: it
+/// starts `\\.\`, or `\\?\` followed by anything but a drive or
+/// `UNC\`.
+fn is_device_path(path: &str) -> bool {
+    let is_separator = |c: char| c == '/' || c == '\\';
+    let mut chars = path.chars();
+    let prefix: Vec<char> = chars.by_ref().take(4).collect();
+    let kind = match prefix[..] {
+        [a, b, kind, c] if is_separator(a) && is_separator(b) && is_separator(c) => kind,
+        _ => return false,
+    };
+    let rest = chars.as_str();
+    match kind {
+        '.' => true,
+        '?' => {
+            let bytes = rest.as_bytes();
+            let is_drive = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+            let is_unc = rest.get(..4).is_some_and(|unc| {
+                unc[..3].eq_ignore_ascii_case("unc") && is_separator(unc.as_bytes()[3] as char)
+            });
+            !is_drive && !is_unc
+        }
+        _ => false,
+    }
+}
+
+/// The path segments of `file_url`, past any authority, still encoded.
+fn segments(file_url: &str) -> std::str::Split<'_, char> {
+    let (_, path) = split_authority(file_url);
+    path.strip_prefix('/').unwrap_or(path).split('/')
+}
+
+/// The index of the first empty path segment of `file_url`. An empty
+/// last segment is a trailing slash, not a run, so it doesn't count.
+fn empty_segment(file_url: &str) -> Option<usize> {
+    let mut segments = segments(file_url).peekable();
+    let mut index = 0;
+    while let Some(segment) = segments.next() {
+        if segment.is_empty() && segments.peek().is_some() {
+            return Some(index);
+        }
+        index += 1;
+    }
+    None
+}
+
+/// `file_url` with each run of slashes in its path merged into one.
+fn collapse_slashes(file_url: &str) -> String {
+    let (_, path) = split_authority(file_url);
+    let mut collapsed = String::with_capacity(file_url.len());
+    collapsed.push_str(&file_url[..file_url.len() - path.len()]);
+    let mut last = None;
+    for c in path.chars() {
+        if c != '/' || last != Some('/') {
+            collapsed.push(c);
+        }
+        last = Some(c);
+    }
+    collapsed
+}
+
+/// The index of the first path segment of `file_url` that decodes to
+/// something with a `.` or `..` piece in it.
+fn dot_segment(file_url: &str) -> Option<usize> {
+    segments(file_url).position(|segment| {
+        percent::decode_binary(segment.as_bytes())
+            .split(|&b| b == b'/' || b == b'\\')
+            .any(|piece| piece == b"." || piece == b"..")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_url_to_pathbuf;
+
+    #[test]
+    fn dot_segments_are_rejected_when_asked() {
+        let options = DecodeOptions {
+            reject_dot_segments: true,
+            ..DecodeOptions::default()
+        };
+        let rejected = [
+            ("file:///a/../b", 1),
+            ("file:///./a", 0),
+            ("file:///a/.", 1),
+            ("file://srv/share/%2e%2E/x", 1),
+            ("file:///a/..%2Fb", 1),
+            ("file:///a/b%5C..", 1),
+            ("file:..", 0),
+        ];
+        for (url, index) in rejected.iter() {
+            match file_url_to_pathbuf_with(url, options) {
+                Err(DecodeError::DotSegment { index: i }) => assert_eq!(i, *index, "{}", url),
+                other => panic!("{}: unexpected {:?}", url, other),
+            }
+            assert!(file_url_to_pathbuf_with(url, DecodeOptions::default()).is_ok());
+        }
+        for url in ["file:///a/.b/c..d/...", "file://./rel", "file:///"].iter() {
+            assert!(
+                Platform::Posix.file_url_to_path_with(url, options).is_ok(),
+                "{}",
+                url
+            );
+        }
+        let err = file_url_to_pathbuf_with("file:///%FF", options).unwrap_err();
+        assert!(err.source().unwrap().is::<FromUtf8Error>());
+    }
+
+    #[test]
+    fn relative_results_are_rejected_when_asked() {
+        let options = DecodeOptions {
+            require_absolute: true,
+            ..DecodeOptions::default()
+        };
+        for url in ["file:a/b", "file://./a", "file:"].iter() {
+            assert!(matches!(
+                Platform::Posix.file_url_to_path_with(url, options),
+                Err(DecodeError::Relative)
+            ));
+        }
+        let posix = Platform::Posix.file_url_to_path_with("file:///a", options);
+        assert_eq!(posix.unwrap(), "/a");
+        let windows = Platform::Windows.file_url_to_path_with("file:///a", options);
+        assert!(matches!(windows, Err(DecodeError::Relative)));
+        let windows = Platform::Windows.file_url_to_path_with("file://srv/share/a", options);
+        assert_eq!(windows.unwrap(), r"\\srv\share\a");
+        if cfg!(unix) {
+            assert!(file_url_to_pathbuf_with("file:///a", options).is_ok());
+            assert!(file_url_to_pathbuf_with("file:a", options).is_err());
+        }
+    }
+
+    #[test]
+    fn slash_runs_follow_the_policy() {
+        let with = |slash_runs| DecodeOptions {
+            slash_runs,
+            ..DecodeOptions::default()
+        };
+        let posix = Platform::Posix;
+        let url = "file:///a////b/";
+        assert_eq!(
+            posix
+                .file_url_to_path_with(url, with(SlashRuns::Preserve))
+                .unwrap(),
+            "/a////b/"
+        );
+        assert_eq!(
+            posix
+                .file_url_to_path_with(url, with(SlashRuns::Collapse))
+                .unwrap(),
+            "/a/b/"
+        );
+        let collapsed = Platform::Windows
+            .file_url_to_path_with("file://srv//share///x", with(SlashRuns::Collapse))
+            .unwrap();
+        assert_eq!(collapsed, r"\\srv\share\x");
+        let rejected = [
+            ("file:///a////b", 1),
+            ("file://////x", 0),
+            ("file://h/a//", 1),
+        ];
+        for (url, index) in rejected.iter() {
+            match posix.file_url_to_path_with(url, with(SlashRuns::Reject)) {
+                Err(DecodeError::EmptySegment { index: i }) => assert_eq!(i, *index, "{}", url),
+                other => panic!("{}: unexpected {:?}", url, other),
+            }
+        }
+        for url in [
+            "file:///",
+            "file:///a/b/",
+            "file://h",
+            "file:////srv/share",
+            "file:a",
+        ]
+        .iter()
+        {
+            assert!(
+                posix
+                    .file_url_to_path_with(url, with(SlashRuns::Reject))
+                    .is_ok(),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn device_paths_are_rejected_when_asked() {
+        let options = DecodeOptions {
+            reject_device_paths: true,
+            ..DecodeOptions::default()
+        };
+        let devices = [
+            "file://./COM1",
+            "file:////./PhysicalDrive0",
+            "file:///%5C%5C.%5CPhysicalDrive0",
+            "file://%3F/GLOBALROOT/Device/Harddisk0",
+        ];
+        for url in devices.iter() {
+            assert!(
+                matches!(
+                    file_url_to_pathbuf_with(url, options),
+                    Err(DecodeError::DevicePath)
+                ),
+                "{}",
+                url
+            );
+            assert!(Platform::Windows
+                .file_url_to_path_with(url, DecodeOptions::default())
+                .is_ok());
+        }
+        let files = [
+            "file:///C:/COM1.txt",
+            "file://srv/share",
+            "file:///%5C%5C%3F%5CC:%5Cx",
+            "file:///%5C%5C%3F%5CUNC%5Csrv%5Cshare",
+        ];
+        for url in files.iter() {
+            assert!(
+                Platform::Windows
+                    .file_url_to_path_with(url, options)
+                    .is_ok(),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn extended_length_is_for_windows() {
+        let long = "a".repeat(260);
+        let url = format!("file://srv/share/{}", long);
+        let options = DecodeOptions {
+            extended_length: true,
+            ..DecodeOptions::default()
+        };
+        let path = Platform::Windows
+            .file_url_to_path_with(&url, options)
+            .unwrap();
+        assert_eq!(path, format!(r"\\?\UNC\srv\share\{}", long));
+        let path = Platform::Posix
+            .file_url_to_path_with(&url, options)
+            .unwrap();
+        assert_eq!(path, Platform::Posix.file_url_to_path(&url).unwrap());
+        if !cfg!(windows) {
+            assert_eq!(
+                file_url_to_pathbuf_with(&url, options).unwrap(),
+                file_url_to_pathbuf(&url).unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Normalizing the case of Windows drive letters, since producers
+//! disagree on `file:///c:/` versus `file:///C:/`.
+use std::borrow::Cow;
+
+use crate::classify::is_encoded_drive;
+use crate::{FileUrl, FileUrlBuf};
+
+/// What to do with the letter of a drive like `C:` in a URL.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DriveLetterCase {
+    /// Write it in uppercase, as Windows itself does.
+    Upper,
+    /// Write it in lowercase, as VS Code does.
+    Lower,
+    /// Leave it as it is.
+    #[default]
+    Preserve,
+}
+
+impl DriveLetterCase {
+    fn apply(self, letter: u8) -> u8 {
+        match self {
+            DriveLetterCase::Upper => letter.to_ascii_uppercase(),
+            DriveLetterCase::Lower => letter.to_ascii_lowercase(),
+            DriveLetterCase::Preserve => letter,
+        }
+    }
+
+    /// `segment` with this case applied if it's a drive.
+    pub(crate) fn apply_to_segment(self, segment: &str) -> Cow<'_, str> {
+        let bytes = segment.as_bytes();
+        let letter = bytes.first().map(|&b| self.apply(b));
+        if is_drive(segment) && letter != bytes.first().copied() {
+            let letter = letter.unwrap() as char;
+            Cow::Owned(format!("{}:", letter))
+        } else {
+            Cow::Borrowed(segment)
+        }
+    }
+}
+
+fn is_drive(segment: &str) -> bool {
+    let bytes = segment.as_bytes();
+    bytes.len() == 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+/// The byte index of the drive letter in `url`: the first path
+/// segment, if that's a drive, with its colon encoded or not.
+fn drive_letter_index(url: &str) -> Option<usize> {
+    // Validation already checked the scheme.
+    let start = match url[5..].strip_prefix("//") {
+        Some(rest) => 7 + rest.find('/')?,
+        None => 5,
+    };
+    let path = url[start..].strip_prefix('/')?;
+    let end = path.find('/').unwrap_or(path.len());
+    if is_drive(&path[..end]) || is_encoded_drive(&path[..end]) {
+        Some(start + 1)
+    } else {
+        None
+    }
+}
+
+impl FileUrl {
+    /// The URL with its drive letter, if it has one, in `case`,
+    /// borrowed when nothing changes.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::{DriveLetterCase, FileUrl};
+    ///
+    /// let url = FileUrl::new("file:///c:/Users/me").unwrap();
+    /// let upper = url.with_drive_case(DriveLetterCase::Upper);
+    /// assert_eq!(upper.as_str(), "file:///C:/Users/me");
+    /// assert_eq!(url.with_drive_case(DriveLetterCase::Lower).as_str(), url.as_str());
+    /// ```
+    pub fn with_drive_case(&self, case: DriveLetterCase) -> Cow<'_, FileUrl> {
+        let url = self.as_str();
+        let i = match drive_letter_index(url) {
+            Some(i) => i,
+            None => return Cow::Borrowed(self),
+        };
+        let letter = case.apply(url.as_bytes()[i]);
+        if letter == url.as_bytes()[i] {
+            return Cow::Borrowed(self);
+        }
+        let mut owned = String::with_capacity(url.len());
+        owned.push_str(&url[..i]);
+        owned.push(letter as char);
+        owned.push_str(&url[i + 1..]);
+        // Only an ASCII letter changed, which keeps the URL valid.
+        Cow::Owned(FileUrlBuf::from_string_unchecked(owned))
+    }
+}
+
+impl FileUrlBuf {
+    /// Puts the drive letter, if there is one, in `case`.
+    pub fn normalize_drive_case(&mut self, case: DriveLetterCase) {
+        if let Cow::Owned(url) = self.with_drive_case(case) {
+            *self = url;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_drive_letters_change() {
+        let cases = [
+            ("file:///c:/x", "file:///C:/x"),
+            ("file:///d:", "file:///D:"),
+            ("file://host/e:/x", "file://host/E:/x"),
+            ("file:///cc:/x", "file:///cc:/x"),
+            ("file:///x/c:", "file:///x/c:"),
+            ("file:///1:/x", "file:///1:/x"),
+            ("file://c:", "file://c:"),
+            ("file:///c%3A/x", "file:///C%3A/x"),
+        ];
+        for (input, upper) in cases.iter() {
+            let url = FileUrl::new(input).unwrap();
+            assert_eq!(url.with_drive_case(DriveLetterCase::Upper).as_str(), *upper);
+            let lower = url.with_drive_case(DriveLetterCase::Upper);
+            let lower = lower.with_drive_case(DriveLetterCase::Lower);
+            assert_eq!(lower.as_str().to_lowercase(), input.to_lowercase());
+            assert!(matches!(
+                url.with_drive_case(DriveLetterCase::Preserve),
+                Cow::Borrowed(_)
+            ));
+        }
+
+        let mut buf = FileUrlBuf::new("file:///Z:/a".to_string()).unwrap();
+        buf.normalize_drive_case(DriveLetterCase::Lower);
+        assert_eq!(buf.as_str(), "file:///z:/a");
+        assert_eq!(DriveLetterCase::Lower.apply_to_segment("Q:"), "q:");
+        assert_eq!(DriveLetterCase::Lower.apply_to_segment("Qx"), "Qx");
+    }
+}
@@ -0,0 +1,139 @@
+//! Links that open a file in an editor, like `vscode://file/...`,
+//! built from the file URL of the path.
+use std::path::Path;
+
+use crate::{EncodeSet, FragmentPosition, PathFileUrlExt, Platform, UTFDecodeError};
+
+/// What's left alone in a query parameter's value.
+static QUERY: EncodeSet = EncodeSet::UNRESERVED.keep(b'/').keep(b':');
+
+/// An editor with its own URI scheme for opening files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Editor {
+    /// `vscode://file/<path>:<line>:<column>`.
+    VsCode,
+    /// `vscode-insiders://file/<path>:<line>:<column>`.
+    VsCodeInsiders,
+    /// JetBrains IDEs, `idea://open?file=<path>&line=<line>&column=<column>`.
+    Idea,
+    /// Sublime Text, `subl://open?url=<file URL>&line=<line>&column=<column>`.
+    Sublime,
+    /// TextMate, `txmt://open?url=<file URL>&line=<line>&column=<column>`.
+    TextMate,
+}
+
+impl Editor {
+    /// A link that opens `path` in this editor, at `position` if
+    /// given. The path should be absolute; the editor has no current
+    /// directory to resolve a relative one against.
+    ///
+    /// # Example:
+    /// ```
+    /// use std::path::Path;
+    /// use file_url::{Editor, FragmentPosition};
+    ///
+    /// let at = FragmentPosition { line: 10, column: Some(5) };
+    /// let path = Path::new("/src/my app/main.rs");
+    /// assert_eq!(
+    ///     Editor::VsCode.link(path, Some(at)).unwrap(),
+    ///     "vscode://file/src/my%20app/main.rs:10:5"
+    /// );
+    /// assert_eq!(
+    ///     Editor::Idea.link(path, None).unwrap(),
+    ///     "idea://open?file=/src/my%20app/main.rs"
+    /// );
+    /// ```
+    pub fn link(
+        self,
+        path: &Path,
+        position: Option<FragmentPosition>,
+    ) -> Result<String, UTFDecodeError> {
+        let url = path.to_file_url()?;
+        // `to_file_url` would have failed on a non-UTF-8 path.
+        Ok(self.link_from(&url, path.to_str().unwrap(), position))
+    }
+
+    /// Like `link`, but for a path in `platform`'s syntax rather than
+    /// the current one's.
+    pub fn link_on(
+        self,
+        path: &str,
+        platform: Platform,
+        position: Option<FragmentPosition>,
+    ) -> String {
+        self.link_from(&platform.path_to_file_url(path), path, position)
+    }
+
+    fn link_from(self, url: &str, path: &str, position: Option<FragmentPosition>) -> String {
+        let (mut link, line, column) = match self {
+            Editor::VsCode => (format!("vscode://file{}", url_path(url)), ":", ":"),
+            Editor::VsCodeInsiders => {
+                let link = format!("vscode-insiders://file{}", url_path(url));
+                (link, ":", ":")
+            }
+            Editor::Idea => {
+                let link = format!("idea://open?file={}", QUERY.encode(path));
+                (link, "&line=", "&column=")
+            }
+            Editor::Sublime => {
+                let link = format!("subl://open?url={}", QUERY.encode(url));
+                (link, "&line=", "&column=")
+            }
+            Editor::TextMate => {
+                let link = format!("txmt://open?url={}", QUERY.encode(url));
+                (link, "&line=", "&column=")
+            }
+        };
+        if let Some(position) = position {
+            link.push_str(&format!("{}{}", line, position.line));
+            if let Some(n) = position.column {
+                link.push_str(&format!("{}{}", column, n));
+            }
+        }
+        link
+    }
+}
+
+/// The path of the file URL `url`, still encoded, or `//host/path` if
+/// it has a host.
+fn url_path(url: &str) -> &str {
+    match url.strip_prefix("file://") {
+        Some(path) if path.starts_with('/') => path,
+        _ => &url["file:".len()..],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_for_each_editor() {
+        let at = Some(FragmentPosition {
+            line: 3,
+            column: None,
+        });
+        let cases = [
+            (Editor::VsCode, "vscode://file/C:/a%20b/x.rs:3"),
+            (
+                Editor::VsCodeInsiders,
+                "vscode-insiders://file/C:/a%20b/x.rs:3",
+            ),
+            (Editor::Idea, "idea://open?file=C:%5Ca%20b%5Cx.rs&line=3"),
+            (
+                Editor::Sublime,
+                "subl://open?url=file:///C:/a%2520b/x.rs&line=3",
+            ),
+            (
+                Editor::TextMate,
+                "txmt://open?url=file:///C:/a%2520b/x.rs&line=3",
+            ),
+        ];
+        for (editor, link) in cases.iter() {
+            let made = editor.link_on(r"C:\a b\x.rs", Platform::Windows, at);
+            assert_eq!(made, *link, "{:?}", editor);
+        }
+        let unc = Editor::VsCode.link_on(r"\\srv\share\x", Platform::Windows, None);
+        assert_eq!(unc, "vscode://file//srv/share/x");
+    }
+}
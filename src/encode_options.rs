@@ -0,0 +1,510 @@
+//! `EncodeOptions` and `OutputProfile`, and the encoders that take
+//! them.
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::escapes::unescape_escapes;
+use crate::percent::decode;
+use crate::platform::split_authority;
+use crate::{
+    is_windows_drive, DriveLetterCase, EncodeSet, FileUrl, PathFileUrlExt, Platform, UTFDecodeError,
+};
+
+/// Options for `path_to_file_url_with` and
+/// `Platform::path_to_file_url_with`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct EncodeOptions {
+    /// Leave a `%` that starts a valid `%XX` escape alone instead of
+    /// encoding it as `%25`, so a path that's already been encoded
+    /// comes out the same. A `%` that doesn't start one is still
+    /// encoded. This loses information: a file really named `a%20b`
+    /// gets the URL of `a b`.
+    pub keep_escapes: bool,
+    /// How to write the authority of a URL without a host.
+    pub authority: AuthorityStyle,
+}
+
+/// How an encoded URL with no host spells its authority. RFC 8089
+/// allows all three, but consumers can be picky about which they
+/// accept. URLs with a host, like `file://server/share`, and the
+/// `file://./a` form of a relative path are written the same way
+/// whatever the style.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AuthorityStyle {
+    /// `file:///path`, an empty authority, as `to_file_url` writes.
+    #[default]
+    Empty,
+    /// `file:/path`, with no authority at all.
+    Omitted,
+    /// `file://localhost/path`.
+    Localhost,
+}
+
+/// Every option for how an encoded URL is written, bundled so that a
+/// house style can be named once, even as a `const`, and passed
+/// wherever URLs are made. `OutputProfile::DEFAULT` writes what
+/// `to_file_url` does.
+///
+/// # Example:
+/// ```
+/// use std::path::Path;
+/// use file_url::{
+///     AuthorityStyle, DriveLetterCase, EncodeOptions, HexCase, OutputProfile, Platform,
+/// };
+///
+/// const LOCALHOST_LOWER: OutputProfile = OutputProfile {
+///     encode: EncodeOptions {
+///         keep_escapes: false,
+///         authority: AuthorityStyle::Localhost,
+///     },
+///     hex_case: HexCase::Lower,
+///     drive_case: DriveLetterCase::Lower,
+///     ..OutputProfile::DEFAULT
+/// };
+///
+/// let url = LOCALHOST_LOWER.to_file_url_on(r"C:\Users\Zoë\", Platform::Windows);
+/// assert_eq!(url, "file://localhost/c:/Users/Zo%c3%ab/");
+///
+/// let url = OutputProfile::DEFAULT.to_file_url(Path::new("/srv/a b")).unwrap();
+/// assert_eq!(url, "file:///srv/a%20b");
+/// ```
+///
+/// A profile can also be stored in config as a string, the
+/// comma-separated list of ways it differs from the default, which is
+/// what `Display` writes, `FromStr` reads, and the `serde` feature
+/// (de)serializes. The names are `localhost`, `omit-authority`,
+/// `keep-escapes`, `lower-hex`, `upper-drive`, `lower-drive` and
+/// `trim-slash`, plus `keep=` and `escape=` followed by ASCII bytes,
+/// percent-encoded where they'd be ambiguous, to adjust the encode set.
+///
+/// ```
+/// use file_url::{AuthorityStyle, HexCase, OutputProfile};
+///
+/// let profile: OutputProfile = "localhost,lower-hex,keep=!$".parse().unwrap();
+/// assert_eq!(profile.encode.authority, AuthorityStyle::Localhost);
+/// assert_eq!(profile.hex_case, HexCase::Lower);
+/// assert!(!profile.encode_set.contains(b'$'));
+/// assert_eq!(profile.to_string(), "localhost,lower-hex,keep=!$");
+/// assert_eq!(OutputProfile::DEFAULT.to_string(), "");
+/// assert!("loud".parse::<OutputProfile>().is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutputProfile {
+    /// The options `path_to_file_url_with` takes.
+    pub encode: EncodeOptions,
+    /// Which bytes of a path segment are percent-encoded. A drive's
+    /// colon is always kept, and a host is encoded as usual.
+    pub encode_set: EncodeSet,
+    /// The case of the hex digits in escapes.
+    pub hex_case: HexCase,
+    /// The case of a drive letter.
+    pub drive_case: DriveLetterCase,
+    /// Drop a trailing slash, other than one that is the whole path,
+    /// so directories are written like files.
+    pub trim_trailing_slash: bool,
+}
+
+impl OutputProfile {
+    /// The crate's own output: `file:///path`, escapes in uppercase,
+    /// and everything but the RFC 3986 unreserved characters escaped.
+    pub const DEFAULT: OutputProfile = OutputProfile {
+        encode: EncodeOptions {
+            keep_escapes: false,
+            authority: AuthorityStyle::Empty,
+        },
+        encode_set: EncodeSet::UNRESERVED,
+        hex_case: HexCase::Upper,
+        drive_case: DriveLetterCase::Preserve,
+        trim_trailing_slash: false,
+    };
+
+    /// Like `PathFileUrlExt::to_file_url`, written in this profile.
+    pub fn to_file_url(&self, path: &Path) -> Result<String, UTFDecodeError> {
+        Ok(self.apply(path.to_file_url()?))
+    }
+
+    /// Like `Platform::path_to_file_url`, written in this profile.
+    pub fn to_file_url_on(&self, path: &str, platform: Platform) -> String {
+        self.apply(platform.path_to_file_url(path))
+    }
+
+    pub(crate) fn apply(&self, mut url: String) -> String {
+        if self.encode_set != EncodeSet::UNRESERVED {
+            url = reencode(&url, &self.encode_set);
+        }
+        url = apply(url, self.encode);
+        if self.trim_trailing_slash {
+            let (_, path) = split_authority(&url);
+            if path.len() > 1 && path.ends_with('/') {
+                url.pop();
+            }
+        }
+        if let Ok(parsed) = FileUrl::new(&url) {
+            if let Cow::Owned(cased) = parsed.with_drive_case(self.drive_case) {
+                url = cased.into_string();
+            }
+        }
+        if self.hex_case == HexCase::Lower {
+            lowercase_escapes(&mut url);
+        }
+        url
+    }
+}
+
+impl Default for OutputProfile {
+    /// `OutputProfile::DEFAULT`.
+    fn default() -> OutputProfile {
+        OutputProfile::DEFAULT
+    }
+}
+
+impl fmt::Display for OutputProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names = Vec::new();
+        match self.encode.authority {
+            AuthorityStyle::Empty => {}
+            AuthorityStyle::Omitted => names.push(String::from("omit-authority")),
+            AuthorityStyle::Localhost => names.push(String::from("localhost")),
+        }
+        if self.encode.keep_escapes {
+            names.push(String::from("keep-escapes"));
+        }
+        if self.hex_case == HexCase::Lower {
+            names.push(String::from("lower-hex"));
+        }
+        match self.drive_case {
+            DriveLetterCase::Preserve => {}
+            DriveLetterCase::Upper => names.push(String::from("upper-drive")),
+            DriveLetterCase::Lower => names.push(String::from("lower-drive")),
+        }
+        if self.trim_trailing_slash {
+            names.push(String::from("trim-slash"));
+        }
+        let changed = |kept: bool| -> String {
+            let mut bytes = String::new();
+            for b in 0..0x80u8 {
+                let differs = EncodeSet::UNRESERVED.contains(b) != self.encode_set.contains(b);
+                if differs && self.encode_set.contains(b) != kept {
+                    if b.is_ascii_graphic() && b != b'%' && b != b',' {
+                        bytes.push(b as char);
+                    } else {
+                        // Writing to a String can't fail.
+                        write!(bytes, "%{:02X}", b).unwrap();
+                    }
+                }
+            }
+            bytes
+        };
+        for (name, kept) in [("keep=", true), ("escape=", false)].iter() {
+            let bytes = changed(*kept);
+            if !bytes.is_empty() {
+                names.push(format!("{}{}", name, bytes));
+            }
+        }
+        write!(f, "{}", names.join(","))
+    }
+}
+
+/// Error for strings that aren't a list of `OutputProfile` names.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseProfileError {
+    name: String,
+}
+
+impl fmt::Display for ParseProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown output profile option {}!", self.name)
+    }
+}
+
+impl Error for ParseProfileError {}
+
+impl FromStr for OutputProfile {
+    type Err = ParseProfileError;
+
+    /// Reads the names `Display` writes, starting from
+    /// `OutputProfile::DEFAULT`. Later names override earlier ones.
+    fn from_str(names: &str) -> Result<OutputProfile, ParseProfileError> {
+        let mut profile = OutputProfile::DEFAULT;
+        for name in names.split(',').filter(|name| !name.is_empty()) {
+            let unknown = || ParseProfileError {
+                name: name.to_string(),
+            };
+            match name {
+                "localhost" => profile.encode.authority = AuthorityStyle::Localhost,
+                "omit-authority" => profile.encode.authority = AuthorityStyle::Omitted,
+                "keep-escapes" => profile.encode.keep_escapes = true,
+                "lower-hex" => profile.hex_case = HexCase::Lower,
+                "upper-drive" => profile.drive_case = DriveLetterCase::Upper,
+                "lower-drive" => profile.drive_case = DriveLetterCase::Lower,
+                "trim-slash" => profile.trim_trailing_slash = true,
+                _ => {
+                    let (kept, bytes) = if let Some(bytes) = name.strip_prefix("keep=") {
+                        (true, bytes)
+                    } else if let Some(bytes) = name.strip_prefix("escape=") {
+                        (false, bytes)
+                    } else {
+                        return Err(unknown());
+                    };
+                    let bytes = decode(bytes).map_err(|_| unknown())?;
+                    if bytes.is_empty() || !bytes.is_ascii() {
+                        return Err(unknown());
+                    }
+                    for b in bytes.bytes() {
+                        profile.encode_set = if kept {
+                            profile.encode_set.keep(b)
+                        } else {
+                            profile.encode_set.escape(b)
+                        };
+                    }
+                }
+            }
+        }
+        Ok(profile)
+    }
+}
+
+/// The case of the hex digits in a `%XX` escape.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum HexCase {
+    /// `%C3%AB`, as RFC 3986 recommends.
+    #[default]
+    Upper,
+    /// `%c3%ab`.
+    Lower,
+}
+
+/// `url` with each path segment decoded and encoded again with `set`.
+fn reencode(url: &str, set: &EncodeSet) -> String {
+    let (_, path) = split_authority(url);
+    let mut out = String::with_capacity(url.len());
+    out.push_str(&url[..url.len() - path.len()]);
+    for (i, piece) in path.split('/').enumerate() {
+        if i > 0 {
+            out.push('/');
+        }
+        // The encoder's own output always decodes.
+        match decode(piece) {
+            Ok(decoded) if i != 1 || !is_windows_drive(&decoded) => {
+                out.push_str(&set.encode(&decoded))
+            }
+            _ => out.push_str(piece),
+        }
+    }
+    out
+}
+
+/// Lowercases the hex digits of every escape in the ASCII `url`.
+fn lowercase_escapes(url: &mut String) {
+    let mut bytes = std::mem::take(url).into_bytes();
+    let mut i = 0;
+    while i + 2 < bytes.len() {
+        if bytes[i] == b'%' {
+            bytes[i + 1].make_ascii_lowercase();
+            bytes[i + 2].make_ascii_lowercase();
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+    // Only ASCII letters changed.
+    *url = String::from_utf8(bytes).unwrap();
+}
+
+/// Like `PathFileUrlExt::to_file_url`, with `options` applied.
+///
+/// # Example:
+/// ```
+/// use std::path::Path;
+/// use file_url::{path_to_file_url_with, AuthorityStyle, EncodeOptions};
+///
+/// let options = EncodeOptions { keep_escapes: true, ..EncodeOptions::default() };
+/// let url = path_to_file_url_with(Path::new("/srv/a%20b/100%"), options).unwrap();
+/// assert_eq!(url, "file:///srv/a%20b/100%25");
+///
+/// let options = EncodeOptions {
+///     authority: AuthorityStyle::Localhost,
+///     ..EncodeOptions::default()
+/// };
+/// let url = path_to_file_url_with(Path::new("/srv/a.txt"), options).unwrap();
+/// assert_eq!(url, "file://localhost/srv/a.txt");
+/// ```
+pub fn path_to_file_url_with(
+    path: &Path,
+    options: EncodeOptions,
+) -> Result<String, UTFDecodeError> {
+    let url = path.to_file_url()?;
+    Ok(apply(url, options))
+}
+
+impl Platform {
+    /// Like `path_to_file_url`, with `options` applied.
+    pub fn path_to_file_url_with(self, path: &str, options: EncodeOptions) -> String {
+        apply(self.path_to_file_url(path), options)
+    }
+}
+
+fn apply(mut url: String, options: EncodeOptions) -> String {
+    if options.keep_escapes && url.contains("%25") {
+        let kept = unescape_escapes(&url);
+        #[cfg(feature = "tracing")]
+        if kept != url {
+            tracing::debug!(url = kept.as_str(), "kept existing escapes");
+        }
+        url = kept;
+    }
+    if url.starts_with("file:///") {
+        match options.authority {
+            AuthorityStyle::Empty => {}
+            AuthorityStyle::Omitted => url.replace_range(..7, "file:"),
+            AuthorityStyle::Localhost => url.insert_str(7, "localhost"),
+        }
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_url_to_pathbuf;
+
+    #[test]
+    fn encoding_twice_is_a_no_op() {
+        let options = EncodeOptions {
+            keep_escapes: true,
+            ..EncodeOptions::default()
+        };
+        let cases = [
+            ("/a b/c", "file:///a%20b/c"),
+            ("/caf%C3%A9/%2f", "file:///caf%C3%A9/%2f"),
+            ("/%%41/%4/%", "file:///%25%41/%254/%25"),
+            ("/%2541", "file:///%2541"),
+        ];
+        for (path, url) in cases.iter() {
+            let encoded = Platform::Posix.path_to_file_url_with(path, options);
+            assert_eq!(encoded, *url, "{}", path);
+            let again = Platform::Posix.path_to_file_url_with(&encoded[7..], options);
+            assert_eq!(again, encoded, "{}", path);
+        }
+        assert_eq!(
+            path_to_file_url_with(Path::new("/x"), EncodeOptions::default()).unwrap(),
+            "file:///x"
+        );
+        let url = path_to_file_url_with(Path::new("/a%20b"), options).unwrap();
+        assert_eq!(file_url_to_pathbuf(&url).unwrap(), Path::new("/a b"));
+    }
+
+    #[test]
+    fn profiles_combine_their_options() {
+        let posix = Platform::Posix;
+        assert_eq!(
+            OutputProfile::default().to_file_url_on("/a b/ü/", posix),
+            posix.path_to_file_url("/a b/ü/")
+        );
+        let profile = OutputProfile {
+            encode_set: EncodeSet::UNRESERVED.keep(b'!').escape(b'~'),
+            trim_trailing_slash: true,
+            ..OutputProfile::DEFAULT
+        };
+        assert_eq!(
+            profile.to_file_url_on("/a!b/~c/", posix),
+            "file:///a!b/%7Ec"
+        );
+        assert_eq!(profile.to_file_url_on("/", posix), "file:///");
+        assert_eq!(
+            profile.to_file_url_on(r"c:\x y\", Platform::Windows),
+            "file:///c:/x%20y"
+        );
+        let profile = OutputProfile {
+            encode: EncodeOptions {
+                keep_escapes: true,
+                authority: AuthorityStyle::Omitted,
+            },
+            hex_case: HexCase::Lower,
+            drive_case: DriveLetterCase::Upper,
+            ..OutputProfile::DEFAULT
+        };
+        assert_eq!(
+            profile.to_file_url_on(r"d:\%C3%BC\ä", Platform::Windows),
+            "file:/D:/%c3%bc/%c3%a4"
+        );
+    }
+
+    #[test]
+    fn profiles_round_trip_through_strings() {
+        let profiles = [
+            OutputProfile::DEFAULT,
+            OutputProfile {
+                encode: EncodeOptions {
+                    keep_escapes: true,
+                    authority: AuthorityStyle::Omitted,
+                },
+                encode_set: EncodeSet::UNRESERVED
+                    .keep(b',')
+                    .keep(b'%')
+                    .keep(b'!')
+                    .escape(b'~'),
+                hex_case: HexCase::Lower,
+                drive_case: DriveLetterCase::Upper,
+                trim_trailing_slash: true,
+            },
+        ];
+        for profile in profiles.iter() {
+            let names = profile.to_string();
+            assert_eq!(
+                names.parse::<OutputProfile>().unwrap(),
+                *profile,
+                "{}",
+                names
+            );
+        }
+        assert_eq!(
+            profiles[1].to_string(),
+            "omit-authority,keep-escapes,lower-hex,upper-drive,trim-slash,keep=!%25%2C,escape=~"
+        );
+        for bad in ["loud", "keep=", "keep=%FF", "keep=\u{e9}", "Localhost"].iter() {
+            assert!(bad.parse::<OutputProfile>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn authority_styles() {
+        let cases = [
+            (
+                Platform::Posix,
+                "/a b",
+                ["file:///a%20b", "file:/a%20b", "file://localhost/a%20b"],
+            ),
+            (
+                Platform::Posix,
+                "/",
+                ["file:///", "file:/", "file://localhost/"],
+            ),
+            (
+                Platform::Windows,
+                r"C:\x",
+                ["file:///C:/x", "file:/C:/x", "file://localhost/C:/x"],
+            ),
+            (Platform::Windows, r"\\srv\share", ["file://srv/share"; 3]),
+        ];
+        let styles = [
+            AuthorityStyle::Empty,
+            AuthorityStyle::Omitted,
+            AuthorityStyle::Localhost,
+        ];
+        for (platform, path, urls) in cases.iter() {
+            for (&authority, url) in styles.iter().zip(urls.iter()) {
+                let options = EncodeOptions {
+                    authority,
+                    ..EncodeOptions::default()
+                };
+                let encoded = platform.path_to_file_url_with(path, options);
+                assert_eq!(encoded, *url, "{:?}", authority);
+                assert_eq!(platform.file_url_to_path(&encoded).unwrap(), *path);
+            }
+        }
+    }
+}
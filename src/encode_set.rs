@@ -0,0 +1,129 @@
+//! Sets of bytes to percent-encode, built in `const` context so a
+//! custom set can live in a `static` with no lazy initialization.
+use alloc::borrow::Cow;
+use alloc::string::String;
+
+use crate::scan;
+
+/// The bytes that get percent-encoded. Starts from the set the crate
+/// itself uses and adjusts one ASCII byte at a time; bytes outside
+/// ASCII are always encoded, so the output stays ASCII.
+///
+/// # Example:
+/// ```
+/// use file_url::EncodeSet;
+///
+/// // Keep sub-delimiters readable, but escape `~`.
+/// static LOOSE: EncodeSet = EncodeSet::UNRESERVED
+///     .keep(b'!')
+///     .keep(b'$')
+///     .keep(b'&')
+///     .escape(b'~');
+///
+/// assert_eq!(LOOSE.encode("a&b~c d!"), "a&b%7Ec%20d!");
+/// assert!(LOOSE.contains(b' '));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncodeSet {
+    /// Whether each byte is left alone, by value.
+    kept: [bool; 256],
+}
+
+impl EncodeSet {
+    /// Everything but the RFC 3986 unreserved characters, which is
+    /// what `encode_file_component` uses.
+    pub const UNRESERVED: EncodeSet = EncodeSet {
+        kept: scan::unreserved_table(),
+    };
+
+    /// Every byte.
+    pub const ALL: EncodeSet = EncodeSet { kept: [false; 256] };
+
+    /// This set, but leaving the ASCII byte `b` alone. Panics if `b`
+    /// isn't ASCII, which is a compile error in `const` context.
+    pub const fn keep(self, b: u8) -> EncodeSet {
+        assert!(b.is_ascii(), "only ASCII bytes can be kept");
+        let mut kept = self.kept;
+        kept[b as usize] = true;
+        EncodeSet { kept }
+    }
+
+    /// This set, but also encoding `b`.
+    pub const fn escape(self, b: u8) -> EncodeSet {
+        let mut kept = self.kept;
+        kept[b as usize] = false;
+        EncodeSet { kept }
+    }
+
+    /// Whether `b` gets percent-encoded.
+    pub const fn contains(&self, b: u8) -> bool {
+        !self.kept[b as usize]
+    }
+
+    /// Percent-encodes the bytes of `part` that are in this set, in
+    /// uppercase hex, borrowing `part` if there are none.
+    pub fn encode<'a>(&self, part: &'a str) -> Cow<'a, str> {
+        const HEX: &[u8; 16] = b"0123456789ABCDEF";
+        let bytes = part.as_bytes();
+        let first = match bytes.iter().position(|&b| self.contains(b)) {
+            Some(first) => first,
+            None => return Cow::Borrowed(part),
+        };
+        let escaped = bytes[first..].iter().filter(|&&b| self.contains(b)).count();
+        let mut encoded = String::with_capacity(bytes.len() + 2 * escaped);
+        encoded.push_str(&part[..first]);
+        for &b in &bytes[first..] {
+            if self.contains(b) {
+                encoded.push('%');
+                encoded.push(HEX[usize::from(b >> 4)] as char);
+                encoded.push(HEX[usize::from(b & 0xF)] as char);
+            } else {
+                // Only ASCII is ever kept.
+                encoded.push(b as char);
+            }
+        }
+        Cow::Owned(encoded)
+    }
+}
+
+/// The bytes encoded in a host name: everything but the unreserved
+/// characters and RFC 3986 sub-delimiters, which a `reg-name` allows.
+#[cfg(any(feature = "std", test))]
+pub(crate) static HOST: EncodeSet = EncodeSet::UNRESERVED
+    .keep(b'!')
+    .keep(b'$')
+    .keep(b'&')
+    .keep(b'\'')
+    .keep(b'(')
+    .keep(b')')
+    .keep(b'*')
+    .keep(b'+')
+    .keep(b',')
+    .keep(b';')
+    .keep(b'=');
+
+impl Default for EncodeSet {
+    fn default() -> EncodeSet {
+        EncodeSet::UNRESERVED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATHS: EncodeSet = EncodeSet::UNRESERVED.keep(b'/');
+
+    #[test]
+    fn unreserved_matches_the_crate_encoding() {
+        let inputs = ["", "plain.txt", "a b/c", "ü~%", "C:\\x"];
+        for input in inputs.iter() {
+            assert_eq!(EncodeSet::UNRESERVED.encode(input), crate::encode(input));
+        }
+        assert_eq!(PATHS.encode("a b/c"), "a%20b/c");
+        assert_eq!(EncodeSet::ALL.encode("ab"), "%61%62");
+        assert!(matches!(PATHS.encode("a/b"), Cow::Borrowed(_)));
+        assert!(EncodeSet::ALL.keep(b'a').escape(b'a').contains(b'a'));
+        assert_eq!(HOST.encode("my server+1"), "my%20server+1");
+    }
+}
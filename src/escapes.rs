@@ -0,0 +1,68 @@
+//! Spotting text that's already percent-encoded, so feeding a URL
+//! back through the encoder doesn't escape it twice.
+use crate::scan;
+
+/// Whether `path_or_segment` looks like it's already been
+/// percent-encoded: it has at least one `%XX` escape, and every `%` in
+/// it starts one. A path that only happens to contain a `%` which
+/// doesn't start an escape, like `100%.txt`, doesn't count.
+///
+/// # Example:
+/// ```
+/// use file_url::looks_percent_encoded;
+///
+/// assert!(looks_percent_encoded("/srv/a%20b.txt"));
+/// assert!(looks_percent_encoded("file:///caf%C3%A9"));
+/// assert!(!looks_percent_encoded("/srv/a b.txt"));
+/// assert!(!looks_percent_encoded("100%.txt"));
+/// assert!(!looks_percent_encoded("%41 and 50%"));
+/// ```
+pub fn looks_percent_encoded(path_or_segment: &str) -> bool {
+    let bytes = path_or_segment.as_bytes();
+    let mut escapes = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        i += scan::first_percent(&bytes[i..]);
+        if i == bytes.len() {
+            break;
+        }
+        if !is_escape(bytes, i) {
+            return false;
+        }
+        escapes += 1;
+        i += 3;
+    }
+    escapes > 0
+}
+
+/// Whether the `%` at `bytes[i]` starts a `%XX` escape.
+fn is_escape(bytes: &[u8], i: usize) -> bool {
+    is_hex_pair(bytes, i + 1)
+}
+
+/// Whether `bytes[i]` and `bytes[i + 1]` are both hex digits.
+fn is_hex_pair(bytes: &[u8], i: usize) -> bool {
+    let is_hex = |i| bytes.get(i).copied().and_then(scan::hex_value).is_some();
+    is_hex(i) && is_hex(i + 1)
+}
+
+/// Turns each `%25XX` in an encoded URL back into `%XX`. Hex digits
+/// are never encoded, so these are exactly the `%` signs of the input
+/// that started an escape.
+#[cfg(feature = "std")]
+pub(crate) fn unescape_escapes(url: &str) -> String {
+    let bytes = url.as_bytes();
+    let mut out = String::with_capacity(url.len());
+    let mut last = 0;
+    let mut i = 0;
+    while let Some(found) = url[i..].find("%25") {
+        i += found;
+        if is_hex_pair(bytes, i + 3) {
+            out.push_str(&url[last..i + 1]);
+            last = i + 3;
+        }
+        i += 3;
+    }
+    out.push_str(&url[last..]);
+    out
+}
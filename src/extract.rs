@@ -0,0 +1,374 @@
+//! Finding the `file://` URLs embedded in free-form text, such as logs,
+//! chat messages and HTML, for tools that linkify them, and turning
+//! the paths in such text into file URLs.
+use std::ops::Range;
+use std::path::Path;
+
+use crate::{FileUrl, FileUrlParseError, PathFileUrlExt, Platform, UTFDecodeError};
+
+/// A `file://` URL found by `find_file_urls`.
+#[derive(Debug)]
+pub struct FileUrlMatch<'a> {
+    /// Where the URL is in the text, in bytes.
+    pub span: Range<usize>,
+    /// The URL, or why what looked like one isn't valid, such as an
+    /// escape that doesn't decode to UTF-8.
+    pub url: Result<&'a FileUrl, FileUrlParseError>,
+}
+
+/// Finds every `file://` URL in `text`, in order. A URL starts at a
+/// `file://`, in any case, that isn't the tail of a longer word like
+/// `myfile://`, and runs to the next whitespace, quote, backtick or
+/// angle bracket, which is how URLs are set off in prose, HTML
+/// attributes and Markdown. Then trailing characters that more likely
+/// belong to the sentence are dropped, as GitHub's autolinker does:
+/// `.,:;!?`, a closing bracket with no opening one in the URL, as in
+/// `(see file:///a)`, and an HTML entity like `&gt;`.
+///
+/// # Example:
+/// ```
+/// use file_url::find_file_urls;
+///
+/// let log = "error in file:///src/main.rs, see (file:///tmp/log%201.txt).";
+/// let found: Vec<_> = find_file_urls(log).collect();
+/// assert_eq!(&log[found[0].span.clone()], "file:///src/main.rs");
+/// assert_eq!(found[1].url.as_ref().unwrap().as_str(), "file:///tmp/log%201.txt");
+/// assert_eq!(found.len(), 2);
+/// ```
+pub fn find_file_urls(text: &str) -> FileUrlMatches<'_> {
+    FileUrlMatches { text, pos: 0 }
+}
+
+/// Iterator returned by `find_file_urls`.
+#[derive(Debug, Clone)]
+pub struct FileUrlMatches<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Iterator for FileUrlMatches<'a> {
+    type Item = FileUrlMatch<'a>;
+
+    fn next(&mut self) -> Option<FileUrlMatch<'a>> {
+        let bytes = self.text.as_bytes();
+        while self.pos + 7 <= bytes.len() {
+            let start = self.pos;
+            self.pos += 1;
+            if !bytes[start..start + 7].eq_ignore_ascii_case(b"file://") {
+                continue;
+            }
+            if start > 0 && is_scheme_byte(bytes[start - 1]) {
+                continue;
+            }
+            let rest = &self.text[start..];
+            let len = rest.find(is_boundary).unwrap_or(rest.len());
+            let end = start + trimmed_len(&rest[..len], 7);
+            self.pos = end.max(start + 7);
+            return Some(FileUrlMatch {
+                span: start..end,
+                url: FileUrl::new(&self.text[start..end]),
+            });
+        }
+        self.pos = bytes.len();
+        None
+    }
+}
+
+/// Whether `b` can be part of a URL scheme, so a `file://` after it
+/// is the end of some other scheme.
+fn is_scheme_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'+' || b == b'-' || b == b'.'
+}
+
+/// Whether `c` ends a URL in running text.
+fn is_boundary(c: char) -> bool {
+    c.is_whitespace() || c.is_control() || matches!(c, '"' | '\'' | '`' | '<' | '>')
+}
+
+/// The length of `url` without the trailing characters that more
+/// likely end the surrounding sentence. The first `keep` bytes, like
+/// `file://`, are kept.
+fn trimmed_len(url: &str, keep: usize) -> usize {
+    let mut url = url;
+    while url.len() > keep {
+        let last = url.as_bytes()[url.len() - 1];
+        let open = match last {
+            b'.' | b',' | b':' | b';' | b'!' | b'?' => {
+                url = strip_entity(url).unwrap_or(&url[..url.len() - 1]);
+                continue;
+            }
+            b')' => '(',
+            b']' => '[',
+            b'}' => '{',
+            _ => break,
+        };
+        let close = last as char;
+        if url.matches(open).count() >= url.matches(close).count() {
+            break;
+        }
+        url = &url[..url.len() - 1];
+    }
+    url.len().max(keep)
+}
+
+/// `url` without a trailing HTML entity like `&gt;`, if it has one.
+fn strip_entity(url: &str) -> Option<&str> {
+    let body = url.strip_suffix(';')?;
+    let amp = body.rfind('&')?;
+    let name = &body[amp + 1..];
+    let is_name = !name.is_empty()
+        && name
+            .strip_prefix('#')
+            .unwrap_or(name)
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric());
+    if is_name {
+        Some(&url[..amp])
+    } else {
+        None
+    }
+}
+
+/// Which kinds of path `linkify_paths` looks for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LinkifyOptions {
+    /// Absolute POSIX paths, like `/var/log/syslog`.
+    pub posix: bool,
+    /// Absolute Windows paths, like `C:\logs\a.txt`, `C:/logs` and
+    /// `\\server\share`. Also lets relative paths use `\`.
+    pub windows: bool,
+    /// Paths relative to the base directory that start with `./` or
+    /// `../`. Ones without the dot, like `src/main.rs`, can't be told
+    /// apart from prose like `and/or`, so they're left alone.
+    pub relative: bool,
+}
+
+impl Default for LinkifyOptions {
+    /// Every kind.
+    fn default() -> LinkifyOptions {
+        LinkifyOptions {
+            posix: true,
+            windows: true,
+            relative: true,
+        }
+    }
+}
+
+/// The kind of path found at some point of the text.
+#[derive(Clone, Copy)]
+enum Style {
+    Posix,
+    Windows,
+    Relative,
+}
+
+/// Rewrites the paths in `text` as file URLs, for reports that want
+/// clickable output. A path starts at the beginning of a word, or
+/// after an opening quote or bracket, and ends where `find_file_urls`
+/// would end a URL, trailing punctuation dropped the same way, so a
+/// path with a space in it is cut short at the space. Relative paths
+/// are resolved against `base`, which should be absolute; this only
+/// fails if `base` isn't UTF-8. Anything already a URL is left alone.
+///
+/// # Example:
+/// ```
+/// use std::path::Path;
+/// use file_url::{linkify_paths, LinkifyOptions};
+///
+/// let report = r"Wrote /tmp/out.txt and C:\logs\run1.txt; see (./notes/a%.md).";
+/// let linked = linkify_paths(report, Path::new("/home/me"), LinkifyOptions::default()).unwrap();
+/// assert_eq!(
+///     linked,
+///     "Wrote file:///tmp/out.txt and file:///C:/logs/run1.txt; see (file:///home/me/notes/a%25.md)."
+/// );
+/// ```
+pub fn linkify_paths(
+    text: &str,
+    base: &Path,
+    options: LinkifyOptions,
+) -> Result<String, UTFDecodeError> {
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut i = 0;
+    let mut prev = None;
+    while let Some(c) = text[i..].chars().next() {
+        let rest = &text[i..];
+        let found = match prev {
+            Some(p) if !starts_word_after(p) => None,
+            _ => detect(rest, options),
+        };
+        match found {
+            Some((style, prefix)) => {
+                let len = rest.find(is_boundary).unwrap_or(rest.len());
+                let path = &rest[..trimmed_len(&rest[..len], prefix)];
+                out.push_str(&text[copied..i]);
+                out.push_str(&path_url(style, path, base, options)?);
+                i += path.len();
+                copied = i;
+                prev = path.chars().next_back();
+            }
+            None => {
+                i += c.len_utf8();
+                prev = Some(c);
+            }
+        }
+    }
+    out.push_str(&text[copied..]);
+    Ok(out)
+}
+
+/// Whether a path can start right after `c`.
+fn starts_word_after(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '"' | '\'' | '`' | '(' | '[' | '{' | '<' | '=')
+}
+
+/// The kind of path `rest` starts with, if any, and the length of the
+/// prefix that marks it.
+fn detect(rest: &str, options: LinkifyOptions) -> Option<(Style, usize)> {
+    let bytes = rest.as_bytes();
+    let separator = |b: u8| b == b'/' || (options.windows && b == b'\\');
+    if options.windows {
+        let drive = bytes.len() >= 3
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && separator(bytes[2]);
+        let unc = rest.starts_with("\\\\") && bytes.get(2).is_some_and(u8::is_ascii_alphanumeric);
+        if drive || unc {
+            return Some((Style::Windows, 3));
+        }
+    }
+    if options.relative {
+        let dots = bytes.iter().take(2).take_while(|&&b| b == b'.').count();
+        if dots > 0 && bytes.get(dots).is_some_and(|&b| separator(b)) {
+            return Some((Style::Relative, dots + 1));
+        }
+    }
+    let posix = options.posix
+        && bytes.first() == Some(&b'/')
+        && bytes
+            .get(1)
+            .is_some_and(|&b| b != b'/' && !is_boundary(b as char));
+    if posix {
+        return Some((Style::Posix, 1));
+    }
+    None
+}
+
+/// The file URL for the path `path` found in the text.
+fn path_url(
+    style: Style,
+    path: &str,
+    base: &Path,
+    options: LinkifyOptions,
+) -> Result<String, UTFDecodeError> {
+    match style {
+        Style::Posix => Ok(Platform::Posix.path_to_file_url(path)),
+        Style::Windows => Ok(Platform::Windows.path_to_file_url(path)),
+        Style::Relative => {
+            let mut full = base.to_path_buf();
+            let separator = |c: char| c == '/' || (options.windows && c == '\\');
+            for piece in path.split(separator) {
+                match piece {
+                    "" | "." => {}
+                    ".." => {
+                        full.pop();
+                    }
+                    _ => full.push(piece),
+                }
+            }
+            let mut url = full.to_file_url()?;
+            if path.ends_with(separator) && !url.ends_with('/') {
+                url.push('/');
+            }
+            Ok(url)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(text: &str) -> Vec<&str> {
+        find_file_urls(text).map(|m| &text[m.span]).collect()
+    }
+
+    #[test]
+    fn trims_surrounding_punctuation() {
+        let cases = [
+            ("at file:///a/b.", vec!["file:///a/b"]),
+            ("(file:///a/b(1))", vec!["file:///a/b(1)"]),
+            ("[x](file:///a)]", vec!["file:///a"]),
+            (
+                "<file:///a> and 'file:///b'",
+                vec!["file:///a", "file:///b"],
+            ),
+            (r#"<a href="FILE:///a%20b">"#, vec!["FILE:///a%20b"]),
+            (
+                "file:///a&gt; file:///b&#62;",
+                vec!["file:///a", "file:///b"],
+            ),
+            ("file:///a?!...\nfile:///b", vec!["file:///a", "file:///b"]),
+            ("see file://.", vec!["file://"]),
+            ("myfile:///a notfile:/b", vec![]),
+            ("`file://srv/share/x`", vec!["file://srv/share/x"]),
+        ];
+        for (text, expected) in cases.iter() {
+            assert_eq!(spans(text), *expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn reports_invalid_urls() {
+        let text = "an escape file:///%FF then file:///ok";
+        let found: Vec<_> = find_file_urls(text).collect();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].span, 10..21);
+        assert!(found[0].url.is_err());
+        assert_eq!(found[1].url.as_ref().unwrap().as_str(), "file:///ok");
+        let text = "ünïcödé file:///é";
+        assert_eq!(spans(text), vec!["file:///é"]);
+    }
+
+    #[test]
+    fn linkifies_each_style() {
+        let base = Path::new("/base/dir");
+        let all = LinkifyOptions::default();
+        let cases = [
+            ("see /etc/hosts.", "see file:///etc/hosts."),
+            ("a/b and/or // not /", "a/b and/or // not /"),
+            (
+                "(D:/x) [\\\\srv\\share]",
+                "(file:///D:/x) [file://srv/share]",
+            ),
+            (
+                "../up ./here/ ..\\win",
+                "file:///base/up file:///base/dir/here/ file:///base/win",
+            ),
+            ("file:///a http://h/b", "file:///a http://h/b"),
+            ("x=/é,", "x=file:///%C3%A9,"),
+        ];
+        for (text, linked) in cases.iter() {
+            assert_eq!(linkify_paths(text, base, all).unwrap(), *linked, "{}", text);
+        }
+
+        let posix_only = LinkifyOptions {
+            windows: false,
+            relative: false,
+            ..all
+        };
+        let text = "C:\\x ./y /z";
+        assert_eq!(
+            linkify_paths(text, base, posix_only).unwrap(),
+            "C:\\x ./y file:///z"
+        );
+        let no_posix = LinkifyOptions {
+            posix: false,
+            ..all
+        };
+        assert_eq!(
+            linkify_paths(text, base, no_posix).unwrap(),
+            "file:///C:/x file:///base/dir/y /z"
+        );
+    }
+}
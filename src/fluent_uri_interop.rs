@@ -0,0 +1,189 @@
+//! Conversions to and from `fluent_uri::Uri`, behind the `fluent-uri`
+//! feature.
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::string::FromUtf8Error;
+
+use fluent_uri::error::ParseError;
+use fluent_uri::Uri;
+
+use crate::{
+    file_url_to_pathbuf, FileUrl, FileUrlBuf, FileUrlParseError, PathFileUrlExt, UTFDecodeError,
+};
+
+/// Error converting between paths and `fluent_uri::Uri`.
+#[derive(Debug)]
+pub enum FluentUriError {
+    /// The path isn't valid UTF-8.
+    Encode(UTFDecodeError),
+    /// The URI's path doesn't decode to UTF-8.
+    Decode(FromUtf8Error),
+    /// The `fluent-uri` crate rejected the URI.
+    Invalid(ParseError),
+    /// The URI doesn't use the `file:` scheme.
+    NotFileScheme(String),
+}
+
+impl fmt::Display for FluentUriError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FluentUriError::Encode(_) => write!(f, "Path is not UTF-8 compatible!"),
+            FluentUriError::Decode(_) => write!(f, "File URI does not decode to UTF-8!"),
+            FluentUriError::Invalid(_) => write!(f, "Invalid URI!"),
+            FluentUriError::NotFileScheme(scheme) => {
+                write!(f, "Expected a file URI, got {}:", scheme)
+            }
+        }
+    }
+}
+
+impl Error for FluentUriError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FluentUriError::Encode(e) => Some(e),
+            FluentUriError::Decode(e) => Some(e),
+            FluentUriError::Invalid(e) => Some(e),
+            FluentUriError::NotFileScheme(_) => None,
+        }
+    }
+}
+
+/// The file URL a URI names, without any query or fragment.
+fn file_url_of(uri: Uri<&str>) -> Result<String, FluentUriError> {
+    let scheme = uri.scheme().as_str();
+    if !scheme.eq_ignore_ascii_case("file") {
+        return Err(FluentUriError::NotFileScheme(scheme.to_string()));
+    }
+    let host = match uri.authority().map(|a| a.host()) {
+        Some(host) if !host.eq_ignore_ascii_case("localhost") => host,
+        _ => "",
+    };
+    Ok(format!("file://{}{}", host, uri.path().as_str()))
+}
+
+/// Method for converting `std::path::Path` and `std::path::PathBuf`
+/// to a `fluent_uri::Uri`.
+pub trait PathFluentUriExt {
+    /// Encodes the path as a file URI.
+    ///
+    /// # Example:
+    /// ```
+    /// use std::path::Path;
+    /// use file_url::PathFluentUriExt;
+    ///
+    /// let uri = Path::new("/srv/a b.txt").to_fluent_uri().unwrap();
+    /// assert_eq!(uri.as_str(), "file:///srv/a%20b.txt");
+    /// ```
+    fn to_fluent_uri(&self) -> Result<Uri<String>, FluentUriError>;
+}
+
+/// Method for constructing a `std::path::PathBuf` from a
+/// `fluent_uri::Uri`.
+pub trait PathFromFluentUriExt: Sized {
+    /// Decodes the path of a `file:` URI, ignoring any query or
+    /// fragment. Pass a `Uri<String>` with `uri.borrow()`.
+    ///
+    /// # Example:
+    /// ```
+    /// use std::path::PathBuf;
+    /// use fluent_uri::Uri;
+    /// use file_url::PathFromFluentUriExt;
+    ///
+    /// let uri = Uri::parse("file:///srv/a%20b.txt").unwrap();
+    /// assert_eq!(PathBuf::from_fluent_uri(uri).unwrap(), PathBuf::from("/srv/a b.txt"));
+    /// ```
+    fn from_fluent_uri(uri: Uri<&str>) -> Result<Self, FluentUriError>;
+}
+
+impl PathFluentUriExt for Path {
+    fn to_fluent_uri(&self) -> Result<Uri<String>, FluentUriError> {
+        let file_url = self.to_file_url().map_err(FluentUriError::Encode)?;
+        Uri::parse(file_url.as_str())
+            .map(|uri| uri.to_owned())
+            .map_err(FluentUriError::Invalid)
+    }
+}
+
+impl PathFromFluentUriExt for PathBuf {
+    fn from_fluent_uri(uri: Uri<&str>) -> Result<PathBuf, FluentUriError> {
+        file_url_to_pathbuf(&file_url_of(uri)?).map_err(FluentUriError::Decode)
+    }
+}
+
+/// Borrows the URL's string, without copying it.
+impl<'a> TryFrom<&'a FileUrl> for Uri<&'a str> {
+    type Error = ParseError;
+
+    fn try_from(file_url: &'a FileUrl) -> Result<Uri<&'a str>, ParseError> {
+        Uri::parse(file_url.as_str())
+    }
+}
+
+impl TryFrom<FileUrlBuf> for Uri<String> {
+    type Error = ParseError<String>;
+
+    fn try_from(file_url: FileUrlBuf) -> Result<Uri<String>, ParseError<String>> {
+        Uri::parse(file_url.into_string())
+    }
+}
+
+impl TryFrom<Uri<&str>> for FileUrlBuf {
+    type Error = FileUrlParseError;
+
+    fn try_from(uri: Uri<&str>) -> Result<FileUrlBuf, FileUrlParseError> {
+        match file_url_of(uri) {
+            Ok(url) => FileUrlBuf::new(url),
+            Err(_) => Err(FileUrlParseError::new("Not an absolute file URL!")),
+        }
+    }
+}
+
+impl TryFrom<&Uri<String>> for FileUrlBuf {
+    type Error = FileUrlParseError;
+
+    fn try_from(uri: &Uri<String>) -> Result<FileUrlBuf, FileUrlParseError> {
+        FileUrlBuf::try_from(uri.borrow())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_round_trip() {
+        let path = Path::new("/gi>/some & what.whtvr");
+        let uri = path.to_fluent_uri().unwrap();
+        assert_eq!(PathBuf::from_fluent_uri(uri.borrow()).unwrap(), path);
+    }
+
+    #[test]
+    fn file_url_types_round_trip() {
+        let file_url = FileUrl::new("file://server/a/b%20c").unwrap();
+        let uri = Uri::try_from(file_url).unwrap();
+        assert_eq!(uri.authority().unwrap().host(), "server");
+        assert_eq!(FileUrlBuf::try_from(uri).unwrap().as_file_url(), file_url);
+
+        let owned = Uri::try_from(file_url.to_file_url_buf()).unwrap();
+        assert_eq!(
+            FileUrlBuf::try_from(&owned).unwrap().as_file_url(),
+            file_url
+        );
+
+        let local = Uri::parse("file://localhost/x?q#f").unwrap();
+        assert_eq!(FileUrlBuf::try_from(local).unwrap().as_str(), "file:///x");
+    }
+
+    #[test]
+    fn rejects_other_schemes_and_bad_uris() {
+        let https = Uri::parse("https://example.com/a").unwrap();
+        assert!(matches!(
+            PathBuf::from_fluent_uri(https),
+            Err(FluentUriError::NotFileScheme(ref s)) if s == "https"
+        ));
+        // FileUrl allows a literal space, RFC 3986 doesn't.
+        assert!(Uri::try_from(FileUrl::new("file:///a b").unwrap()).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! Line and column fragments, like `#L10`, for deep links into files.
+use crate::{FileUrl, FileUrlBuf};
+
+/// A position in a text file, as named by a URL fragment. Lines and
+/// columns count from one, as editors and compilers show them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FragmentPosition {
+    /// The line.
+    pub line: u32,
+    /// The column, if the fragment gives one.
+    pub column: Option<u32>,
+}
+
+impl FragmentPosition {
+    /// Reads a fragment, without its `#`, in one of the forms tools
+    /// write: `L10` and `L10,5` as VS Code and GitHub do, `L10C5`,
+    /// `10`, `10:5`, and `line=10` or `line=10,5`. A range like
+    /// `L10-L20` gives its start. Anything else is `None`.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::FragmentPosition;
+    ///
+    /// let pos = FragmentPosition::parse("L10,5").unwrap();
+    /// assert_eq!((pos.line, pos.column), (10, Some(5)));
+    /// assert_eq!(FragmentPosition::parse("line=7").unwrap().line, 7);
+    /// assert!(FragmentPosition::parse("section-2").is_none());
+    /// ```
+    pub fn parse(fragment: &str) -> Option<FragmentPosition> {
+        let rest = fragment
+            .strip_prefix("line=")
+            .or_else(|| fragment.strip_prefix('L'))
+            .unwrap_or(fragment);
+        let rest = rest.split('-').next().unwrap_or_default();
+        let (line, column) = match rest.find([',', ':', 'C']) {
+            Some(i) => (&rest[..i], Some(&rest[i + 1..])),
+            None => (rest, None),
+        };
+        let line = number(line)?;
+        let column = match column {
+            Some(column) => Some(number(column)?),
+            None => None,
+        };
+        Some(FragmentPosition { line, column })
+    }
+}
+
+/// A positive decimal number, with nothing else around it.
+fn number(s: &str) -> Option<u32> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok().filter(|&n| n > 0)
+}
+
+impl FileUrl {
+    /// Splits off the fragment: everything after the first `#`. The
+    /// crate's own encoder writes a `#` in a file name as `%23`, so a
+    /// bare one here usually starts a fragment, though leniently
+    /// parsed URLs may use it in names too.
+    ///
+    /// # Example:
+    /// ```
+    /// use std::path::Path;
+    /// use file_url::FileUrl;
+    ///
+    /// let url = FileUrl::new("file:///src/main.rs#L10").unwrap();
+    /// let (file, fragment) = url.split_fragment();
+    /// assert_eq!(file.to_path_buf(), Path::new("/src/main.rs"));
+    /// assert_eq!(fragment, Some("L10"));
+    /// ```
+    pub fn split_fragment(&self) -> (&FileUrl, Option<&str>) {
+        let url = self.as_str();
+        match url.find('#') {
+            // Cutting at an ASCII `#` leaves a valid URL.
+            Some(i) => (FileUrl::from_str_unchecked(&url[..i]), Some(&url[i + 1..])),
+            None => (self, None),
+        }
+    }
+
+    /// The line, and maybe column, that the fragment names, if it's in
+    /// one of the forms `FragmentPosition::parse` reads.
+    pub fn fragment_position(&self) -> Option<FragmentPosition> {
+        FragmentPosition::parse(self.split_fragment().1?)
+    }
+}
+
+impl FileUrlBuf {
+    /// The URL with its fragment replaced by `#L<line>`, the form
+    /// VS Code and GitHub use, so editors and terminals open the file
+    /// at that line.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::FileUrlBuf;
+    ///
+    /// let url = FileUrlBuf::from_path("/src/main.rs").unwrap();
+    /// assert_eq!(url.with_fragment_line(10).as_str(), "file:///src/main.rs#L10");
+    /// ```
+    pub fn with_fragment_line(self, line: u32) -> FileUrlBuf {
+        self.with_fragment(&format!("L{}", line))
+    }
+
+    /// The URL with its fragment replaced by `#L<line>,<column>`.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::FileUrlBuf;
+    ///
+    /// let url = FileUrlBuf::from_path("/src/main.rs").unwrap();
+    /// let url = url.with_fragment_position(10, 5);
+    /// assert_eq!(url.as_str(), "file:///src/main.rs#L10,5");
+    /// assert_eq!(url.fragment_position().unwrap().column, Some(5));
+    /// ```
+    pub fn with_fragment_position(self, line: u32, column: u32) -> FileUrlBuf {
+        self.with_fragment(&format!("L{},{}", line, column))
+    }
+
+    fn with_fragment(self, fragment: &str) -> FileUrlBuf {
+        let mut url = self.into_string();
+        if let Some(i) = url.find('#') {
+            url.truncate(i);
+        }
+        url.push('#');
+        url.push_str(fragment);
+        // The fragment is ASCII letters, digits and punctuation.
+        FileUrlBuf::from_string_unchecked(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_common_forms() {
+        let cases = [
+            ("L10", Some((10, None))),
+            ("L10,5", Some((10, Some(5)))),
+            ("L10C5", Some((10, Some(5)))),
+            ("10:5", Some((10, Some(5)))),
+            ("line=10,5", Some((10, Some(5)))),
+            ("L10-L20", Some((10, None))),
+            ("L0", None),
+            ("L", None),
+            ("L10,", None),
+            ("L+1", None),
+            ("top", None),
+        ];
+        for (fragment, expected) in cases.iter() {
+            let pos = FragmentPosition::parse(fragment).map(|p| (p.line, p.column));
+            assert_eq!(pos, *expected, "{}", fragment);
+        }
+    }
+
+    #[test]
+    fn replaces_fragments() {
+        let url = FileUrlBuf::new("file:///a#old".to_string()).unwrap();
+        let url = url.with_fragment_line(3);
+        assert_eq!(url.as_str(), "file:///a#L3");
+        assert_eq!(url.split_fragment().0.as_str(), "file:///a");
+        let url = url.with_fragment_position(4, 2);
+        assert_eq!(url.as_str(), "file:///a#L4,2");
+        let plain = FileUrl::new("file:///a").unwrap();
+        assert_eq!(plain.split_fragment(), (plain, None));
+        assert!(plain.fragment_position().is_none());
+    }
+}
@@ -0,0 +1,108 @@
+//! `std::fs` helpers that take file URLs, for quick scripting use.
+//! URLs that don't decode, or that aren't file URLs for this machine,
+//! come back as `io::ErrorKind::InvalidInput` errors, so everything
+//! here is plain `io::Result`.
+//!
+//! # Example:
+//! ```no_run
+//! use std::fs::File;
+//! use file_url::fs_ext::{metadata_from_url, read_to_string_from_url, FileUrlOpenExt};
+//!
+//! # fn main() -> std::io::Result<()> {
+//! let hosts = read_to_string_from_url("file:///etc/hosts")?;
+//! let len = metadata_from_url("file:///etc/hosts")?.len();
+//! let file = File::open_file_url("file:///etc/hosts")?;
+//! # Ok(())
+//! # }
+//! ```
+use std::fs::{self, File, Metadata};
+use std::io;
+
+use crate::file_url_to_io_path;
+
+/// Opening a `std::fs::File` by URL.
+pub trait FileUrlOpenExt: Sized {
+    /// Opens the file a URL points to in read-only mode.
+    fn open_file_url(file_url: &str) -> io::Result<Self>;
+
+    /// Creates (or truncates) the file a URL points to, opened for
+    /// writing.
+    fn create_file_url(file_url: &str) -> io::Result<Self>;
+}
+
+impl FileUrlOpenExt for File {
+    fn open_file_url(file_url: &str) -> io::Result<File> {
+        File::open(file_url_to_io_path(file_url)?)
+    }
+
+    fn create_file_url(file_url: &str) -> io::Result<File> {
+        File::create(file_url_to_io_path(file_url)?)
+    }
+}
+
+/// Reads the whole file a URL points to as bytes.
+pub fn read_from_url(file_url: &str) -> io::Result<Vec<u8>> {
+    fs::read(file_url_to_io_path(file_url)?)
+}
+
+/// Reads the whole file a URL points to as a string.
+pub fn read_to_string_from_url(file_url: &str) -> io::Result<String> {
+    fs::read_to_string(file_url_to_io_path(file_url)?)
+}
+
+/// Queries metadata for the file or directory a URL points to.
+pub fn metadata_from_url(file_url: &str) -> io::Result<Metadata> {
+    fs::metadata(file_url_to_io_path(file_url)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PathFileUrlExt;
+    use std::io::Write;
+
+    #[test]
+    fn create_then_read() {
+        let path = std::env::temp_dir().join(format!("file_url fs_ext {}.txt", std::process::id()));
+        let file_url = path.to_file_url().unwrap();
+
+        File::create_file_url(&file_url)
+            .unwrap()
+            .write_all(b"hi there")
+            .unwrap();
+        assert_eq!(read_to_string_from_url(&file_url).unwrap(), "hi there");
+        assert_eq!(read_from_url(&file_url).unwrap(), b"hi there");
+        assert_eq!(metadata_from_url(&file_url).unwrap().len(), 8);
+        assert!(File::open_file_url(&file_url).is_ok());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn bad_url_is_invalid_input() {
+        let err = metadata_from_url("file:///%FF").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn only_local_file_urls_are_opened() {
+        let mut rejected = vec!["http://evil.com/etc/passwd", "/etc/passwd", "etc/passwd"];
+        if !cfg!(windows) {
+            rejected.push("file://server/share/x");
+            rejected.push("file:////server/share/x");
+        }
+        for url in rejected.iter() {
+            let err = File::open_file_url(url).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{}", url);
+        }
+
+        let local = std::env::temp_dir().join(format!("file_url localhost {}", std::process::id()));
+        fs::write(&local, b"x").unwrap();
+        let url = local
+            .to_file_url()
+            .unwrap()
+            .replacen("file://", "file://localhost", 1);
+        assert_eq!(read_from_url(&url).unwrap(), b"x");
+        fs::remove_file(&local).unwrap();
+    }
+}
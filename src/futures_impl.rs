@@ -0,0 +1,84 @@
+//! `Stream` adapters, behind the `futures` feature.
+use std::path::{Path, PathBuf};
+use std::string::FromUtf8Error;
+
+use futures::stream::{Map, Stream, StreamExt};
+
+use crate::{file_url_to_pathbuf, FileUrlBuf, FromPathError};
+
+/// Stream returned by `FileUrlStreamExt::to_file_urls`.
+pub type ToFileUrls<S> = Map<S, fn(<S as Stream>::Item) -> Result<FileUrlBuf, FromPathError>>;
+
+/// Stream returned by `FileUrlStreamExt::to_paths`.
+pub type ToPaths<S> = Map<S, fn(<S as Stream>::Item) -> Result<PathBuf, FromUtf8Error>>;
+
+fn decode<S: AsRef<str>>(url: S) -> Result<PathBuf, FromUtf8Error> {
+    file_url_to_pathbuf(url.as_ref())
+}
+
+/// Conversion combinators for streams of paths or file URLs, e.g.
+/// from a directory watcher or crawler.
+///
+/// # Example:
+/// ```
+/// use std::path::PathBuf;
+/// use futures::executor::block_on;
+/// use futures::stream::{self, StreamExt};
+/// use file_url::FileUrlStreamExt;
+///
+/// let paths = stream::iter(vec![PathBuf::from("/a b"), PathBuf::from("/c")]);
+/// let urls: Vec<_> = block_on(paths.to_file_urls().collect());
+/// assert_eq!(urls[0].as_ref().unwrap().as_str(), "file:///a%20b");
+///
+/// let urls = stream::iter(vec!["file:///a%20b"]);
+/// let paths: Vec<_> = block_on(urls.to_paths().collect());
+/// assert_eq!(paths[0].as_ref().unwrap(), &PathBuf::from("/a b"));
+/// ```
+pub trait FileUrlStreamExt: Stream + Sized {
+    /// Maps each path to a `FileUrlBuf`, yielding an error for paths
+    /// that aren't absolute or aren't valid UTF-8.
+    fn to_file_urls(self) -> ToFileUrls<Self>
+    where
+        Self::Item: AsRef<Path>,
+    {
+        self.map(FileUrlBuf::from_path::<Self::Item> as fn(_) -> _)
+    }
+
+    /// Maps each file URL (a `FileUrlBuf`, `String`, `&str`, ...) to
+    /// a `PathBuf`, yielding an error for URLs that don't decode to
+    /// UTF-8.
+    fn to_paths(self) -> ToPaths<Self>
+    where
+        Self::Item: AsRef<str>,
+    {
+        self.map(decode::<Self::Item> as fn(_) -> _)
+    }
+}
+
+impl<S: Stream> FileUrlStreamExt for S {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::stream;
+
+    #[test]
+    fn round_trip_through_streams() {
+        let paths = vec![PathBuf::from("/gi>/some & what"), PathBuf::from("/x")];
+        let urls = stream::iter(paths.clone())
+            .to_file_urls()
+            .map(Result::unwrap)
+            .to_paths()
+            .map(Result::unwrap);
+        assert_eq!(block_on(urls.collect::<Vec<_>>()), paths);
+    }
+
+    #[test]
+    fn errors_are_per_item() {
+        let urls = stream::iter(vec!["file:///%FF", "file:///ok"]);
+        let results: Vec<_> = block_on(urls.to_paths().collect());
+        assert!(results[0].is_err());
+        assert_eq!(results[1].as_ref().unwrap(), &PathBuf::from("/ok"));
+    }
+}
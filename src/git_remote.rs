@@ -0,0 +1,184 @@
+//! File URLs written for git remotes, which read `file://` their own
+//! way: the host is ignored, so a UNC share needs four slashes, and a
+//! drive letter must come after the third.
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use crate::platform::split_authority;
+use crate::{FileUrl, PathFileUrlExt, Platform, UTFDecodeError};
+
+/// Why a URL won't work as a git remote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitRemoteError {
+    /// The scheme isn't `file:`.
+    NotFileUrl,
+    /// The scheme is `FILE:` or similar; git only knows `file:`.
+    SchemeCase,
+    /// `file:/path` or `file:path`, which git reads as the `scp`-style
+    /// remote `path` on a host named `file`.
+    MissingSlashes,
+    /// `file://C:/path`, with the drive where the host goes.
+    DriveAsHost,
+    /// `file://server/path`. Git drops the host and opens `/path` on
+    /// this machine; a UNC share is written `file:////server/path`.
+    HostIgnored,
+}
+
+impl fmt::Display for GitRemoteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GitRemoteError::NotFileUrl => write!(f, "Git remote is not a file URL!"),
+            GitRemoteError::SchemeCase => write!(f, "Git only reads a lowercase file: scheme!"),
+            GitRemoteError::MissingSlashes => {
+                write!(f, "Git reads a file URL without // as a host named file!")
+            }
+            GitRemoteError::DriveAsHost => {
+                write!(f, "Git needs the drive letter after file:///, not file://!")
+            }
+            GitRemoteError::HostIgnored => {
+                write!(
+                    f,
+                    "Git ignores the host of a file URL; shares need file:////!"
+                )
+            }
+        }
+    }
+}
+
+impl Error for GitRemoteError {}
+
+/// Checks that `url` names the same location to git as it does to
+/// everything else, saying why not if it doesn't. `file://localhost/`
+/// and the four-slash UNC form `file:////server/share` are fine.
+///
+/// # Example:
+/// ```
+/// use file_url::{check_git_remote, GitRemoteError};
+///
+/// assert!(check_git_remote("file:///C:/repos/app.git").is_ok());
+/// assert!(check_git_remote("file:////server/share/app.git").is_ok());
+/// assert_eq!(check_git_remote("file://C:/repos/app.git"), Err(GitRemoteError::DriveAsHost));
+/// assert_eq!(check_git_remote("file://server/share"), Err(GitRemoteError::HostIgnored));
+/// ```
+pub fn check_git_remote(url: &str) -> Result<(), GitRemoteError> {
+    let rest = match url.get(..5) {
+        Some("file:") => &url[5..],
+        Some(scheme) if scheme.eq_ignore_ascii_case("file:") => {
+            return Err(GitRemoteError::SchemeCase)
+        }
+        _ => return Err(GitRemoteError::NotFileUrl),
+    };
+    let auth_and_path = rest
+        .strip_prefix("//")
+        .ok_or(GitRemoteError::MissingSlashes)?;
+    let host = match auth_and_path.find('/') {
+        Some(i) => &auth_and_path[..i],
+        None => auth_and_path,
+    };
+    if is_drive_host(host) {
+        Err(GitRemoteError::DriveAsHost)
+    } else if host.is_empty() || host.eq_ignore_ascii_case("localhost") {
+        Ok(())
+    } else {
+        Err(GitRemoteError::HostIgnored)
+    }
+}
+
+/// Whether `host` is really a drive, like `C:` or the old `C|`.
+fn is_drive_host(host: &str) -> bool {
+    let bytes = host.as_bytes();
+    bytes.len() == 2 && bytes[0].is_ascii_alphabetic() && (bytes[1] == b':' || bytes[1] == b'|')
+}
+
+impl FileUrl {
+    /// The URL rewritten so git reads it as naming the same location,
+    /// which `check_git_remote` accepts: `file:///C:/x` for
+    /// `file://C:/x`, `file:////server/share` for `file://server/share`,
+    /// and `file:///x` for `file:/x` and `file://localhost/x`.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::FileUrl;
+    ///
+    /// let url = FileUrl::new("file://server/share/app.git").unwrap();
+    /// assert_eq!(url.to_git_remote(), "file:////server/share/app.git");
+    /// let url = FileUrl::new("file://C:/repos/app.git").unwrap();
+    /// assert_eq!(url.to_git_remote(), "file:///C:/repos/app.git");
+    /// ```
+    pub fn to_git_remote(&self) -> String {
+        let (host, path) = split_authority(self.as_str());
+        if host.is_empty() {
+            format!("file://{}", path)
+        } else if is_drive_host(host) {
+            format!("file:///{}:{}", &host[..1], path)
+        } else {
+            format!("file:////{}{}", host, path)
+        }
+    }
+}
+
+/// The git remote URL for the repository at `path`, in the form
+/// `FileUrl::to_git_remote` writes.
+///
+/// # Example:
+/// ```
+/// use std::path::Path;
+/// use file_url::{check_git_remote, path_to_git_remote};
+///
+/// let url = path_to_git_remote(Path::new("/srv/git/app.git")).unwrap();
+/// assert_eq!(url, "file:///srv/git/app.git");
+/// assert!(check_git_remote(&url).is_ok());
+/// ```
+pub fn path_to_git_remote(path: &Path) -> Result<String, UTFDecodeError> {
+    let url = path.to_file_url()?;
+    Ok(FileUrl::from_str_unchecked(&url).to_git_remote())
+}
+
+impl Platform {
+    /// Like `path_to_git_remote`, for a path in this platform's syntax.
+    pub fn path_to_git_remote(self, path: &str) -> String {
+        let url = self.path_to_file_url(path);
+        FileUrl::from_str_unchecked(&url).to_git_remote()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remotes_pass_their_own_check() {
+        let cases = [
+            (r"C:\repos\my app.git", "file:///C:/repos/my%20app.git"),
+            (r"\\srv\share\app.git", "file:////srv/share/app.git"),
+        ];
+        for (path, url) in cases.iter() {
+            let remote = Platform::Windows.path_to_git_remote(path);
+            assert_eq!(remote, *url, "{}", path);
+            assert_eq!(check_git_remote(&remote), Ok(()));
+        }
+        for s in ["file:/x", "file://localhost/x", "file:////srv/x"].iter() {
+            let remote = FileUrl::new(s).unwrap().to_git_remote();
+            assert_eq!(check_git_remote(&remote), Ok(()), "{}", s);
+        }
+        let url = FileUrl::new("file://c|/x").unwrap();
+        assert_eq!(url.to_git_remote(), "file:///c:/x");
+    }
+
+    #[test]
+    fn check_explains_failures() {
+        let cases = [
+            ("https://example.com/a.git", GitRemoteError::NotFileUrl),
+            ("FILE:///x", GitRemoteError::SchemeCase),
+            ("file:/x", GitRemoteError::MissingSlashes),
+            ("file:C:/x", GitRemoteError::MissingSlashes),
+            ("file://c|/x", GitRemoteError::DriveAsHost),
+            ("file://srv", GitRemoteError::HostIgnored),
+        ];
+        for (url, error) in cases.iter() {
+            assert_eq!(check_git_remote(url), Err(*error), "{}", url);
+        }
+        assert!(check_git_remote("file://LOCALHOST/x").is_ok());
+    }
+}
@@ -0,0 +1,242 @@
+//! Glob patterns matched against file URLs.
+use crate::percent::decode;
+
+use crate::relative::UrlParts;
+use crate::types::hides_dots;
+use crate::{FileUrl, FileUrlParseError, Segments};
+
+/// One character position in a segment pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    /// `?`, exactly one character.
+    Any,
+    /// `*`, any run of characters.
+    Star,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    /// `**` standing alone, zero or more whole segments.
+    AnyDepth,
+    Pattern(Vec<Token>),
+}
+
+/// A glob written as a file URL. `*` matches within a segment, `?`
+/// matches one character, and a segment of just `**` matches any
+/// number of segments. Segments are compared decoded, so `%20` in the
+/// pattern matches a space in the URL whichever way the URL spells
+/// it. An escaped wildcard like `%2A` matches only a literal `*`.
+/// Wildcards never match a separator, so `file:///data/*.csv` doesn't
+/// match `file:///data/a%2Fb.csv`, which decodes to the path
+/// `/data/a/b.csv`; on Windows that goes for `%5C` too.
+///
+/// # Example:
+/// ```
+/// use file_url::{FileUrl, FileUrlGlob};
+///
+/// let glob = FileUrlGlob::new("file:///data/**/*%20report.csv").unwrap();
+/// assert!(glob.matches(FileUrl::new("file:///data/2021/q1/annual report.csv").unwrap()));
+/// assert!(glob.matches(FileUrl::new("file:///data/x%20report.csv").unwrap()));
+/// assert!(!glob.matches(FileUrl::new("file:///other/x%20report.csv").unwrap()));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileUrlGlob {
+    pattern: String,
+    host: String,
+    segments: Vec<Segment>,
+}
+
+impl FileUrlGlob {
+    /// Parses a glob. The pattern must be an absolute file URL apart
+    /// from its wildcards, and its escapes must decode to UTF-8.
+    pub fn new(pattern: &str) -> Result<FileUrlGlob, FileUrlParseError> {
+        let parts = UrlParts::parse(pattern)
+            .ok_or_else(|| FileUrlParseError::new("Glob is not an absolute file URL!"))?;
+        let segments = parts
+            .segments
+            .iter()
+            .map(|seg| parse_segment(seg))
+            .collect::<Option<Vec<Segment>>>()
+            .ok_or_else(|| FileUrlParseError::new("Glob does not decode to UTF-8!"))?;
+
+        Ok(FileUrlGlob {
+            pattern: pattern.to_string(),
+            host: parts.host.to_string(),
+            segments,
+        })
+    }
+
+    /// The pattern as it was given.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Whether `url` matches the pattern. Dot segments in the URL are
+    /// resolved first, so `file:///data/../etc/x.csv` does not match
+    /// `file:///data/**`. Encoded ones like `%2e%2e` can't be, so a URL
+    /// with one, or with a segment that decodes to several, matches
+    /// nothing.
+    pub fn matches(&self, url: &FileUrl) -> bool {
+        let parts = url.parts();
+        if !self.host.eq_ignore_ascii_case(parts.host)
+            || parts.segments.iter().any(|s| hides_dots(s, cfg!(windows)))
+        {
+            return false;
+        }
+        // FileUrl validation already checked every segment decodes.
+        let segments: Segments<String> = parts
+            .segments
+            .iter()
+            .map(|seg| decode(seg).unwrap().into_owned())
+            .collect();
+        match_segments(&self.segments, &segments)
+    }
+}
+
+fn parse_segment(raw: &str) -> Option<Segment> {
+    if raw == "**" {
+        return Some(Segment::AnyDepth);
+    }
+
+    let mut tokens = Vec::new();
+    let mut literal_start = 0;
+    for (i, c) in raw.char_indices() {
+        let wildcard = match c {
+            '*' => Token::Star,
+            '?' => Token::Any,
+            _ => continue,
+        };
+        push_literal(&mut tokens, &raw[literal_start..i])?;
+        tokens.push(wildcard);
+        literal_start = i + 1;
+    }
+    push_literal(&mut tokens, &raw[literal_start..])?;
+    Some(Segment::Pattern(tokens))
+}
+
+/// Decodes a run of pattern text between wildcards, so that escaped
+/// wildcards come out as literals.
+fn push_literal(tokens: &mut Vec<Token>, raw: &str) -> Option<()> {
+    let decoded = decode(raw).ok()?;
+    tokens.extend(decoded.chars().map(Token::Literal));
+    Some(())
+}
+
+fn match_segments(pattern: &[Segment], segments: &[String]) -> bool {
+    match pattern.split_first() {
+        None => segments.is_empty(),
+        Some((Segment::AnyDepth, rest)) => {
+            (0..=segments.len()).any(|skip| match_segments(rest, &segments[skip..]))
+        }
+        Some((Segment::Pattern(tokens), rest)) => match segments.split_first() {
+            Some((seg, remaining)) => match_tokens(tokens, seg) && match_segments(rest, remaining),
+            None => false,
+        },
+    }
+}
+
+/// Wildcard match within one segment. An empty segment (from a
+/// trailing slash) only matches an empty pattern, so `file:///a/*`
+/// doesn't match the directory `file:///a/` itself.
+fn match_tokens(tokens: &[Token], seg: &str) -> bool {
+    if seg.is_empty() {
+        return tokens.is_empty();
+    }
+
+    let chars: Vec<char> = seg.chars().collect();
+    let (mut t, mut c) = (0, 0);
+    // Position of the last `*` seen, and the character it was tried at.
+    let mut backtrack = None;
+    while c < chars.len() {
+        match tokens.get(t) {
+            Some(Token::Star) => {
+                backtrack = Some((t, c));
+                t += 1;
+                continue;
+            }
+            Some(Token::Any) => {
+                t += 1;
+                c += 1;
+                continue;
+            }
+            Some(Token::Literal(l)) if *l == chars[c] => {
+                t += 1;
+                c += 1;
+                continue;
+            }
+            _ => {}
+        }
+        match backtrack {
+            Some((star, tried)) => {
+                t = star + 1;
+                c = tried + 1;
+                backtrack = Some((star, c));
+            }
+            None => return false,
+        }
+    }
+    tokens[t..].iter().all(|tok| *tok == Token::Star)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, url: &str) -> bool {
+        FileUrlGlob::new(pattern)
+            .unwrap()
+            .matches(FileUrl::new(url).unwrap())
+    }
+
+    #[test]
+    fn wildcards_within_segment() {
+        assert!(matches("file:///a/*.csv", "file:///a/x.csv"));
+        assert!(matches("file:///a/*.csv", "file:///a/.csv"));
+        assert!(!matches("file:///a/*.csv", "file:///a/b/x.csv"));
+        assert!(matches("file:///a/?.txt", "file:///a/%C3%A9.txt"));
+        assert!(!matches("file:///a/?.txt", "file:///a/ab.txt"));
+        assert!(matches("file:///a/*b*c", "file:///a/xbybzc"));
+        assert!(!matches("file:///a/*", "file:///a/"));
+        assert!(matches("file:///a/*/", "file:///a/b/"));
+    }
+
+    #[test]
+    fn any_depth() {
+        assert!(matches("file:///a/**/x", "file:///a/x"));
+        assert!(matches("file:///a/**/x", "file:///a/b/c/x"));
+        assert!(matches("file:///a/**", "file:///a/b/"));
+        assert!(!matches("file:///a/**", "file:///a/../b"));
+        assert!(!matches("file:///a/**", "file:///a/%2e%2e/b"));
+        assert!(!matches("file:///a/**", "file:///a/.%2E/b"));
+        assert!(!matches("file:///a/**/x", "file:///a/b/y"));
+    }
+
+    #[test]
+    fn encoding_is_normalized() {
+        assert!(matches("file:///my%20dir/*", "file:///my dir/f"));
+        assert!(matches("file:///my dir/*", "file:///my%20dir/f"));
+        assert!(matches("file:///a/%2A", "file:///a/*"));
+        assert!(!matches("file:///a/%2A", "file:///a/b"));
+    }
+
+    #[test]
+    fn wildcards_skip_encoded_separators() {
+        assert!(!matches("file:///data/*.csv", "file:///data/a%2Fb.csv"));
+        assert!(!matches("file:///data/a?b.csv", "file:///data/a%2fb.csv"));
+        assert!(!matches("file:///data/**/*", "file:///data/a%2F"));
+        assert!(!matches("file:///data/*%2F*.csv", "file:///data/a%2Fb.csv"));
+        assert_eq!(
+            matches("file:///data/*.csv", "file:///data/a%5Cb.csv"),
+            !cfg!(windows)
+        );
+    }
+
+    #[test]
+    fn hosts_and_bad_patterns() {
+        assert!(matches("file://localhost/a/*", "file:///a/b"));
+        assert!(!matches("file://server/a/*", "file:///a/b"));
+        assert!(FileUrlGlob::new("data/*.csv").is_err());
+        assert!(FileUrlGlob::new("file:///%FF/*").is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! Conversions to and from `http::Uri`, behind the `http` feature.
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::string::FromUtf8Error;
+
+use http::uri::{InvalidUri, Uri};
+
+use crate::{
+    file_url_to_pathbuf, FileUrl, FileUrlBuf, FileUrlParseError, PathFileUrlExt, UTFDecodeError,
+};
+
+/// Error converting between paths and `http::Uri`.
+#[derive(Debug)]
+pub enum UriError {
+    /// The path isn't valid UTF-8.
+    Encode(UTFDecodeError),
+    /// The URI's path doesn't decode to UTF-8.
+    Decode(FromUtf8Error),
+    /// The `http` crate rejected the URI.
+    Invalid(InvalidUri),
+    /// The URI doesn't use the `file:` scheme.
+    NotFileScheme(Option<String>),
+}
+
+impl fmt::Display for UriError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UriError::Encode(_) => write!(f, "Path is not UTF-8 compatible!"),
+            UriError::Decode(_) => write!(f, "File URI does not decode to UTF-8!"),
+            UriError::Invalid(_) => write!(f, "Invalid URI!"),
+            UriError::NotFileScheme(Some(scheme)) => {
+                write!(f, "Expected a file URI, got {}:", scheme)
+            }
+            UriError::NotFileScheme(None) => write!(f, "Expected a file URI, got no scheme!"),
+        }
+    }
+}
+
+impl Error for UriError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UriError::Encode(e) => Some(e),
+            UriError::Decode(e) => Some(e),
+            UriError::Invalid(e) => Some(e),
+            UriError::NotFileScheme(_) => None,
+        }
+    }
+}
+
+/// `http::Uri` insists on an authority, so local file URLs are given
+/// the `localhost` one, which RFC 8089 treats the same as none.
+fn with_authority(file_url: &str) -> String {
+    match file_url.get(..8) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("file:///") => {
+            format!("file://localhost/{}", &file_url[8..])
+        }
+        _ => file_url.to_string(),
+    }
+}
+
+/// The file URL an `http::Uri` names, without any query.
+fn file_url_of(uri: &Uri) -> Result<String, UriError> {
+    match uri.scheme_str() {
+        Some(scheme) if scheme.eq_ignore_ascii_case("file") => {}
+        scheme => return Err(UriError::NotFileScheme(scheme.map(String::from))),
+    }
+    let host = match uri.authority().map(|a| a.as_str()) {
+        Some(host) if !host.eq_ignore_ascii_case("localhost") => host,
+        _ => "",
+    };
+    Ok(format!("file://{}{}", host, uri.path()))
+}
+
+/// Method for converting `std::path::Path` and `std::path::PathBuf`
+/// to a `http::Uri`.
+pub trait PathUriExt {
+    /// Encodes the path as a file URL with a `localhost` authority.
+    ///
+    /// # Example:
+    /// ```
+    /// use std::path::Path;
+    /// use file_url::PathUriExt;
+    ///
+    /// let uri = Path::new("/srv/a b.txt").to_uri().unwrap();
+    /// assert_eq!(uri.to_string(), "file://localhost/srv/a%20b.txt");
+    /// ```
+    fn to_uri(&self) -> Result<Uri, UriError>;
+}
+
+/// Method for constructing a `std::path::PathBuf` from a `http::Uri`.
+pub trait PathFromUriExt: Sized {
+    /// Decodes the path of a `file:` URI, ignoring any query.
+    ///
+    /// # Example:
+    /// ```
+    /// use std::path::PathBuf;
+    /// use file_url::PathFromUriExt;
+    ///
+    /// let uri: http::Uri = "file://localhost/srv/a%20b.txt".parse().unwrap();
+    /// assert_eq!(PathBuf::from_uri(&uri).unwrap(), PathBuf::from("/srv/a b.txt"));
+    /// ```
+    fn from_uri(uri: &Uri) -> Result<Self, UriError>;
+}
+
+impl PathUriExt for Path {
+    fn to_uri(&self) -> Result<Uri, UriError> {
+        let file_url = self.to_file_url().map_err(UriError::Encode)?;
+        with_authority(&file_url).parse().map_err(UriError::Invalid)
+    }
+}
+
+impl PathFromUriExt for PathBuf {
+    fn from_uri(uri: &Uri) -> Result<PathBuf, UriError> {
+        file_url_to_pathbuf(&file_url_of(uri)?).map_err(UriError::Decode)
+    }
+}
+
+impl TryFrom<&FileUrl> for Uri {
+    type Error = InvalidUri;
+
+    fn try_from(file_url: &FileUrl) -> Result<Uri, InvalidUri> {
+        with_authority(file_url.as_str()).parse()
+    }
+}
+
+impl TryFrom<FileUrlBuf> for Uri {
+    type Error = InvalidUri;
+
+    fn try_from(file_url: FileUrlBuf) -> Result<Uri, InvalidUri> {
+        Uri::try_from(file_url.as_file_url())
+    }
+}
+
+impl TryFrom<&Uri> for FileUrlBuf {
+    type Error = FileUrlParseError;
+
+    fn try_from(uri: &Uri) -> Result<FileUrlBuf, FileUrlParseError> {
+        match file_url_of(uri) {
+            Ok(url) => FileUrlBuf::new(url),
+            Err(_) => Err(FileUrlParseError::new("Not an absolute file URL!")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_round_trip() {
+        let path = Path::new("/gi>/some & what.whtvr");
+        let uri = path.to_uri().unwrap();
+        assert_eq!(uri.authority().unwrap(), "localhost");
+        assert_eq!(PathBuf::from_uri(&uri).unwrap(), path);
+    }
+
+    #[test]
+    fn file_url_types_round_trip() {
+        let file_url: FileUrlBuf = "file:///a/b%20c".parse().unwrap();
+        let uri = Uri::try_from(file_url.clone()).unwrap();
+        assert_eq!(uri.to_string(), "file://localhost/a/b%20c");
+        assert_eq!(FileUrlBuf::try_from(&uri).unwrap(), file_url);
+
+        let unc: FileUrlBuf = "file://server/share".parse().unwrap();
+        let uri = Uri::try_from(unc.clone()).unwrap();
+        assert_eq!(FileUrlBuf::try_from(&uri).unwrap(), unc);
+    }
+
+    #[test]
+    fn rejects_other_schemes() {
+        let uri: Uri = "https://example.com/a".parse().unwrap();
+        assert!(matches!(
+            PathBuf::from_uri(&uri),
+            Err(UriError::NotFileScheme(Some(ref s))) if s == "https"
+        ));
+        let uri: Uri = "/just/a/path".parse().unwrap();
+        assert!(matches!(
+            PathBuf::from_uri(&uri),
+            Err(UriError::NotFileScheme(None))
+        ));
+    }
+}
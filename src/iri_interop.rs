@@ -0,0 +1,211 @@
+//! Conversions for `iri_string` types, behind the `iri-string` feature.
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::string::FromUtf8Error;
+
+use iri_string::types::{IriStr, IriString, UriStr};
+use iri_string::validate::Error as IriValidateError;
+
+use crate::{file_url_to_pathbuf, FileUrl, FileUrlParseError, PathFileUrlExt, UTFDecodeError};
+
+/// Error converting between paths and file IRIs.
+#[derive(Debug)]
+pub enum IriError {
+    /// The path isn't valid UTF-8.
+    Encode(UTFDecodeError),
+    /// The IRI's path doesn't decode to UTF-8.
+    Decode(FromUtf8Error),
+    /// `iri_string` rejected the result as an IRI.
+    Validate(IriValidateError),
+    /// The IRI doesn't use the `file:` scheme.
+    NotFileScheme(String),
+}
+
+impl fmt::Display for IriError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IriError::Encode(_) => write!(f, "Path is not UTF-8 compatible!"),
+            IriError::Decode(_) => write!(f, "File IRI does not decode to UTF-8!"),
+            IriError::Validate(_) => write!(f, "Invalid IRI!"),
+            IriError::NotFileScheme(scheme) => write!(f, "Expected a file IRI, got {}:", scheme),
+        }
+    }
+}
+
+impl Error for IriError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            IriError::Encode(e) => Some(e),
+            IriError::Decode(e) => Some(e),
+            IriError::Validate(e) => Some(e),
+            IriError::NotFileScheme(_) => None,
+        }
+    }
+}
+
+/// Converts a path to a file IRI. ASCII is escaped as for
+/// `to_file_url`, but non-ASCII characters IRIs allow are left as-is
+/// rather than percent-encoded as UTF-8.
+///
+/// # Example:
+/// ```
+/// use std::path::Path;
+/// use file_url::path_to_file_iri;
+///
+/// let iri = path_to_file_iri(Path::new("/données/a b.txt")).unwrap();
+/// assert_eq!(iri.as_str(), "file:///données/a%20b.txt");
+/// ```
+pub fn path_to_file_iri(path: &Path) -> Result<IriString, IriError> {
+    let url = path.to_file_url().map_err(IriError::Encode)?;
+    IriString::try_from(unescape_iri_chars(&url))
+        .map_err(|e| IriError::Validate(e.validation_error()))
+}
+
+/// Decodes the path of a file IRI, ignoring any query or fragment.
+///
+/// # Example:
+/// ```
+/// use std::path::PathBuf;
+/// use iri_string::types::IriStr;
+/// use file_url::file_iri_to_pathbuf;
+///
+/// let iri = IriStr::new("file:///données/a%20b.txt#x").unwrap();
+/// assert_eq!(file_iri_to_pathbuf(iri).unwrap(), PathBuf::from("/données/a b.txt"));
+/// ```
+pub fn file_iri_to_pathbuf(iri: &IriStr) -> Result<PathBuf, IriError> {
+    if !iri.scheme_str().eq_ignore_ascii_case("file") {
+        return Err(IriError::NotFileScheme(iri.scheme_str().to_string()));
+    }
+    let url = format!(
+        "file://{}{}",
+        iri.authority_str().unwrap_or(""),
+        iri.path_str()
+    );
+    file_url_to_pathbuf(&url).map_err(IriError::Decode)
+}
+
+/// Whether a character may appear unescaped in an IRI path, i.e. is
+/// a `ucschar` in RFC 3987 terms.
+fn is_ucschar(c: char) -> bool {
+    let c = c as u32;
+    let plane_end = c & 0xfffe == 0xfffe;
+    matches!(c, 0xa0..=0xd7ff | 0xf900..=0xfdcf | 0xfdf0..=0xffef)
+        || (matches!(c, 0x10000..=0xefffd) && !plane_end)
+}
+
+/// Replaces percent-encoded UTF-8 sequences with the characters they
+/// encode, where IRIs allow those characters raw.
+fn unescape_iri_chars(url: &str) -> String {
+    let bytes = url.as_bytes();
+    let mut out = String::with_capacity(url.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        // Gather a run of escaped non-ASCII bytes.
+        let mut run = Vec::new();
+        let mut end = i;
+        while end + 2 < bytes.len() && bytes[end] == b'%' {
+            let hex = (
+                (bytes[end + 1] as char).to_digit(16),
+                (bytes[end + 2] as char).to_digit(16),
+            );
+            match hex {
+                (Some(hi), Some(lo)) if hi >= 8 => {
+                    let b = (hi << 4 | lo) as u8;
+                    run.push(b);
+                    end += 3;
+                }
+                _ => break,
+            }
+        }
+        if run.is_empty() {
+            let c = url[i..].chars().next().unwrap();
+            out.push(c);
+            i += c.len_utf8();
+            continue;
+        }
+        match String::from_utf8(run) {
+            Ok(decoded) => {
+                let mut pos = i;
+                for c in decoded.chars() {
+                    let len = c.len_utf8() * 3;
+                    if is_ucschar(c) {
+                        out.push(c);
+                    } else {
+                        out.push_str(&url[pos..pos + len]);
+                    }
+                    pos += len;
+                }
+            }
+            Err(_) => out.push_str(&url[i..end]),
+        }
+        i = end;
+    }
+    out
+}
+
+impl<'a> TryFrom<&'a FileUrl> for &'a IriStr {
+    type Error = IriValidateError;
+
+    fn try_from(url: &'a FileUrl) -> Result<&'a IriStr, IriValidateError> {
+        IriStr::new(url.as_str())
+    }
+}
+
+impl<'a> TryFrom<&'a FileUrl> for &'a UriStr {
+    type Error = IriValidateError;
+
+    fn try_from(url: &'a FileUrl) -> Result<&'a UriStr, IriValidateError> {
+        UriStr::new(url.as_str())
+    }
+}
+
+impl<'a> TryFrom<&'a IriStr> for &'a FileUrl {
+    type Error = FileUrlParseError;
+
+    fn try_from(iri: &'a IriStr) -> Result<&'a FileUrl, FileUrlParseError> {
+        FileUrl::new(iri.as_str())
+    }
+}
+
+impl<'a> TryFrom<&'a UriStr> for &'a FileUrl {
+    type Error = FileUrlParseError;
+
+    fn try_from(uri: &'a UriStr) -> Result<&'a FileUrl, FileUrlParseError> {
+        FileUrl::new(uri.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_ascii_escapes() {
+        assert_eq!(
+            unescape_iri_chars("file:///%E6%97%A5%20%25/%C2%85"),
+            "file:///日%20%25/%C2%85"
+        );
+        assert_eq!(unescape_iri_chars("file:///%FF%zz"), "file:///%FF%zz");
+    }
+
+    #[test]
+    fn iri_round_trip() {
+        let path = Path::new("/日本/Ünïcödé 1.txt");
+        let iri = path_to_file_iri(path).unwrap();
+        assert_eq!(iri.as_str(), "file:///日本/Ünïcödé%201.txt");
+        assert_eq!(file_iri_to_pathbuf(&iri).unwrap(), path);
+    }
+
+    #[test]
+    fn borrowed_conversions() {
+        let url = FileUrl::new("file:///a%20b").unwrap();
+        let uri: &UriStr = TryFrom::try_from(url).unwrap();
+        let back: &FileUrl = TryFrom::try_from(uri).unwrap();
+        assert_eq!(back, url);
+
+        let raw_space = FileUrl::new("file:///a b").unwrap();
+        assert!(<&UriStr>::try_from(raw_space).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! `LazyFileUrl`, a view of a file URL that decodes only what's asked
+//! for.
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::string::FromUtf8Error;
+
+use crate::classify::{legacy_unc, scheme};
+use crate::percent::decode;
+use crate::types::other_scheme;
+use crate::{file_url_to_path, FileUrlParseError};
+
+/// A file URL that's checked only for its `file:` scheme up front,
+/// with each segment decoded when it's accessed. Filtering on a file
+/// name or a single segment then costs nothing for the rest of the
+/// URL, which suits pipelines that throw most URLs away.
+///
+/// Segments are the non-empty pieces of the path between slashes,
+/// after any authority; a drive like `C:` is a segment of its own.
+///
+/// # Example:
+/// ```
+/// use std::path::Path;
+/// use file_url::LazyFileUrl;
+///
+/// let url = LazyFileUrl::new("file:///srv/my%20data/report.pdf").unwrap();
+/// assert_eq!(url.file_name().unwrap().unwrap(), "report.pdf");
+/// assert_eq!(url.nth_segment(1).unwrap().unwrap(), "my data");
+/// assert_eq!(url.to_path().unwrap(), Path::new("/srv/my data/report.pdf"));
+///
+/// // Only the segments that are read have to decode.
+/// let url = LazyFileUrl::new("file:///%FF/ok.txt").unwrap();
+/// assert_eq!(url.file_name().unwrap().unwrap(), "ok.txt");
+/// assert!(url.nth_segment(0).unwrap().is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LazyFileUrl<'a> {
+    url: &'a str,
+    host: Option<&'a str>,
+    path: &'a str,
+}
+
+impl<'a> LazyFileUrl<'a> {
+    /// Wraps `url`, checking only that it starts with `file:`, in
+    /// any case, followed by an authority or an absolute path.
+    pub fn new(url: &'a str) -> Result<LazyFileUrl<'a>, FileUrlParseError> {
+        let has_scheme = url
+            .get(..5)
+            .is_some_and(|s| s.eq_ignore_ascii_case("file:"));
+        if !has_scheme {
+            if let Some(scheme) = scheme(url) {
+                return Err(other_scheme(scheme));
+            }
+        }
+        if !has_scheme || !url[5..].starts_with('/') {
+            return Err(FileUrlParseError::new("Not an absolute file URL!"));
+        }
+        let mut host = None;
+        let mut path = &url[5..];
+        if let Some((legacy, rest)) = legacy_unc(path) {
+            host = Some(legacy);
+            path = rest;
+        } else if let Some(auth_and_path) = path.strip_prefix("//") {
+            let end = auth_and_path.find('/').unwrap_or(auth_and_path.len());
+            if end > 0 {
+                host = Some(&auth_and_path[..end]);
+            }
+            path = &auth_and_path[end..];
+        }
+        Ok(LazyFileUrl { url, host, path })
+    }
+
+    /// The URL as it was given.
+    pub fn as_str(&self) -> &'a str {
+        self.url
+    }
+
+    /// The authority, still encoded, if it isn't empty. For a legacy
+    /// UNC URL like `file:////server/share` it's the server.
+    pub fn host(&self) -> Option<&'a str> {
+        self.host
+    }
+
+    /// The segments, still encoded.
+    pub fn raw_segments(&self) -> impl Iterator<Item = &'a str> + Clone + 'a {
+        self.path.split('/').filter(|s| !s.is_empty())
+    }
+
+    /// The decoded `n`th segment, counting from zero, or `None` if
+    /// there are no more than `n`.
+    pub fn nth_segment(&self, n: usize) -> Option<Result<Cow<'a, str>, FromUtf8Error>> {
+        self.raw_segments().nth(n).map(decode)
+    }
+
+    /// The decoded last segment, or `None` if there are no segments.
+    pub fn file_name(&self) -> Option<Result<Cow<'a, str>, FromUtf8Error>> {
+        self.path.rsplit('/').find(|s| !s.is_empty()).map(decode)
+    }
+
+    /// Decodes the whole URL, like `file_url_to_path`.
+    pub fn to_path(&self) -> Result<Cow<'a, Path>, FromUtf8Error> {
+        file_url_to_path(self.url)
+    }
+
+    /// Decodes the whole URL, like `file_url_to_pathbuf`.
+    pub fn to_path_buf(&self) -> Result<PathBuf, FromUtf8Error> {
+        self.to_path().map(Cow::into_owned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_url_to_pathbuf;
+
+    #[test]
+    fn decodes_on_access() {
+        let url = LazyFileUrl::new("FILE://server//C:/a%20b/c/").unwrap();
+        assert_eq!(url.host(), Some("server"));
+        let raw: Vec<&str> = url.raw_segments().collect();
+        assert_eq!(raw, ["C:", "a%20b", "c"]);
+        assert_eq!(url.nth_segment(1).unwrap().unwrap(), "a b");
+        assert!(url.nth_segment(3).is_none());
+        assert_eq!(url.file_name().unwrap().unwrap(), "c");
+        assert_eq!(
+            url.to_path_buf().unwrap(),
+            file_url_to_pathbuf(url.as_str()).unwrap()
+        );
+
+        let legacy = LazyFileUrl::new("file://///server/share/x").unwrap();
+        assert_eq!(legacy.host(), Some("server"));
+        assert_eq!(legacy.nth_segment(0).unwrap().unwrap(), "share");
+
+        let root = LazyFileUrl::new("file:///").unwrap();
+        assert!(root.file_name().is_none());
+        assert_eq!(root.host(), None);
+
+        for bad in ["", "file:", "file:a", "http:///a", "fil"].iter() {
+            assert!(LazyFileUrl::new(bad).is_err(), "{}", bad);
+        }
+    }
+}
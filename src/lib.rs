@@ -2,26 +2,301 @@
 //!
 //! Makes it easier to Path/PathBuf to/from file URLs.
 //!
+//! Parsing any `&str` and encoding any `Path` never panics: malformed
+//! URLs and non-UTF-8 paths come back as errors. The exceptions are
+//! `FileUrl::from_static`, which panics by design, and encoding with
+//! the `verify-url` feature, which panics on a URL the `url` crate
+//! reads differently. The `fuzz` directory has `cargo fuzz` targets
+//! for both directions, and the tests replay their seed corpus.
+//!
+//! Everything that touches `Path` or the filesystem needs the default
+//! `std` feature. Without it the crate is `no_std` with `alloc`, and
+//! offers the string-to-string `encode_unix_path_str` and
+//! `decode_to_unix_path_string`.
+//!
 //! Author: Jared Adam Smith
 //! license: MIT
 //! © 2021
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+extern crate alloc;
+// The cdylib still needs a panic handler and an allocator. Hosted
+// targets take them from std; bare-metal targets drop the cdylib.
+#[cfg(all(not(feature = "std"), not(test), not(target_os = "none")))]
+extern crate std;
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::string::{FromUtf8Error, ToString};
+use core::fmt;
+use core::ops::Deref;
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::fmt;
+#[cfg(all(feature = "std", unix))]
+use std::ffi::OsString;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(all(feature = "std", unix))]
+use std::mem;
+#[cfg(all(feature = "std", unix))]
+use std::os::unix::ffi::OsStringExt;
+#[cfg(feature = "std")]
 use std::path::{Path, PathBuf};
-use std::string::FromUtf8Error;
-use std::borrow::Cow;
 
-use lazy_static::lazy_static;
-use regex::Regex;
-use urlencoding::{decode, encode};
+#[cfg(all(feature = "std", not(unix)))]
+use percent::decode;
 
-lazy_static! {
-    // We don't want to percent encode the colon on a Windows drive letter.
-    static ref WINDOWS_DRIVE: Regex = Regex::new(r"[a-zA-Z]:").unwrap();
-    static ref SEPARATOR: Regex = Regex::new(r"[/\\]").unwrap();
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impl;
+#[cfg(feature = "std")]
+mod archive;
+#[cfg(feature = "std")]
+mod bulk;
+#[cfg(feature = "camino")]
+mod camino_interop;
+#[cfg(feature = "cap-std")]
+mod cap_std_impl;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "clap")]
+mod clap_impl;
+mod classify;
+#[cfg(feature = "std")]
+mod coerce;
+#[cfg(feature = "compact_str")]
+mod compact_str_impl;
+#[cfg(feature = "std")]
+mod components;
+#[cfg(feature = "std")]
+mod converter;
+#[cfg(feature = "std")]
+mod debug_info;
+#[cfg(feature = "std")]
+mod decode_options;
+#[cfg(feature = "std")]
+mod drive_case;
+#[cfg(feature = "editor-links")]
+mod editor_links;
+#[cfg(feature = "std")]
+mod encode_options;
+mod encode_set;
+mod escapes;
+#[cfg(feature = "std")]
+mod extract;
+#[cfg(feature = "fluent-uri")]
+mod fluent_uri_interop;
+#[cfg(feature = "std")]
+mod fragment;
+#[cfg(feature = "std")]
+pub mod fs_ext;
+#[cfg(feature = "futures")]
+mod futures_impl;
+#[cfg(feature = "std")]
+mod git_remote;
+#[cfg(feature = "std")]
+mod glob;
+#[cfg(feature = "http")]
+mod http_interop;
+#[cfg(feature = "iri-string")]
+mod iri_interop;
+#[cfg(feature = "std")]
+mod lazy;
+#[cfg(feature = "std")]
+mod long_path;
+#[cfg(feature = "std")]
+mod mount;
+#[cfg(feature = "std")]
+mod normalize;
+#[cfg(feature = "std")]
+mod parse;
+#[cfg(all(feature = "std", not(unix)))]
+mod path_model;
+mod percent;
+#[cfg(feature = "std")]
+mod platform;
+#[cfg(feature = "python")]
+mod python_impl;
+#[cfg(feature = "quickcheck")]
+mod quickcheck_impl;
+#[cfg(feature = "rayon")]
+mod rayon_impl;
+#[cfg(feature = "std")]
+mod relative;
+#[cfg(feature = "std")]
+mod roundtrip;
+#[cfg(feature = "std")]
+mod sandbox;
+mod scan;
+#[cfg(feature = "schemars")]
+mod schemars_impl;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "serde")]
+pub mod serde_path;
+#[cfg(feature = "std")]
+mod sort;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+#[cfg(feature = "tokio")]
+mod tokio_impl;
+#[cfg(feature = "std")]
+mod types;
+mod unix_str;
+#[cfg(feature = "uriparse")]
+mod uriparse_interop;
+#[cfg(feature = "url")]
+mod url_interop;
+#[cfg(feature = "std")]
+mod walk;
+#[cfg(feature = "wasm")]
+mod wasm_impl;
+
+#[cfg(feature = "std")]
+pub use archive::{ArchiveUrl, ArchiveUrlError};
+#[cfg(feature = "std")]
+pub use bulk::{
+    decode_lines, file_urls_to_paths, parse_file_urls, parse_uri_list, paths_to_file_urls,
+    to_uri_list, write_file_urls, DecodedLines, FileUrlLines, LineSeparator, WriteOptions,
+};
+#[cfg(feature = "camino")]
+pub use camino_interop::file_url_to_utf8_pathbuf;
+#[cfg(feature = "cap-std")]
+pub use cap_std_impl::SandboxedDir;
+#[cfg(feature = "clap")]
+pub use clap_impl::{FileUrlValueParser, PathOrFileUrlValueParser};
+pub use classify::{classify, InputKind};
+#[cfg(feature = "std")]
+pub use coerce::{coerce_to_pathbuf, CoerceError};
+#[cfg(feature = "compact_str")]
+pub use compact_str_impl::PathCompactFileUrlExt;
+#[cfg(feature = "std")]
+pub use components::{UrlComponent, UrlComponents};
+#[cfg(feature = "std")]
+pub use converter::Converter;
+#[cfg(feature = "std")]
+pub use debug_info::normalize_debug_url;
+#[cfg(feature = "std")]
+pub use decode_options::{file_url_to_pathbuf_with, DecodeError, DecodeOptions, SlashRuns};
+#[cfg(feature = "std")]
+pub use drive_case::DriveLetterCase;
+#[cfg(feature = "editor-links")]
+pub use editor_links::Editor;
+#[cfg(feature = "std")]
+pub use encode_options::{
+    path_to_file_url_with, AuthorityStyle, EncodeOptions, HexCase, OutputProfile,
+    ParseProfileError,
+};
+pub use encode_set::EncodeSet;
+pub use escapes::looks_percent_encoded;
+#[cfg(feature = "std")]
+pub use extract::{find_file_urls, linkify_paths, FileUrlMatch, FileUrlMatches, LinkifyOptions};
+#[cfg(feature = "fluent-uri")]
+pub use fluent_uri_interop::{FluentUriError, PathFluentUriExt, PathFromFluentUriExt};
+#[cfg(feature = "std")]
+pub use fragment::FragmentPosition;
+#[cfg(feature = "futures")]
+pub use futures_impl::{FileUrlStreamExt, ToFileUrls, ToPaths};
+#[cfg(feature = "std")]
+pub use git_remote::{check_git_remote, path_to_git_remote, GitRemoteError};
+#[cfg(feature = "std")]
+pub use glob::FileUrlGlob;
+#[cfg(feature = "http")]
+pub use http_interop::{PathFromUriExt, PathUriExt, UriError};
+#[cfg(feature = "iri-string")]
+pub use iri_interop::{file_iri_to_pathbuf, path_to_file_iri, IriError};
+#[cfg(feature = "std")]
+pub use lazy::LazyFileUrl;
+#[cfg(feature = "std")]
+pub use mount::{MountError, MountMap, VirtualRoot};
+#[cfg(feature = "std")]
+pub use parse::{ParseOptions, ParseWarning, Strictness};
+#[cfg(feature = "std")]
+pub use platform::Platform;
+#[cfg(feature = "python")]
+pub use python_impl::python_module;
+#[cfg(feature = "quickcheck")]
+pub use quickcheck_impl::RoundTripPath;
+#[cfg(feature = "rayon")]
+pub use rayon_impl::{par_file_urls_to_paths, par_paths_to_file_urls};
+#[cfg(feature = "std")]
+pub use relative::{common_ancestor, relative_url};
+#[cfg(feature = "std")]
+pub use roundtrip::{verify_roundtrip, RoundtripError};
+#[cfg(feature = "std")]
+pub use sandbox::{contains_traversal, SandboxError, SandboxedResolver};
+#[cfg(feature = "std")]
+pub use sort::{directories_first_cmp, natural_cmp, UrlOrdering};
+#[cfg(feature = "tokio")]
+pub use tokio_impl::{open_file_url, read_dir_file_url, read_file_url};
+#[cfg(feature = "std")]
+pub use types::{is_absolute_file_url, is_file_url, FileUrl, FileUrlBuf, FromPathError};
+pub use unix_str::{decode_to_unix_path_string, encode_unix_path_str};
+#[cfg(feature = "uriparse")]
+pub use uriparse_interop::{PathFromUriparseExt, PathUriparseExt, UriparseError};
+#[cfg(feature = "url")]
+pub use url_interop::{PathFromUrlExt, PathUrlExt, UrlError};
+#[cfg(feature = "std")]
+pub use walk::{walk_as_file_urls, WalkFileUrls};
+#[cfg(feature = "wasm")]
+pub use wasm_impl::{file_url_to_path_js, path_to_file_url_js};
+
+/// Percent-encodes `part`, borrowing it if nothing needs escaping.
+pub(crate) fn encode(part: &str) -> Cow<'_, str> {
+    if scan::first_reserved(part.as_bytes()) == part.len() {
+        return Cow::Borrowed(part);
+    }
+    let mut encoded = String::with_capacity(scan::encoded_len(part.as_bytes()));
+    push_encoded(&mut encoded, part);
+    Cow::Owned(encoded)
 }
 
-static FORWARD_SLASH: &str = "/";
+/// Percent-encodes `part` onto the end of `url`, copying runs of
+/// unreserved bytes whole and escaping everything else, byte by byte
+/// in uppercase hex.
+pub(crate) fn push_encoded<B: UrlBuf>(url: &mut B, part: &str) {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    let bytes = part.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let run = scan::first_reserved(&bytes[i..]);
+        if run > 0 {
+            // The run is ASCII, so both ends are char boundaries.
+            url.push_str(&part[i..i + run]);
+            i += run;
+        }
+        if let Some(&b) = bytes.get(i) {
+            url.push('%');
+            url.push(HEX[usize::from(b >> 4)] as char);
+            url.push(HEX[usize::from(b & 0xF)] as char);
+            i += 1;
+        }
+    }
+}
+
+/// The length of the file URL for `path`, where `is_separator` picks
+/// out the bytes that become a `/`. Exact for an absolute path with
+/// no empty or `.` components, and an upper bound otherwise.
+pub(crate) fn file_url_len(path: &str, is_separator: impl Fn(u8) -> bool) -> usize {
+    let separators = path.bytes().filter(|&b| is_separator(b)).count();
+    // Relative and Windows paths get a `/` that isn't in the path.
+    let leading = usize::from(!path.starts_with('/'));
+    "file://".len() + leading + scan::encoded_len(path.as_bytes()) - 2 * separators
+}
+
+/// Whether a path component is a Windows drive letter like `C:`,
+/// whose colon we don't want to percent encode.
+pub(crate) fn is_windows_drive(part: &str) -> bool {
+    let bytes = part.as_bytes();
+    bytes.len() == 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+/// The intermediate list of path segments when picking a URL apart.
+/// With the `smallvec` feature, paths of up to 16 segments keep it
+/// on the stack.
+#[cfg(all(feature = "std", feature = "smallvec"))]
+pub(crate) type Segments<T> = smallvec::SmallVec<[T; 16]>;
+#[cfg(all(feature = "std", not(feature = "smallvec")))]
+pub(crate) type Segments<T> = Vec<T>;
 
 /// Error for file paths that don't decode to
 /// valid UTF-8 strings.
@@ -31,6 +306,7 @@ pub struct UTFDecodeError {
 }
 
 impl UTFDecodeError {
+    #[cfg(feature = "std")]
     fn new(msg: &str) -> UTFDecodeError {
         UTFDecodeError {
             details: msg.to_string(),
@@ -44,12 +320,58 @@ impl fmt::Display for UTFDecodeError {
     }
 }
 
-impl Error for UTFDecodeError {
-    fn description(&self) -> &str {
-        &self.details
+#[cfg(feature = "std")]
+impl Error for UTFDecodeError {}
+
+/// Error for strings that aren't valid absolute
+/// file URLs.
+#[derive(Debug)]
+pub struct FileUrlParseError {
+    details: String,
+    kind: ParseErrorKind,
+}
+
+/// Why a `FileUrlParseError` was returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ParseErrorKind {
+    /// The string isn't a valid file URL.
+    Invalid,
+    /// The string is longer than `ParseOptions::max_len` allows.
+    TooLong,
+    /// The URL has more segments than `ParseOptions::max_segments`
+    /// allows.
+    TooManySegments,
+}
+
+impl FileUrlParseError {
+    #[cfg(feature = "std")]
+    fn new(msg: &str) -> FileUrlParseError {
+        FileUrlParseError::with_kind(ParseErrorKind::Invalid, msg)
+    }
+
+    #[cfg(feature = "std")]
+    fn with_kind(kind: ParseErrorKind, msg: &str) -> FileUrlParseError {
+        FileUrlParseError {
+            details: msg.to_string(),
+            kind,
+        }
+    }
+
+    /// Why parsing failed.
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for FileUrlParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.details)
     }
 }
 
+#[cfg(feature = "std")]
+impl Error for FileUrlParseError {}
+
 /// Percent-encodes the path component. Ignores
 /// Microsoft Windows drive letters and separator
 /// characters.
@@ -64,16 +386,31 @@ impl Error for UTFDecodeError {
 /// let windows_drive = encode_file_component("C:");
 /// assert_eq!(windows_drive, "C:");
 /// ```
-pub fn encode_file_component(path_part: &str) -> Cow<str> {
-    // If it's a separator char or a Windows drive return
-    // as-is.
-    if SEPARATOR.is_match(path_part) || WINDOWS_DRIVE.is_match(path_part) {
+pub fn encode_file_component(path_part: &str) -> Cow<'_, str> {
+    if is_kept_whole(path_part) {
         Cow::from(path_part)
     } else {
         encode(path_part)
     }
 }
 
+/// Like `encode_file_component`, but writes onto the end of `url`.
+fn push_file_component<B: UrlBuf>(url: &mut B, path_part: &str) {
+    if is_kept_whole(path_part) {
+        url.push_str(path_part);
+    } else {
+        push_encoded(url, path_part);
+    }
+}
+
+/// Whether a component is a separator char or a Windows drive, which
+/// are written as-is. A lone `\\` is only a separator on Windows;
+/// elsewhere it's an ordinary file name and gets encoded.
+fn is_kept_whole(path_part: &str) -> bool {
+    path_part == "/" || (cfg!(windows) && path_part == "\\") || is_windows_drive(path_part)
+}
+
+#[cfg(feature = "std")]
 /// Turns a file URL into a PathBuf. Note that because
 /// `std::path::PathBuf` is backed by a `std::ffi::OsString`
 /// the result is platform-dependent, i.e. Microsoft Windows
@@ -82,6 +419,10 @@ pub fn encode_file_component(path_part: &str) -> Cow<str> {
 /// valid file path can be non-UTF8 we have to return a
 /// Result in case the string decode fails.
 ///
+/// Decoding is strict on every platform: nothing is ever replaced
+/// with U+FFFD. Segments are decoded one at a time, so the error's
+/// `as_bytes` is the decoded segment that failed.
+///
 /// # Examples:
 /// ```
 /// use std::path::PathBuf;
@@ -89,68 +430,265 @@ pub fn encode_file_component(path_part: &str) -> Cow<str> {
 ///
 /// let p_buf = file_url_to_pathbuf("file:///foo/bar%20baz.txt").unwrap();
 /// assert_eq!(p_buf, PathBuf::from("/foo/bar baz.txt"));
+///
+/// let err = file_url_to_pathbuf("file:///ok/caf%E9.txt/x").unwrap_err();
+/// assert_eq!(err.as_bytes(), b"caf\xe9.txt");
 /// ```
 pub fn file_url_to_pathbuf(file_url: &str) -> Result<PathBuf, FromUtf8Error> {
-    SEPARATOR
-        .split(file_url)
-        .enumerate()
-        .map(|(i, url_piece)| {
-            if i == 0 && url_piece == "file:" {
-                // File url should always be abspath
-                Ok(String::from(FORWARD_SLASH))
-            } else {
-                let dec_str = decode(url_piece);
-                match dec_str {
-                    Ok(decoded) => Ok(decoded.into_owned()),
-                    Err(e) => Err(e),
-                }
-            }
-        })
-        .collect()
+    file_url_to_path(file_url).map(Cow::into_owned)
+}
+
+#[cfg(feature = "std")]
+/// Like `file_url_to_pathbuf`, but borrows the path straight out of
+/// the URL when there's nothing to decode, as is the case for most
+/// ordinary file names. Only Unix-like systems can borrow, since
+/// elsewhere the separators need rewriting.
+///
+/// # Examples:
+/// ```
+/// use std::borrow::Cow;
+/// use std::path::Path;
+/// use file_url::file_url_to_path;
+///
+/// let clean = file_url_to_path("file:///foo/bar.txt").unwrap();
+/// assert_eq!(clean, Path::new("/foo/bar.txt"));
+/// assert!(cfg!(not(unix)) || matches!(clean, Cow::Borrowed(_)));
+///
+/// let escaped = file_url_to_path("file:///foo/bar%20baz.txt").unwrap();
+/// assert_eq!(escaped, Path::new("/foo/bar baz.txt"));
+/// ```
+pub fn file_url_to_path(file_url: &str) -> Result<Cow<'_, Path>, FromUtf8Error> {
+    if let Some(path) = borrowed_path(file_url) {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(file_url, path = ?path, "decoded without copying");
+        return Ok(Cow::Borrowed(path));
+    }
+    let mut path = PathBuf::new();
+    let decoded = decode_into(file_url, &mut path);
+    #[cfg(feature = "tracing")]
+    match &decoded {
+        Ok(()) => tracing::trace!(file_url, path = ?path, "decoded"),
+        Err(err) => tracing::debug!(file_url, segment = ?err.as_bytes(), "segment isn't UTF-8"),
+    }
+    decoded?;
+    Ok(Cow::Owned(path))
+}
+
+/// Replaces the contents of `path` with the decoded `file_url`. On
+/// Unix-like systems the URL is copied into the path's buffer and
+/// decoded there in a single pass.
+#[cfg(all(feature = "std", unix))]
+pub(crate) fn decode_into(file_url: &str, path: &mut PathBuf) -> Result<(), FromUtf8Error> {
+    if let Some(clean) = borrowed_path(file_url) {
+        path.as_mut_os_string().clear();
+        path.push(clean);
+        return Ok(());
+    }
+    let mut bytes = mem::take(path).into_os_string().into_vec();
+    bytes.clear();
+    bytes.extend_from_slice(file_url.as_bytes());
+    let decoded = unix_str::decode_in_place(&mut bytes);
+    *path = PathBuf::from(OsString::from_vec(bytes));
+    decoded
+}
+
+/// Replaces the contents of `path` with the decoded `file_url`, one
+/// segment at a time.
+#[cfg(all(feature = "std", not(unix)))]
+pub(crate) fn decode_into(file_url: &str, path: &mut PathBuf) -> Result<(), FromUtf8Error> {
+    path.as_mut_os_string().clear();
+    // Decoding never makes the URL longer.
+    path.reserve(file_url.len());
+    for (i, url_piece) in file_url.split('/').enumerate() {
+        if i == 0 && url_piece == "file:" {
+            // File url should always be abspath
+            path.push("/");
+        } else if scan::first_percent(url_piece.as_bytes()) == url_piece.len() {
+            path.push(url_piece);
+        } else {
+            path.push(&*decode(url_piece)?);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+/// The decoded path as a slice of `file_url`, if it is one: the URL
+/// has no escapes or empty segments past the ones after the scheme,
+/// so its path can be used as-is from the last of those slashes.
+fn borrowed_path(file_url: &str) -> Option<&Path> {
+    let rest = file_url.strip_prefix("file:")?;
+    if !cfg!(unix) || scan::first_percent(rest.as_bytes()) < rest.len() {
+        return None;
+    }
+    let slashes = rest.len() - rest.trim_start_matches('/').len();
+    if slashes == 0 || rest[slashes..].contains("//") {
+        return None;
+    }
+    Some(Path::new(&rest[slashes - 1..]))
+}
+
+#[cfg(feature = "std")]
+/// Like `file_url_to_pathbuf`, but decodes into the URL's own buffer,
+/// since decoding only ever shrinks it. Handy for batch pipelines that
+/// already own each URL and don't need it afterwards. Only Unix-like
+/// systems decode in place; elsewhere this allocates like
+/// `file_url_to_pathbuf`.
+///
+/// # Examples:
+/// ```
+/// use std::path::PathBuf;
+/// use file_url::decode_file_url_in_place;
+///
+/// let url = String::from("file:///foo/bar%20baz.txt");
+/// let p_buf = decode_file_url_in_place(url).unwrap();
+/// assert_eq!(p_buf, PathBuf::from("/foo/bar baz.txt"));
+/// ```
+pub fn decode_file_url_in_place(file_url: String) -> Result<PathBuf, FromUtf8Error> {
+    #[cfg(unix)]
+    {
+        let mut bytes = file_url.into_bytes();
+        unix_str::decode_in_place(&mut bytes)?;
+        Ok(PathBuf::from(OsString::from_vec(bytes)))
+    }
+    #[cfg(not(unix))]
+    file_url_to_pathbuf(&file_url)
+}
+
+#[cfg(feature = "std")]
+/// Decodes a file URL for the filesystem helpers. Anything that isn't
+/// an absolute file URL naming this machine is an
+/// `io::ErrorKind::InvalidInput` error, rather than whatever path it
+/// happens to decode to: another scheme, a bare path, and a host
+/// other than `localhost`, except on Windows, where a host is a UNC
+/// share.
+pub(crate) fn file_url_to_io_path(file_url: &str) -> io::Result<PathBuf> {
+    let invalid = |e| io::Error::new(io::ErrorKind::InvalidInput, e);
+    let url = FileUrl::new(file_url).map_err(invalid)?;
+    let (host, path) = platform::split_authority(url.as_str());
+    if !host.is_empty() && !cfg!(windows) {
+        return Err(invalid(FileUrlParseError::new(
+            "File URL names a remote host!",
+        )));
+    }
+    // `file://localhost/x` is `file:///x`.
+    if host.is_empty() && file_url.len() - path.len() > "file://".len() {
+        let local = format!("file://{}", path);
+        return Ok(FileUrl::from_str_unchecked(&local).to_path_buf());
+    }
+    Ok(url.to_path_buf())
+}
+
+/// An output buffer that `push_file_url` can write a URL into.
+pub(crate) trait UrlBuf: Deref<Target = str> {
+    fn reserve_exact(&mut self, additional: usize);
+    fn push(&mut self, c: char);
+    fn push_str(&mut self, s: &str);
+}
+
+impl UrlBuf for String {
+    fn reserve_exact(&mut self, additional: usize) {
+        String::reserve_exact(self, additional)
+    }
+
+    fn push(&mut self, c: char) {
+        String::push(self, c)
+    }
+
+    fn push_str(&mut self, s: &str) {
+        String::push_str(self, s)
+    }
+}
+
+#[cfg(feature = "std")]
+/// Writes the file URL for `path` onto the end of `url`, one encoded
+/// component at a time. Separators are always `/`, a Windows drive
+/// like `C:` gets a `/` in front of it, and a UNC prefix's server
+/// becomes the host.
+pub(crate) fn push_file_url<B: UrlBuf>(path: &Path, url: &mut B) -> Result<(), UTFDecodeError> {
+    let text = match path.to_str() {
+        Some(text) => text,
+        None => {
+            let msg = format!("Path {} is not UTF-8 compatible!", path.to_string_lossy());
+            return Err(UTFDecodeError::new(&msg));
+        }
+    };
+    #[cfg(feature = "verify-url")]
+    let start = url.len();
+    #[cfg(unix)]
+    {
+        // Splits the string the same way `components` would.
+        unix_str::push_unix_file_url(url, text);
+    }
+    #[cfg(not(unix))]
+    {
+        let is_separator = |b| b == b'/' || (cfg!(windows) && b == b'\\');
+        url.reserve_exact(file_url_len(text, is_separator));
+        path_model::PathModel::new(path).push_url(url);
+    }
+    #[cfg(feature = "verify-url")]
+    url_interop::verify_emitted(path, &url[start..]);
+    Ok(())
 }
 
+#[cfg(feature = "std")]
 /// Method for converting std::path::PathBuf and
 /// `std::path::Path` to a file URL.
 pub trait PathFileUrlExt {
     /// Assuming a PathBuf or Path is valid UTF8, converts
     /// to a file URL as an owned String.
     fn to_file_url(&self) -> Result<String, UTFDecodeError>;
+
+    /// Like `to_file_url`, but if the path lives under the directory
+    /// of `base_url` returns a relative reference instead (e.g.
+    /// `img/logo.png`). Falls back to the absolute file URL otherwise.
+    fn to_file_url_relative_to(&self, base_url: &str) -> Result<String, UTFDecodeError>;
 }
 
+#[cfg(feature = "std")]
 /// Method for constructing a `std::path::PathBuf` from a file URL.
 pub trait PathFromFileUrlExt<PathBuf> {
     /// Constructs a PathBuf from the supplied &str.
     fn from_file_url(file_url: &str) -> Result<PathBuf, FromUtf8Error>;
 }
 
+#[cfg(feature = "std")]
 impl PathFileUrlExt for Path {
     fn to_file_url(&self) -> Result<String, UTFDecodeError> {
-        let path_parts: Result<PathBuf, UTFDecodeError> = self
-            .components()
-            .map(|part| match part.as_os_str().to_str() {
-                Some(part) => Ok(encode_file_component(part).to_string()),
-                None => Err(UTFDecodeError::new("File path not UTF-8 compatible!")),
-            })
-            .collect();
+        // Sized up front and written front to back, so this is the
+        // only allocation.
+        let mut url = String::new();
+        let encoded = push_file_url(self, &mut url);
+        #[cfg(feature = "tracing")]
+        match &encoded {
+            Ok(()) => tracing::trace!(path = ?self, url = url.as_str(), "encoded"),
+            Err(_) => tracing::debug!(path = ?self, "path isn't UTF-8"),
+        }
+        encoded?;
+        Ok(url)
+    }
 
-        match path_parts {
-            // Unwrap shouldn't fail here since everything should be properly decoded.
-            Ok(parts) => Ok(format!("file://{}", parts.to_str().unwrap())),
-            Err(e) => Err(e),
+    fn to_file_url_relative_to(&self, base_url: &str) -> Result<String, UTFDecodeError> {
+        let url = self.to_file_url()?;
+        match relative_url(base_url, &url) {
+            Some(rel) if !rel.starts_with("../") => Ok(rel),
+            _ => Ok(url),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl PathFromFileUrlExt<PathBuf> for PathBuf {
     fn from_file_url(file_url: &str) -> Result<PathBuf, FromUtf8Error> {
         file_url_to_pathbuf(file_url)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use std::path::PathBuf;
+    use crate::percent::decode;
 
     #[test]
     fn basic_pathbuf_to_url() {
@@ -197,4 +735,256 @@ mod tests {
         let two = "file:///foo/bar.txt";
         assert_eq!(one, two);
     }
+
+    #[test]
+    fn colon_mid_component_is_encoded() {
+        let url = Path::new("/a:%41").to_file_url().unwrap();
+        assert_eq!(url, "file:///a%3A%2541");
+        assert_eq!(PathBuf::from_file_url(&url).unwrap(), Path::new("/a:%41"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn backslash_is_not_a_url_separator() {
+        let p = Path::new(r"/dir\with\backslashes");
+        let url = p.to_file_url().unwrap();
+        assert_eq!(url, "file:///dir%5Cwith%5Cbackslashes");
+        assert_eq!(PathBuf::from_file_url(&url).unwrap(), p);
+        assert_eq!(
+            PathBuf::from_file_url(r"file:///a\b").unwrap(),
+            Path::new(r"/a\b")
+        );
+    }
+
+    #[test]
+    fn clean_urls_borrow() {
+        let urls = [
+            "file:///a/b.txt",
+            "file://host/a",
+            "file:///a//b/",
+            "file:////a",
+            "file:/a",
+            "file:///",
+            "file:",
+            "file:a",
+            "file:///a%20b",
+        ];
+        for url in urls.iter() {
+            let path = file_url_to_path(url).unwrap();
+            let borrowed = matches!(path, Cow::Borrowed(_));
+            let clean = url.starts_with("file:/") && !url.contains('%') && !url.contains("a//");
+            assert_eq!(borrowed, cfg!(unix) && clean, "{}", url);
+            // The fast path must agree with decoding every segment.
+            let slow = segment_by_segment(url).unwrap();
+            assert_eq!(path.as_os_str(), slow.as_os_str(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn in_place_matches_decoding() {
+        let urls = [
+            "file:///a/b%20c.txt",
+            "file://host//a/",
+            "file:///a%2Fb",
+            "file:///x/%2Fetc/passwd",
+            "file:///%FF/%2Fa",
+            "file:///a%zz%4",
+            "file:",
+            "file:a",
+            "a/b",
+            "/%C3%BC",
+            "",
+        ];
+        for url in urls.iter() {
+            let in_place = decode_file_url_in_place(url.to_string());
+            let mut converted = PathBuf::from("/leftover");
+            let into = decode_into(url, &mut converted).map(|()| converted);
+            let mut decoded = vec![in_place, into];
+            if cfg!(unix) {
+                let string = decode_to_unix_path_string(url);
+                decoded.push(string.map(PathBuf::from));
+            }
+            for decoded in decoded.iter() {
+                match (decoded, segment_by_segment(url)) {
+                    (Ok(a), Ok(b)) => assert_eq!(a.as_os_str(), b.as_os_str(), "{}", url),
+                    (Err(a), Err(b)) => assert_eq!(a.as_bytes(), b.as_bytes(), "{}", url),
+                    _ => panic!("{}", url),
+                }
+            }
+        }
+    }
+
+    /// Decodes the way `file_url_to_pathbuf` is specified to: each
+    /// segment decoded on its own and pushed onto a PathBuf.
+    fn segment_by_segment(url: &str) -> Result<PathBuf, FromUtf8Error> {
+        let mut path = PathBuf::new();
+        for (i, piece) in url.split('/').enumerate() {
+            if i == 0 && piece == "file:" {
+                path.push("/");
+            } else {
+                path.push(&*decode(piece)?);
+            }
+        }
+        Ok(path)
+    }
+
+    #[test]
+    fn escapes_every_reserved_byte() {
+        for c in (0..=0x7Fu8).map(char::from).chain("ü€😀".chars()) {
+            let s = c.to_string();
+            let expected: String = if c.is_ascii_alphanumeric() || "-._~".contains(c) {
+                s.clone()
+            } else {
+                s.bytes().map(|b| format!("%{:02X}", b)).collect()
+            };
+            assert_eq!(encode(&s), expected);
+            assert_eq!(encode(&format!("a{}b", s)), format!("a{}b", expected));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn normalized_paths_skip_component_parsing() {
+        let paths = [
+            "/", "/a/b", "/a//b", "/a/./b", "/a/b/", "/a/.", "/./a", "/a/../b", "rel", "/.a/b.",
+            "./a", "././a", "", "//a", "a/./b/", "/.",
+        ];
+        for p in paths.iter() {
+            let path = Path::new(p);
+            let mut slow = String::from("file://");
+            for (i, part) in path.components().enumerate() {
+                let part = part.as_os_str().to_str().unwrap();
+                if i > 1 || (i == 1 && !p.starts_with('/')) {
+                    slow.push('/');
+                }
+                slow.push_str(&encode_file_component(part));
+            }
+            assert_eq!(path.to_file_url().unwrap(), slow, "{}", p);
+            assert_eq!(encode_unix_path_str(p), slow, "{}", p);
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn unc_prefixes_are_encoded() {
+        let cases = [
+            (r"\\my server\my share\a b", "file://my%20server/my%20share/a%20b"),
+            (r"\\?\UNC\srv\share\x", "file://srv/share/x"),
+            (r"\\?\C:\x", "file:///C:/x"),
+            (r"c:\x", "file:///c:/x"),
+        ];
+        for (path, url) in cases.iter() {
+            assert_eq!(Path::new(path).to_file_url().unwrap(), *url);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn drive_like_components_stay_whole() {
+        assert_eq!(
+            Path::new("/C:/x y").to_file_url().unwrap(),
+            "file:///C:/x%20y"
+        );
+    }
+
+    #[test]
+    fn preallocates_exactly() {
+        let mut url = String::new();
+        push_file_url(Path::new("/deep/ü ß/&&&/a b/%25/x.txt"), &mut url).unwrap();
+        assert_eq!(
+            url,
+            "file:///deep/%C3%BC%20%C3%9F/%26%26%26/a%20b/%2525/x.txt"
+        );
+        assert_eq!(url.capacity(), url.len());
+
+        let url = Path::new("/a b/c").to_file_url().unwrap();
+        assert_eq!(url.capacity(), url.len());
+
+        let enc = encode("ü&ß").into_owned();
+        assert_eq!(enc.capacity(), enc.len());
+    }
+
+    #[test]
+    fn path_relative_to_base() {
+        let p = Path::new("/site/img/my logo.png");
+        let rel = p.to_file_url_relative_to("file:///site/").unwrap();
+        assert_eq!(rel, "img/my%20logo.png");
+
+        let rel = p
+            .to_file_url_relative_to("file:///site/index.html")
+            .unwrap();
+        assert_eq!(rel, "img/my%20logo.png");
+    }
+
+    #[test]
+    fn path_outside_base_stays_absolute() {
+        let p = PathBuf::from("/other/file.txt");
+        let url = p.to_file_url_relative_to("file:///site/").unwrap();
+        assert_eq!(url, "file:///other/file.txt");
+    }
+
+    /// Runs the same calls as the fuzz targets over their seeds, so
+    /// the no-panic guarantee is checked without a nightly toolchain.
+    #[test]
+    fn fuzz_seeds_never_panic() {
+        let seeds = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/seeds");
+        for target in ["parse", "encode"].iter() {
+            for entry in std::fs::read_dir(seeds.join(target)).unwrap() {
+                let bytes = std::fs::read(entry.unwrap().path()).unwrap();
+                // Like `fuzz_target!` with a `&str`, skip non-UTF-8 input.
+                let input = match std::str::from_utf8(&bytes) {
+                    Ok(input) => input,
+                    Err(_) => continue,
+                };
+                if *target == "parse" {
+                    fuzz_parse(input);
+                } else {
+                    fuzz_encode(input);
+                }
+            }
+        }
+    }
+
+    fn fuzz_parse(input: &str) {
+        let _ = file_url_to_pathbuf(input);
+        let _ = decode_file_url_in_place(input.to_string());
+        let _ = contains_traversal(input);
+        let _ = Platform::Posix.file_url_to_path(input);
+        let _ = Platform::Windows.file_url_to_path(input);
+        let _ = SandboxedResolver::new(Path::new("/srv")).resolve(input);
+
+        let mid = input.len() / 2;
+        let (a, b) = match (input.get(..mid), input.get(mid..)) {
+            (Some(a), Some(b)) => (a, b),
+            _ => (input, input),
+        };
+        let _ = relative_url(a, b);
+        if let (Ok(glob), Ok(url)) = (FileUrlGlob::new(a), FileUrl::new(b)) {
+            let _ = glob.matches(url);
+        }
+
+        if let Ok(url) = FileUrl::new(input) {
+            let _ = url.to_path_buf();
+            let _ = url.components().count();
+            let _ = url.is_within(url);
+            let _ = UrlOrdering::default().compare(url, url);
+            let mut buf = url.to_file_url_buf();
+            buf.push(a);
+        }
+    }
+
+    fn fuzz_encode(input: &str) {
+        let _ = encode_file_component(input);
+        let _ = Platform::Posix.path_to_file_url(input);
+        let _ = Platform::Posix.is_absolute(input);
+        let _ = Platform::Windows.path_to_file_url(input);
+        let _ = Platform::Windows.is_absolute(input);
+
+        let path = Path::new(input);
+        if let Ok(url) = path.to_file_url() {
+            let _ = file_url_to_pathbuf(&url);
+        }
+        let _ = path.to_file_url_relative_to(input);
+        let _ = FileUrlBuf::from_path(path);
+    }
 }
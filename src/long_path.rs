@@ -0,0 +1,73 @@
+//! Windows extended-length (`\\?\`) paths, for paths too long for
+//! the Win32 APIs' `MAX_PATH` limit.
+
+/// The Win32 path limit, in UTF-16 units, including the terminating
+/// NUL.
+const MAX_PATH: usize = 260;
+
+/// The `\\?\` form of the Windows path `path`, if it's absolute, not
+/// already in that form, and too long for `MAX_PATH`.
+pub(crate) fn extended_length(path: &str) -> Option<String> {
+    if path.encode_utf16().count() < MAX_PATH {
+        return None;
+    }
+    let is_separator = |c: char| c == '/' || c == '\\';
+    let mut chars = path.chars();
+    let (mut long, rest, fixed) = match (chars.next(), chars.next(), chars.next()) {
+        (Some(a), Some(b), _) if is_separator(a) && is_separator(b) => {
+            let rest = &path[2..];
+            if rest.starts_with("?\\") || rest.starts_with(".\\") {
+                return None;
+            }
+            // The server and share can't be climbed out of.
+            (String::from(r"\\?\UNC"), rest, 2)
+        }
+        (Some(letter), Some(':'), Some(sep))
+            if letter.is_ascii_alphabetic() && is_separator(sep) =>
+        {
+            (String::from(r"\\?"), path, 1)
+        }
+        _ => return None,
+    };
+    long.reserve(path.len());
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in rest.split(is_separator) {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if segments.len() > fixed {
+                    segments.pop();
+                }
+            }
+            _ => segments.push(segment),
+        }
+    }
+    for segment in segments {
+        long.push('\\');
+        long.push_str(segment);
+    }
+    Some(long)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_long_absolute_paths_change() {
+        let long = "a".repeat(MAX_PATH);
+        assert_eq!(
+            extended_length(&format!(r"C:\x\..\y/./{}\", long)),
+            Some(format!(r"\\?\C:\y\{}", long))
+        );
+        assert_eq!(
+            extended_length(&format!(r"\\srv\share\..\..\{}", long)),
+            Some(format!(r"\\?\UNC\srv\share\{}", long))
+        );
+        assert_eq!(extended_length(&format!(r"\\?\C:\{}", long)), None);
+        assert_eq!(extended_length(&format!(r"rel\{}", long)), None);
+        assert_eq!(extended_length(&format!(r"\{}", long)), None);
+        assert_eq!(extended_length(r"C:\short"), None);
+    }
+}
@@ -0,0 +1,274 @@
+//! `MountMap` and `VirtualRoot`, for translating file URLs between
+//! places that see the same files under different paths.
+use std::error::Error;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use crate::percent::decode;
+use crate::types::hides_dots;
+use crate::{FileUrl, FileUrlBuf, UTFDecodeError};
+
+/// Error for URLs and paths a `MountMap` can't translate.
+#[derive(Debug)]
+pub enum MountError {
+    /// No mount's URL prefix or root covers the input.
+    NotMounted,
+    /// A segment is `..`, or decodes to more than one path component,
+    /// like `a%2Fb`, which could reach outside the mount.
+    Traversal,
+    /// A path segment isn't UTF-8, so it has no URL.
+    Encode(UTFDecodeError),
+}
+
+impl fmt::Display for MountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MountError::NotMounted => write!(f, "No mount covers this location!"),
+            MountError::Traversal => write!(f, "Location climbs out of its mount!"),
+            MountError::Encode(_) => write!(f, "Path is not UTF-8 compatible!"),
+        }
+    }
+}
+
+impl Error for MountError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MountError::Encode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Maps file URL prefixes to directories on this machine and back,
+/// for services that pass URLs between machines with different mount
+/// layouts, like a file server's `file://fileserver/projects/` that
+/// one client mounts at `/mnt/projects`. The longest matching prefix
+/// or root wins, and prefixes are compared the way `FileUrl::is_within`
+/// compares them, segment by segment and decoded.
+///
+/// # Example:
+/// ```
+/// use std::path::{Path, PathBuf};
+/// use file_url::{FileUrl, MountMap};
+///
+/// let mounts = MountMap::new()
+///     .mount(FileUrl::new("file://fileserver/projects/").unwrap(), Path::new("/mnt/projects"))
+///     .mount(FileUrl::new("file:///home/").unwrap(), Path::new("/home"));
+///
+/// let url = FileUrl::new("file://fileserver/projects/q1/a%20b.txt").unwrap();
+/// let path = mounts.resolve(url).unwrap();
+/// assert_eq!(path, PathBuf::from("/mnt/projects/q1/a b.txt"));
+///
+/// let back = mounts.to_url(&path).unwrap();
+/// assert_eq!(back.as_str(), url.as_str());
+///
+/// assert!(mounts.to_url(Path::new("/etc/passwd")).is_err());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MountMap {
+    mounts: Vec<(FileUrlBuf, PathBuf)>,
+}
+
+impl MountMap {
+    /// A map with no mounts.
+    pub fn new() -> MountMap {
+        MountMap::default()
+    }
+
+    /// Adds a mount of `url_prefix` at `root`, which should be
+    /// absolute.
+    pub fn mount(mut self, url_prefix: &FileUrl, root: &Path) -> MountMap {
+        self.mounts
+            .push((url_prefix.to_file_url_buf(), root.to_path_buf()));
+        self
+    }
+
+    /// The path on this machine that `url` names. Dot segments are
+    /// resolved first, so `..` can't climb out of a mount.
+    pub fn resolve(&self, url: &FileUrl) -> Result<PathBuf, MountError> {
+        // `is_within` would call these not mounted.
+        if url
+            .parts()
+            .segments
+            .iter()
+            .any(|s| hides_dots(s, cfg!(windows)))
+        {
+            return Err(MountError::Traversal);
+        }
+        let (prefix, root) = self
+            .mounts
+            .iter()
+            .filter(|(prefix, _)| url.is_within(prefix))
+            .max_by_key(|(prefix, _)| segment_count(prefix))
+            .ok_or(MountError::NotMounted)?;
+
+        let mut path = root.clone();
+        let parts = url.parts();
+        for segment in &parts.segments[segment_count(prefix)..] {
+            // Validation already checked every segment decodes.
+            let segment = decode(segment).unwrap();
+            // An encoded separator or, on Windows, a drive would let
+            // the push go somewhere else.
+            let mut components = Path::new(&*segment).components();
+            match (components.next(), components.next()) {
+                (Some(Component::Normal(segment)), None) => path.push(segment),
+                (None, _) | (Some(Component::CurDir), None) => {}
+                _ => return Err(MountError::Traversal),
+            }
+        }
+        Ok(path)
+    }
+
+    /// The URL that names `path`, which must lie under one of the
+    /// mounts' roots. Roots and paths are compared component by
+    /// component, and a `..` in the path is refused.
+    pub fn to_url(&self, path: &Path) -> Result<FileUrlBuf, MountError> {
+        let (prefix, rest) = self
+            .mounts
+            .iter()
+            .filter_map(|(prefix, root)| Some((prefix, path.strip_prefix(root).ok()?, root)))
+            .max_by_key(|(_, _, root)| root.components().count())
+            .map(|(prefix, rest, _)| (prefix, rest))
+            .ok_or(MountError::NotMounted)?;
+
+        let mut url = prefix.clone();
+        for component in rest.components() {
+            match component {
+                Component::Normal(segment) => url.try_push(segment).map_err(MountError::Encode)?,
+                Component::CurDir => {}
+                _ => return Err(MountError::Traversal),
+            }
+        }
+        Ok(url)
+    }
+}
+
+/// Presents a directory as the root of the filesystem in file URLs,
+/// like a chroot: URLs it emits start from `/` rather than revealing
+/// where the directory really is, and URLs it decodes are anchored
+/// beneath it. Lets sandboxed apps exchange URLs with the outside
+/// world without leaking host paths. It's the `MountMap` that mounts
+/// `file:///` at the root, so the same rules and errors apply; a URL
+/// with a host isn't under the root.
+///
+/// # Example:
+/// ```
+/// use std::path::{Path, PathBuf};
+/// use file_url::{FileUrl, VirtualRoot};
+///
+/// let root = VirtualRoot::new(Path::new("/var/sandbox/app1"));
+/// let url = root.to_url(Path::new("/var/sandbox/app1/docs/a b.txt")).unwrap();
+/// assert_eq!(url.as_str(), "file:///docs/a%20b.txt");
+///
+/// let path = root.resolve(FileUrl::new("file:///docs/../etc/passwd").unwrap()).unwrap();
+/// assert_eq!(path, PathBuf::from("/var/sandbox/app1/etc/passwd"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct VirtualRoot {
+    map: MountMap,
+}
+
+impl VirtualRoot {
+    /// A virtual root at `real_root`, which should be absolute.
+    pub fn new(real_root: &Path) -> VirtualRoot {
+        VirtualRoot {
+            map: MountMap::new().mount(FileUrl::from_static("file:///"), real_root),
+        }
+    }
+
+    /// The directory presented as `/`.
+    pub fn real_root(&self) -> &Path {
+        &self.map.mounts[0].1
+    }
+
+    /// The real path that `url` names, beneath the root. `..` never
+    /// climbs above it.
+    pub fn resolve(&self, url: &FileUrl) -> Result<PathBuf, MountError> {
+        self.map.resolve(url)
+    }
+
+    /// The URL for the real path `path`, which must lie beneath the
+    /// root, written as though the root were `/`.
+    pub fn to_url(&self, path: &Path) -> Result<FileUrlBuf, MountError> {
+        self.map.to_url(path)
+    }
+}
+
+/// How many segments of `prefix` a URL under it shares, not counting
+/// the empty one after a trailing slash.
+fn segment_count(prefix: &FileUrl) -> usize {
+    let parts = prefix.parts();
+    match parts.segments.last() {
+        Some(&"") => parts.segments.len() - 1,
+        _ => parts.segments.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> &FileUrl {
+        FileUrl::new(s).unwrap()
+    }
+
+    #[test]
+    fn virtual_root_hides_the_real_one() {
+        let root = VirtualRoot::new(Path::new("/jail"));
+        assert_eq!(root.real_root(), Path::new("/jail"));
+        assert_eq!(
+            root.to_url(Path::new("/jail")).unwrap().as_str(),
+            "file:///"
+        );
+        assert_eq!(
+            root.resolve(url("file:///../../x")).unwrap(),
+            Path::new("/jail/x")
+        );
+        assert_eq!(root.resolve(url("file:///")).unwrap(), Path::new("/jail"));
+        assert!(matches!(
+            root.resolve(url("file://host/x")),
+            Err(MountError::NotMounted)
+        ));
+        assert!(matches!(
+            root.to_url(Path::new("/etc/passwd")),
+            Err(MountError::NotMounted)
+        ));
+    }
+
+    #[test]
+    fn longest_prefix_wins() {
+        let mounts = MountMap::new()
+            .mount(url("file://srv/share"), Path::new("/mnt/share"))
+            .mount(url("file://SRV/share/deep/"), Path::new("/deep"));
+        let resolve = |s| {
+            mounts
+                .resolve(url(s))
+                .map(|p| p.to_string_lossy().into_owned())
+        };
+
+        assert_eq!(resolve("file://srv/share/a").unwrap(), "/mnt/share/a");
+        assert_eq!(resolve("file://srv/share/deep/./b").unwrap(), "/deep/b");
+        assert_eq!(
+            resolve("file://srv/share/deep/../c").unwrap(),
+            "/mnt/share/c"
+        );
+        assert_eq!(resolve("file://srv/share").unwrap(), "/mnt/share");
+        assert!(matches!(
+            resolve("file://srv/shared/a"),
+            Err(MountError::NotMounted)
+        ));
+        assert!(matches!(
+            resolve("file://srv/share/..%2F..%2Fetc"),
+            Err(MountError::Traversal)
+        ));
+
+        let to_url = |p| mounts.to_url(Path::new(p)).map(FileUrlBuf::into_string);
+        assert_eq!(to_url("/deep/x y").unwrap(), "file://SRV/share/deep/x%20y");
+        assert_eq!(to_url("/mnt/share/./z").unwrap(), "file://srv/share/z");
+        assert!(matches!(
+            to_url("/mnt/share/../etc"),
+            Err(MountError::Traversal)
+        ));
+        assert!(matches!(to_url("/mnt/shared"), Err(MountError::NotMounted)));
+    }
+}
@@ -0,0 +1,125 @@
+//! A normal form for file URLs, so that URLs written differently for
+//! the same path compare equal as strings.
+use std::borrow::Cow;
+
+use crate::classify::is_encoded_drive;
+use crate::parse::is_strict_char;
+use crate::{scan, FileUrl};
+
+impl FileUrl {
+    /// The URL exactly as it was given. Parsing never rewrites its
+    /// input, so this is always `as_str`; it's spelled out for code
+    /// that has to re-emit URLs byte for byte and wants to say so.
+    pub fn as_original_str(&self) -> &str {
+        self.as_str()
+    }
+
+    /// The URL in a normal form, borrowed if it's already in it: the
+    /// scheme and host lowercased, `localhost` dropped, escapes of
+    /// unreserved characters decoded, other escapes in uppercase,
+    /// and anything that needs escaping, including a `%` that doesn't
+    /// start an escape, escaped. A drive's encoded colon, as in
+    /// `file:///c%3A/`, is decoded. Dot segments and the case of drive
+    /// letters are left alone, since changing them can change which
+    /// file is meant. Two URLs with the same normal form name the same
+    /// file.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::FileUrl;
+    ///
+    /// let url = FileUrl::new("FILE://LocalHost/a%2d%7e/%c3%bc b/100%").unwrap();
+    /// assert_eq!(url.as_original_str(), "FILE://LocalHost/a%2d%7e/%c3%bc b/100%");
+    /// assert_eq!(url.as_normalized_str(), "file:///a-~/%C3%BC%20b/100%25");
+    /// ```
+    pub fn as_normalized_str(&self) -> Cow<'_, str> {
+        let url = self.as_str();
+        let (host, path) = match url[5..].strip_prefix("//") {
+            Some(rest) => rest.split_at(rest.find('/').unwrap_or(rest.len())),
+            None => ("", &url[5..]),
+        };
+        let host = if host.eq_ignore_ascii_case("localhost") {
+            ""
+        } else {
+            host
+        };
+
+        let mut normal = String::with_capacity(url.len() + 2);
+        normal.push_str("file://");
+        normal.extend(host.chars().map(|c| c.to_ascii_lowercase()));
+        let bytes = path.as_bytes();
+        let first = path.get(1..).and_then(|p| p.split('/').next());
+        // The index of the `%` of an encoded drive colon.
+        let drive_colon = first.filter(|s| is_encoded_drive(s)).map(|_| 2);
+        let mut chars = path.char_indices();
+        while let Some((i, c)) = chars.next() {
+            let escaped = bytes
+                .get(i + 1..i + 3)
+                .filter(|_| c == '%')
+                .and_then(|hex| Some(scan::hex_value(hex[0])? << 4 | scan::hex_value(hex[1])?));
+            match escaped {
+                Some(b) if scan::is_unreserved(b) || Some(i) == drive_colon => {
+                    normal.push(char::from(b))
+                }
+                Some(b) => push_escape(&mut normal, b),
+                None if is_strict_char(c, false) => normal.push(c),
+                None => {
+                    let mut buf = [0; 4];
+                    for &b in c.encode_utf8(&mut buf).as_bytes() {
+                        push_escape(&mut normal, b);
+                    }
+                }
+            }
+            if escaped.is_some() {
+                chars.nth(1);
+            }
+        }
+
+        if normal == url {
+            Cow::Borrowed(url)
+        } else {
+            Cow::Owned(normal)
+        }
+    }
+}
+
+fn push_escape(out: &mut String, b: u8) {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    out.push('%');
+    out.push(char::from(HEX[usize::from(b >> 4)]));
+    out.push(char::from(HEX[usize::from(b & 0xF)]));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_url_to_pathbuf;
+
+    #[test]
+    fn normal_forms_decode_the_same() {
+        let cases = [
+            ("file:///a/b", "file:///a/b"),
+            ("file://localhost/a", "file:///a"),
+            ("file://SRV/Sh%61re/", "file://srv/Share/"),
+            ("file:///C:/x/./../%2e", "file:///C:/x/./../."),
+            ("file:///a%2fb/%zz/%4", "file:///a%2Fb/%25zz/%254"),
+            ("file:///ü#?", "file:///%C3%BC%23%3F"),
+            ("file:///c%3a/a%3A", "file:///c:/a%3A"),
+        ];
+        for (input, normal) in cases.iter() {
+            let url = FileUrl::new(input).unwrap();
+            let normalized = url.as_normalized_str();
+            assert_eq!(normalized, *normal, "{}", input);
+            if input.starts_with("file:///") {
+                assert_eq!(
+                    file_url_to_pathbuf(&normalized).unwrap(),
+                    file_url_to_pathbuf(input).unwrap(),
+                    "{}",
+                    input
+                );
+            }
+            let again = FileUrl::new(&*normalized).unwrap();
+            assert!(matches!(again.as_normalized_str(), Cow::Borrowed(_)));
+        }
+    }
+}
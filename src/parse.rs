@@ -0,0 +1,516 @@
+//! `FileUrl::parse_with` and the options it takes, for choosing how
+//! forgiving parsing is.
+use std::fmt;
+
+use crate::classify::legacy_unc;
+use crate::{scan, FileUrl, FileUrlParseError, ParseErrorKind};
+
+/// How `FileUrl::parse_with` treats characters that RFC 3986 says
+/// must be percent-encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Strictness {
+    /// Accepts URLs as they're pasted from file managers and
+    /// browsers: raw spaces, non-ASCII characters, backticks and
+    /// `" # < > ? [ \ ] ^ { | }` are taken literally, as are `%` signs
+    /// that don't start an escape. This is what `FileUrl::new` does.
+    Lenient,
+    /// Only accepts URLs in which every character outside the RFC 3986
+    /// path characters (plus `[` and `]` in the authority) is
+    /// percent-encoded, and every `%` starts a valid escape. Legacy UNC
+    /// URLs like `file:////server/share` are refused. A first
+    /// segment shaped like a drive, such as `CC:` or `1:`, must also be
+    /// a real one: a single ASCII letter and a colon.
+    Strict,
+}
+
+/// Options for `FileUrl::parse_with`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    /// Which unencoded characters are accepted.
+    pub strictness: Strictness,
+    /// Whether to trim leading and trailing whitespace, as copied
+    /// from a clipboard or a line of text, before parsing. A newline
+    /// left inside the URL is then an error of its own.
+    pub trim: bool,
+    /// The longest input accepted, in bytes and before any trimming,
+    /// which bounds the work done on untrusted input.
+    pub max_len: Option<usize>,
+    /// The most path segments accepted, counting one per `/` in the
+    /// path.
+    pub max_segments: Option<usize>,
+}
+
+impl Default for ParseOptions {
+    /// Lenient, untrimmed and unlimited, like `FileUrl::new`.
+    fn default() -> ParseOptions {
+        ParseOptions {
+            strictness: Strictness::Lenient,
+            trim: false,
+            max_len: None,
+            max_segments: None,
+        }
+    }
+}
+
+impl FileUrl {
+    /// Validates `s` as an absolute file URL under `options`. With the
+    /// default options this is `FileUrl::new`.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::{FileUrl, ParseErrorKind, ParseOptions, Strictness};
+    ///
+    /// let pasted = "file:///C:/My Documents/report.docx";
+    /// let url = FileUrl::parse_with(pasted, ParseOptions::default()).unwrap();
+    /// assert_eq!(url.as_str(), pasted);
+    ///
+    /// let strict = ParseOptions {
+    ///     strictness: Strictness::Strict,
+    ///     ..ParseOptions::default()
+    /// };
+    /// let err = FileUrl::parse_with(pasted, strict).unwrap_err();
+    /// assert_eq!(err.to_string(), "File URL contains an unencoded ' ' at byte 13!");
+    /// assert!(FileUrl::parse_with("file:///C:/My%20Documents", strict).is_ok());
+    ///
+    /// let trim = ParseOptions { trim: true, ..ParseOptions::default() };
+    /// let url = FileUrl::parse_with("  file:///tmp/a.txt\r\n", trim).unwrap();
+    /// assert_eq!(url.as_str(), "file:///tmp/a.txt");
+    /// assert!(FileUrl::parse_with("file:///a\nfile:///b", trim).is_err());
+    ///
+    /// let limited = ParseOptions { max_segments: Some(2), ..ParseOptions::default() };
+    /// let err = FileUrl::parse_with("file:///a/b/c", limited).unwrap_err();
+    /// assert_eq!(err.kind(), ParseErrorKind::TooManySegments);
+    /// ```
+    pub fn parse_with(s: &str, options: ParseOptions) -> Result<&FileUrl, FileUrlParseError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "parse",
+            input = s,
+            strictness = ?options.strictness,
+            trim = options.trim,
+        )
+        .entered();
+        let parsed = parse(s, options);
+        #[cfg(feature = "tracing")]
+        match &parsed {
+            Ok(url) => tracing::trace!(url = url.as_str(), "parsed"),
+            Err(err) => tracing::debug!(%err, kind = ?err.kind(), "rejected"),
+        }
+        parsed
+    }
+
+    /// Like `parse_with`, but also says what was accepted that a
+    /// stricter reader might not have, so applications can log or show
+    /// why a URL was read the way it was. The warnings are in the
+    /// order they occur, and their byte indices are into the URL as
+    /// returned, after any trimming.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::{FileUrl, ParseOptions, ParseWarning};
+    ///
+    /// let (url, warnings) =
+    ///     FileUrl::parse_with_warnings("file://srv/a b?x", ParseOptions::default()).unwrap();
+    /// assert_eq!(url.as_str(), "file://srv/a b?x");
+    /// assert_eq!(
+    ///     warnings,
+    ///     [
+    ///         ParseWarning::Host { index: 7 },
+    ///         ParseWarning::Unencoded { character: ' ', index: 12 },
+    ///         ParseWarning::QueryOrFragment { character: '?', index: 14 },
+    ///     ]
+    /// );
+    /// assert_eq!(warnings[1].to_string(), "unencoded ' ' at byte 12 accepted");
+    /// ```
+    pub fn parse_with_warnings(
+        s: &str,
+        options: ParseOptions,
+    ) -> Result<(&FileUrl, Vec<ParseWarning>), FileUrlParseError> {
+        let url = FileUrl::parse_with(s, options)?;
+        let warnings = warnings(url.as_str());
+        #[cfg(feature = "tracing")]
+        for warning in &warnings {
+            tracing::debug!(url = url.as_str(), %warning, "lenient fix-up");
+        }
+        Ok((url, warnings))
+    }
+}
+
+/// The checks behind `FileUrl::parse_with`.
+fn parse(s: &str, options: ParseOptions) -> Result<&FileUrl, FileUrlParseError> {
+    #[cfg(feature = "tracing")]
+    let original = s;
+    if let Some(max) = options.max_len.filter(|&max| s.len() > max) {
+        let msg = format!("File URL is longer than {} bytes!", max);
+        return Err(FileUrlParseError::with_kind(ParseErrorKind::TooLong, &msg));
+    }
+    let s = if options.trim { s.trim() } else { s };
+    #[cfg(feature = "tracing")]
+    if s.len() != original.len() {
+        tracing::debug!(
+            trimmed = original.len() - s.len(),
+            "trimmed surrounding whitespace"
+        );
+    }
+    if options.trim && s.contains(['\n', '\r']) {
+        return Err(FileUrlParseError::new(
+            "File URL contains an embedded newline!",
+        ));
+    }
+    let url = FileUrl::new(s)?;
+    if let Some(max) = options.max_segments {
+        let slashes = s[path_start(s)..].bytes().filter(|&b| b == b'/');
+        if slashes.take(max + 1).count() > max {
+            let msg = format!("File URL has more than {} segments!", max);
+            return Err(FileUrlParseError::with_kind(
+                ParseErrorKind::TooManySegments,
+                &msg,
+            ));
+        }
+    }
+    if options.strictness == Strictness::Strict {
+        check_strict(s)?;
+        check_drive(s)?;
+        if legacy_host_start(s).is_some() {
+            return Err(FileUrlParseError::new("File URL uses the legacy UNC form!"));
+        }
+    }
+    Ok(url)
+}
+
+/// Something `FileUrl::parse_with_warnings` accepted but that may not
+/// mean what the URL's author intended. Only a lenient parse warns
+/// about characters, since a strict one rejects them instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ParseWarning {
+    /// The URL names a host, starting at byte `index`. Windows decodes
+    /// it to a UNC path; elsewhere it becomes the first path segment,
+    /// and `Platform` conversions drop a `localhost` one entirely.
+    Host {
+        /// Where the host starts.
+        index: usize,
+    },
+    /// A character that should have been percent-encoded, such as a
+    /// space, was taken literally.
+    Unencoded {
+        /// The character.
+        character: char,
+        /// Where it is.
+        index: usize,
+    },
+    /// A legacy UNC URL, like `file:////server/share`, whose host
+    /// starting at byte `index` is in the path. It's read as though
+    /// it were `file://server/share`.
+    LegacyUnc {
+        /// Where the host starts.
+        index: usize,
+    },
+    /// A `\`, which Windows decoding takes as a path separator and
+    /// other platforms as part of a file name.
+    Backslash {
+        /// Where it is.
+        index: usize,
+    },
+    /// A `?` or `#`, which would start a query or fragment in a web
+    /// URL, was kept as part of the path rather than stripped.
+    QueryOrFragment {
+        /// The character.
+        character: char,
+        /// Where it is.
+        index: usize,
+    },
+    /// A `%` that doesn't start a valid escape was taken literally.
+    MalformedEscape {
+        /// Where it is.
+        index: usize,
+    },
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseWarning::Host { index } => write!(f, "host at byte {} kept", index),
+            ParseWarning::LegacyUnc { index } => {
+                write!(f, "legacy UNC host at byte {} kept", index)
+            }
+            ParseWarning::Unencoded { character, index } => {
+                write!(f, "unencoded {:?} at byte {} accepted", character, index)
+            }
+            ParseWarning::Backslash { index } => {
+                write!(f, "backslash at byte {} may be a separator", index)
+            }
+            ParseWarning::QueryOrFragment { character, index } => {
+                write!(f, "{:?} at byte {} kept in the path", character, index)
+            }
+            ParseWarning::MalformedEscape { index } => {
+                write!(f, "malformed escape at byte {} taken literally", index)
+            }
+        }
+    }
+}
+
+/// The warnings for the valid file URL `s`.
+fn warnings(s: &str) -> Vec<ParseWarning> {
+    let mut warnings = Vec::new();
+    let authority_end = path_start(s);
+    if authority_end > 7 {
+        warnings.push(ParseWarning::Host { index: 7 });
+    }
+    if let Some(index) = legacy_host_start(s) {
+        warnings.push(ParseWarning::LegacyUnc { index });
+    }
+    let bytes = s.as_bytes();
+    for (index, character) in s.char_indices().skip(5) {
+        let warning = match character {
+            '%' => {
+                let escape = bytes.get(index + 1..index + 3);
+                if escape.is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)) {
+                    continue;
+                }
+                ParseWarning::MalformedEscape { index }
+            }
+            '\\' => ParseWarning::Backslash { index },
+            '?' | '#' => ParseWarning::QueryOrFragment { character, index },
+            c if is_strict_char(c, index < authority_end) => continue,
+            _ => ParseWarning::Unencoded { character, index },
+        };
+        warnings.push(warning);
+    }
+    warnings
+}
+
+/// Whether `c` may appear unencoded in a strict file URL: the RFC
+/// 3986 `pchar`s and `/`, plus the brackets of an IPv6 host when
+/// `in_authority`.
+pub(crate) fn is_strict_char(c: char, in_authority: bool) -> bool {
+    c.is_ascii()
+        && (scan::is_unreserved(c as u8)
+            || "!$&'()*+,;=:@/".contains(c)
+            || (in_authority && (c == '[' || c == ']')))
+}
+
+/// The byte index where the path of the file URL `s` starts, past
+/// the scheme and any authority.
+fn path_start(s: &str) -> usize {
+    // Validation already checked the scheme.
+    match s[5..].strip_prefix("//") {
+        Some(rest) => 7 + rest.find('/').unwrap_or(rest.len()),
+        None => 5,
+    }
+}
+
+fn check_strict(s: &str) -> Result<(), FileUrlParseError> {
+    let bytes = s.as_bytes();
+    let authority_end = path_start(s);
+    for (i, c) in s.char_indices().skip(5) {
+        if c == '%' {
+            let escape = bytes.get(i + 1..i + 3);
+            if !escape.is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)) {
+                let msg = format!("File URL has a malformed escape at byte {}!", i);
+                return Err(FileUrlParseError::new(&msg));
+            }
+        } else if !is_strict_char(c, i < authority_end) {
+            let msg = format!("File URL contains an unencoded {:?} at byte {}!", c, i);
+            return Err(FileUrlParseError::new(&msg));
+        }
+    }
+    Ok(())
+}
+
+/// Where the host of a legacy UNC file URL starts, if `s` is one.
+fn legacy_host_start(s: &str) -> Option<usize> {
+    let (host, path) = legacy_unc(&s[5..])?;
+    Some(s.len() - host.len() - path.len())
+}
+
+/// Rejects a first path segment that looks like a drive designator
+/// but isn't one: one or two ASCII alphanumerics and a colon, other
+/// than a letter and a colon. Under a host the first segment is a
+/// share name instead, so it's left alone.
+fn check_drive(s: &str) -> Result<(), FileUrlParseError> {
+    let start = path_start(s);
+    let host = s.get(7..start).unwrap_or_default();
+    if !host.is_empty() && !host.eq_ignore_ascii_case("localhost") {
+        return Ok(());
+    }
+    let path = &s[start..];
+    let path = path.strip_prefix('/').unwrap_or(path);
+    let first = path.split('/').next().unwrap_or_default();
+    let bytes = first.as_bytes();
+    let drive_shaped = matches!(bytes.len(), 2 | 3)
+        && bytes.ends_with(b":")
+        && bytes[..bytes.len() - 1]
+            .iter()
+            .all(u8::is_ascii_alphanumeric);
+    if drive_shaped && !(bytes.len() == 2 && bytes[0].is_ascii_alphabetic()) {
+        let msg = format!("File URL has an invalid drive letter {:?}!", first);
+        return Err(FileUrlParseError::new(&msg));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_rejects_what_lenient_accepts() {
+        let strict = ParseOptions {
+            strictness: Strictness::Strict,
+            ..ParseOptions::default()
+        };
+        let lenient = ParseOptions::default();
+        let both = [
+            "file:///a/b.txt",
+            "file://[::1]/x",
+            "file:///C:/a%20b/~user/(1)+x=y;z@w",
+            "file:///%C3%BC",
+        ];
+        for url in both.iter() {
+            assert!(FileUrl::parse_with(url, strict).is_ok(), "{}", url);
+            assert!(FileUrl::parse_with(url, lenient).is_ok(), "{}", url);
+        }
+        let lenient_only = [
+            "file:///a b",
+            "file:///ü",
+            "file:///a#b",
+            "file:///a?b",
+            "file:///a[1]",
+            "file:///a\\b",
+            "file:///100%",
+            "file:///%zz",
+            "file:///%4",
+        ];
+        for url in lenient_only.iter() {
+            assert!(FileUrl::parse_with(url, strict).is_err(), "{}", url);
+            assert!(FileUrl::parse_with(url, lenient).is_ok(), "{}", url);
+        }
+        // The basic checks still apply.
+        assert!(FileUrl::parse_with("http:///a", lenient).is_err());
+        let err = FileUrl::parse_with("file:///%25/%", strict).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "File URL has a malformed escape at byte 12!"
+        );
+    }
+
+    #[test]
+    fn strict_checks_drive_letters() {
+        let strict = ParseOptions {
+            strictness: Strictness::Strict,
+            ..ParseOptions::default()
+        };
+        for url in [
+            "file:///C:/x",
+            "file:///c:",
+            "file://h/CC:/x",
+            "file:///CCC:/x",
+        ]
+        .iter()
+        {
+            assert!(FileUrl::parse_with(url, strict).is_ok(), "{}", url);
+        }
+        for url in [
+            "file:///CC:/x",
+            "file:///1:/x",
+            "file:/C1:",
+            "file://localhost/1:",
+        ]
+        .iter()
+        {
+            assert!(FileUrl::parse_with(url, strict).is_err(), "{}", url);
+            assert!(FileUrl::parse_with(url, ParseOptions::default()).is_ok());
+        }
+        let err = FileUrl::parse_with("file:///1:/x", strict).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "File URL has an invalid drive letter \"1:\"!"
+        );
+    }
+
+    #[test]
+    fn legacy_unc_is_lenient_only() {
+        let url = "file://///srv/share/a%20b";
+        let (_, warnings) = FileUrl::parse_with_warnings(url, ParseOptions::default()).unwrap();
+        assert_eq!(warnings, [ParseWarning::LegacyUnc { index: 10 }]);
+        let strict = ParseOptions {
+            strictness: Strictness::Strict,
+            ..ParseOptions::default()
+        };
+        assert!(FileUrl::parse_with(url, strict).is_err());
+        assert!(FileUrl::parse_with("file:////C:/x", strict).is_ok());
+    }
+
+    #[test]
+    fn trims_only_when_asked() {
+        let trim = ParseOptions {
+            trim: true,
+            ..ParseOptions::default()
+        };
+        let padded = "\t file:///a%20b \n";
+        assert!(FileUrl::parse_with(padded, ParseOptions::default()).is_err());
+        let url = FileUrl::parse_with(padded, trim).unwrap();
+        assert_eq!(url.to_path_buf(), std::path::PathBuf::from("/a b"));
+        // Inner spaces are part of the URL.
+        assert_eq!(
+            FileUrl::parse_with(" file:///a b ", trim).unwrap().as_str(),
+            "file:///a b"
+        );
+        let err = FileUrl::parse_with("file:///a\r\nb", trim).unwrap_err();
+        assert_eq!(err.to_string(), "File URL contains an embedded newline!");
+        assert!(FileUrl::parse_with("   ", trim).is_err());
+    }
+
+    #[test]
+    fn warns_about_what_lenient_accepts() {
+        let lenient = ParseOptions::default();
+        let (_, warnings) = FileUrl::parse_with_warnings("file:///a/b%20c", lenient).unwrap();
+        assert!(warnings.is_empty());
+        let (_, warnings) =
+            FileUrl::parse_with_warnings("file:///C:\\x/ü/100%#1", lenient).unwrap();
+        assert_eq!(
+            warnings,
+            [
+                ParseWarning::Backslash { index: 10 },
+                ParseWarning::Unencoded {
+                    character: 'ü',
+                    index: 13
+                },
+                ParseWarning::MalformedEscape { index: 19 },
+                ParseWarning::QueryOrFragment {
+                    character: '#',
+                    index: 20
+                },
+            ]
+        );
+        let strict = ParseOptions {
+            strictness: Strictness::Strict,
+            trim: true,
+            ..ParseOptions::default()
+        };
+        let (url, warnings) = FileUrl::parse_with_warnings(" file://localhost/x ", strict).unwrap();
+        assert_eq!(url.as_str(), "file://localhost/x");
+        assert_eq!(warnings, [ParseWarning::Host { index: 7 }]);
+        assert!(FileUrl::parse_with_warnings("file:///a b", strict).is_err());
+    }
+
+    #[test]
+    fn limits_are_their_own_errors() {
+        let limited = ParseOptions {
+            max_len: Some(16),
+            max_segments: Some(3),
+            trim: true,
+            ..ParseOptions::default()
+        };
+        assert!(FileUrl::parse_with("file:///a/b/c", limited).is_ok());
+        assert!(FileUrl::parse_with("file://h/a/b/", limited).is_ok());
+        let err = FileUrl::parse_with("file:///a/b/c/d", limited).unwrap_err();
+        assert_eq!(err.kind(), ParseErrorKind::TooManySegments);
+        assert_eq!(err.to_string(), "File URL has more than 3 segments!");
+        // The length limit applies before trimming.
+        let err = FileUrl::parse_with("    file:///a/b  ", limited).unwrap_err();
+        assert_eq!(err.kind(), ParseErrorKind::TooLong);
+        let err = FileUrl::parse_with("http:///", limited).unwrap_err();
+        assert_eq!(err.kind(), ParseErrorKind::Invalid);
+    }
+}
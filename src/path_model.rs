@@ -0,0 +1,152 @@
+//! The shape of a Windows path as its file URL sees it: a prefix, a
+//! root, and the segments after them. Encoding builds this once from
+//! `Path::components` and then writes it out, so every prefix kind,
+//! and a prefix with or without a root, is handled in one place.
+use std::path::{Component, Path, Prefix};
+
+use crate::{encode_set, push_encoded, push_file_component, Segments, UrlBuf};
+
+/// How a path starts, as far as its URL is concerned.
+#[derive(Debug, PartialEq, Eq)]
+enum UrlPrefix<'a> {
+    /// No prefix: a relative or root-relative path.
+    None,
+    /// `C:` or `\\?\C:`, written as a leading `/C:`.
+    Disk(u8),
+    /// `\\server\share` or `\\?\UNC\server\share`, whose server
+    /// becomes the host and whose share the first segment.
+    Unc { server: &'a str, share: &'a str },
+    /// `\\.\device` or `\\?\name`, which have no URL form of their
+    /// own, so the whole prefix is kept as one encoded segment.
+    Opaque(&'a str),
+}
+
+/// A path split into the pieces its file URL is written from.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct PathModel<'a> {
+    prefix: UrlPrefix<'a>,
+    /// Whether the path has a root after its prefix. `C:\x` does and
+    /// `C:x`, which is relative to the drive's current directory,
+    /// doesn't.
+    root: bool,
+    segments: Segments<&'a str>,
+}
+
+impl<'a> PathModel<'a> {
+    /// Splits `path`, whose text is known to be UTF-8.
+    pub(crate) fn new(path: &'a Path) -> PathModel<'a> {
+        // The whole path is UTF-8, so every piece of it is.
+        let text = |s: &'a std::ffi::OsStr| s.to_str().unwrap_or_default();
+        let mut model = PathModel {
+            prefix: UrlPrefix::None,
+            root: false,
+            segments: Segments::new(),
+        };
+        for component in path.components() {
+            match component {
+                Component::Prefix(prefix) => {
+                    model.prefix = match prefix.kind() {
+                        Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => {
+                            UrlPrefix::Disk(letter)
+                        }
+                        Prefix::UNC(server, share) | Prefix::VerbatimUNC(server, share) => {
+                            UrlPrefix::Unc {
+                                server: text(server),
+                                share: text(share),
+                            }
+                        }
+                        Prefix::Verbatim(_) | Prefix::DeviceNS(_) => {
+                            UrlPrefix::Opaque(text(prefix.as_os_str()))
+                        }
+                    }
+                }
+                Component::RootDir => model.root = true,
+                Component::CurDir | Component::ParentDir | Component::Normal(_) => {
+                    model.segments.push(text(component.as_os_str()))
+                }
+            }
+        }
+        model
+    }
+
+    /// Writes the file URL onto the end of `url`.
+    pub(crate) fn push_url<B: UrlBuf>(&self, url: &mut B) {
+        url.push_str("file://");
+        // Whether the first segment needs a `/` even without a root:
+        // a relative path's and a drive-relative path's don't.
+        let joined = match self.prefix {
+            UrlPrefix::None => false,
+            UrlPrefix::Disk(letter) => {
+                url.push('/');
+                url.push(char::from(letter));
+                url.push(':');
+                false
+            }
+            UrlPrefix::Unc { server, share } => {
+                url.push_str(&encode_set::HOST.encode(server));
+                url.push('/');
+                push_encoded(url, share);
+                true
+            }
+            UrlPrefix::Opaque(prefix) => {
+                url.push('/');
+                push_file_component(url, prefix);
+                true
+            }
+        };
+        if self.root {
+            url.push('/');
+        }
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 || (joined && !self.root) {
+                url.push('/');
+            }
+            push_file_component(url, segment);
+        }
+    }
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_prefix_kind() {
+        let cases = [
+            (r"C:\a b\c", "file:///C:/a%20b/c"),
+            (r"C:\", "file:///C:/"),
+            ("C:", "file:///C:"),
+            ("C:rel\\x", "file:///C:rel/x"),
+            (r"\\?\C:\x", "file:///C:/x"),
+            (r"\\?\C:", "file:///C:"),
+            (r"\\srv\share", "file://srv/share"),
+            (r"\\srv\share\", "file://srv/share/"),
+            (r"\\srv\share\a", "file://srv/share/a"),
+            (r"\\?\UNC\srv\share\a", "file://srv/share/a"),
+            (r"\\.\COM1", "file:///%5C%5C.%5CCOM1"),
+            (r"\\?\pictures\a", "file:///%5C%5C%3F%5Cpictures/a"),
+            (r"\x\y", "file:///x/y"),
+            (r"\", "file:///"),
+            (r"rel\x", "file://rel/x"),
+            (r".\rel", "file://./rel"),
+            (r"..\up", "file://../up"),
+            ("", "file://"),
+        ];
+        for (path, expected) in cases.iter() {
+            let mut url = String::new();
+            PathModel::new(Path::new(path)).push_url(&mut url);
+            assert_eq!(url, *expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn roots_are_tracked_apart_from_prefixes() {
+        let model = PathModel::new(Path::new("D:x"));
+        assert_eq!(model.prefix, UrlPrefix::Disk(b'D'));
+        assert!(!model.root);
+        let model = PathModel::new(Path::new(r"\\?\D:\x"));
+        assert_eq!(model.prefix, UrlPrefix::Disk(b'D'));
+        assert!(model.root);
+        assert_eq!(&model.segments[..], ["x"]);
+    }
+}
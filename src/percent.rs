@@ -0,0 +1,93 @@
+//! Percent-decoding, so the crate needs no decoding dependency.
+#[cfg(feature = "std")]
+use alloc::borrow::Cow;
+#[cfg(feature = "std")]
+use alloc::string::{FromUtf8Error, String};
+use core::ops::Range;
+
+use crate::scan;
+
+/// Percent-decodes `bytes`, borrowing them when there's no `%` to
+/// decode. Malformed escapes are kept literally.
+#[cfg(feature = "std")]
+pub(crate) fn decode_binary(bytes: &[u8]) -> Cow<'_, [u8]> {
+    let start = scan::first_percent(bytes);
+    if start == bytes.len() {
+        return Cow::Borrowed(bytes);
+    }
+    let mut decoded = bytes.to_vec();
+    let len = decode_within(&mut decoded, start..bytes.len(), start);
+    decoded.truncate(start + len);
+    Cow::Owned(decoded)
+}
+
+/// Percent-decodes `s`, failing if the result isn't UTF-8.
+#[cfg(feature = "std")]
+pub(crate) fn decode(s: &str) -> Result<Cow<'_, str>, FromUtf8Error> {
+    match decode_binary(s.as_bytes()) {
+        Cow::Borrowed(_) => Ok(Cow::Borrowed(s)),
+        Cow::Owned(bytes) => String::from_utf8(bytes).map(Cow::Owned),
+    }
+}
+
+/// Percent-decodes `bytes[from]` to `bytes[at..]`, where `at` is no
+/// greater than `from.start`, returning the decoded length. Malformed
+/// escapes are kept literally.
+pub(crate) fn decode_within(bytes: &mut [u8], from: Range<usize>, at: usize) -> usize {
+    let hex = scan::hex_value;
+    let (mut read, mut write) = (from.start, at);
+    while read < from.end {
+        if bytes[read] != b'%' {
+            // Move the run up to the next `%` in one go.
+            let run = scan::first_percent(&bytes[read..from.end]);
+            bytes.copy_within(read..read + run, write);
+            read += run;
+            write += run;
+            if read == from.end {
+                break;
+            }
+        }
+        let escaped = match bytes.get(read + 1..read + 3) {
+            Some(&[hi, lo]) if read + 3 <= from.end => {
+                hex(hi).and_then(|hi| hex(lo).map(|lo| hi << 4 | lo))
+            }
+            _ => None,
+        };
+        match escaped {
+            Some(b) => {
+                bytes[write] = b;
+                read += 3;
+            }
+            None => {
+                bytes[write] = b'%';
+                read += 1;
+            }
+        }
+        write += 1;
+    }
+    write - at
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_like_urlencoding() {
+        let cases: &[(&str, &str)] = &[
+            ("", ""),
+            ("plain", "plain"),
+            ("a%20b", "a b"),
+            ("%C3%BC%c3%bc", "üü"),
+            ("%zz%4%", "%zz%4%"),
+            ("%2541", "%41"),
+            ("%%41", "%A"),
+        ];
+        for (input, output) in cases.iter() {
+            assert_eq!(decode(input).unwrap(), *output, "{}", input);
+        }
+        assert!(matches!(decode("clean"), Ok(Cow::Borrowed(_))));
+        assert!(decode("%FF").is_err());
+        assert_eq!(decode_binary(b"%FF%2f"), &b"\xff/"[..]);
+    }
+}
@@ -0,0 +1,309 @@
+//! Conversions for a chosen platform's path syntax, independent of
+//! the OS the code is running on. `PathFileUrlExt` goes through
+//! `std::path`, which only understands the host's own paths; these
+//! work on plain strings so e.g. a Linux server can turn a client's
+//! `C:\Users\me` into a file URL and back.
+use std::string::FromUtf8Error;
+
+use crate::classify::legacy_unc;
+use crate::percent::decode;
+
+use crate::{encode, encode_file_component, encode_set, file_url_len, Segments};
+
+/// Path syntax to convert with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Platform {
+    /// `/`-separated paths, as on Linux and macOS.
+    Posix,
+    /// Drive letters, UNC shares and either separator.
+    Windows,
+}
+
+impl Platform {
+    /// The platform this code was compiled for.
+    pub fn current() -> Platform {
+        if cfg!(windows) {
+            Platform::Windows
+        } else {
+            Platform::Posix
+        }
+    }
+
+    /// Looks a platform up by name: `posix` (or `unix`) or `windows`
+    /// (or Node's `win32`). Case is ignored.
+    pub fn from_name(name: &str) -> Option<Platform> {
+        match name.to_ascii_lowercase().as_str() {
+            "posix" | "unix" => Some(Platform::Posix),
+            "windows" | "win32" => Some(Platform::Windows),
+            _ => None,
+        }
+    }
+
+    /// Whether `path` is absolute in this platform's syntax: rooted on
+    /// POSIX, or starting with a drive and separator or a UNC prefix
+    /// on Windows. A driveless `\foo` is relative to the current
+    /// drive, so it doesn't count.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::Platform;
+    ///
+    /// assert!(Platform::Windows.is_absolute(r"C:\x"));
+    /// assert!(Platform::Windows.is_absolute(r"\\server\share"));
+    /// assert!(!Platform::Windows.is_absolute(r"\x"));
+    /// assert!(Platform::Posix.is_absolute("/x"));
+    /// ```
+    pub fn is_absolute(self, path: &str) -> bool {
+        match self {
+            Platform::Posix => path.starts_with('/'),
+            Platform::Windows => {
+                let bytes = path.as_bytes();
+                strip_two_separators(path).is_some()
+                    || (bytes.len() > 2
+                        && path.get(..2).is_some_and(is_drive)
+                        && is_windows_separator(bytes[2] as char))
+            }
+        }
+    }
+
+    /// Converts an absolute path in this platform's syntax to a file
+    /// URL. A relative path is taken to be relative to the root.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::Platform;
+    ///
+    /// let url = Platform::Windows.path_to_file_url(r"C:\My Files\a.txt");
+    /// assert_eq!(url, "file:///C:/My%20Files/a.txt");
+    ///
+    /// let url = Platform::Windows.path_to_file_url(r"\\server\share\a.txt");
+    /// assert_eq!(url, "file://server/share/a.txt");
+    ///
+    /// let url = Platform::Posix.path_to_file_url("/tmp/a b");
+    /// assert_eq!(url, "file:///tmp/a%20b");
+    /// ```
+    pub fn path_to_file_url(self, path: &str) -> String {
+        let mut url = String::with_capacity(file_url_len(path, |b| b == b'/' || b == b'\\'));
+        url.push_str("file://");
+        let mut rest = path;
+        if self == Platform::Windows {
+            if let Some(unc) = strip_two_separators(path) {
+                let end = unc.find(is_windows_separator).unwrap_or(unc.len());
+                url.push_str(&encode_set::HOST.encode(&unc[..end]));
+                rest = &unc[end..];
+            }
+        } else {
+            // Backslashes are ordinary filename characters here.
+            return posix_path_to_file_url(path);
+        }
+
+        let mut pieces = rest.split(is_windows_separator).peekable();
+        if pieces.peek() == Some(&"") {
+            pieces.next();
+        }
+        for piece in pieces {
+            url.push('/');
+            url.push_str(&encode_file_component(piece));
+        }
+        if url.len() == "file://".len() {
+            url.push('/');
+        }
+        url
+    }
+
+    /// Converts a file URL to a path in this platform's syntax. Only
+    /// fails if the URL doesn't decode to UTF-8, in which case the
+    /// error's `as_bytes` is the decoded segment that failed; nothing
+    /// is ever replaced with U+FFFD.
+    ///
+    /// On Windows a non-local host becomes a UNC path. POSIX has no
+    /// equivalent, so there the host is kept as a leading `//host`,
+    /// as Python's `url2pathname` does.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::Platform;
+    ///
+    /// let path = Platform::Windows.file_url_to_path("file:///C:/My%20Files/a.txt").unwrap();
+    /// assert_eq!(path, r"C:\My Files\a.txt");
+    ///
+    /// let path = Platform::Windows.file_url_to_path("file://server/share/a.txt").unwrap();
+    /// assert_eq!(path, r"\\server\share\a.txt");
+    ///
+    /// let path = Platform::Posix.file_url_to_path("file:///tmp/a%20b").unwrap();
+    /// assert_eq!(path, "/tmp/a b");
+    /// ```
+    pub fn file_url_to_path(self, file_url: &str) -> Result<String, FromUtf8Error> {
+        let (host, path) = split_authority(file_url);
+        let host = decode(host)?;
+        let mut segments = path
+            .split('/')
+            .map(|seg| decode(seg).map(|d| d.into_owned()))
+            .collect::<Result<Segments<String>, FromUtf8Error>>()?;
+        // The path is absolute, so its first piece is always empty.
+        if segments.len() > 1 && segments[0].is_empty() {
+            segments.remove(0);
+        }
+
+        match self {
+            Platform::Posix => {
+                let joined = segments.join("/");
+                if host.is_empty() {
+                    Ok(format!("/{}", joined))
+                } else {
+                    Ok(format!("//{}/{}", host, joined))
+                }
+            }
+            Platform::Windows => {
+                if !host.is_empty() {
+                    return Ok(format!(r"\\{}\{}", host, segments.join("\\")));
+                }
+                if is_drive(&segments[0]) {
+                    // A bare drive names its root directory.
+                    if segments.len() == 1 {
+                        segments.push(String::new());
+                    }
+                    Ok(segments.join("\\"))
+                } else {
+                    Ok(format!("\\{}", segments.join("\\")))
+                }
+            }
+        }
+    }
+}
+
+fn posix_path_to_file_url(path: &str) -> String {
+    let mut url = String::with_capacity(file_url_len(path, |b| b == b'/'));
+    url.push_str("file://");
+    let mut pieces = path.split('/').peekable();
+    if pieces.peek() == Some(&"") {
+        pieces.next();
+    }
+    for piece in pieces {
+        url.push('/');
+        // `encode_file_component` would pass a piece with a backslash
+        // through untouched.
+        url.push_str(&encode(piece));
+    }
+    if url.len() == "file://".len() {
+        url.push('/');
+    }
+    url
+}
+
+fn is_windows_separator(c: char) -> bool {
+    c == '/' || c == '\\'
+}
+
+fn strip_two_separators(path: &str) -> Option<&str> {
+    let mut chars = path.chars();
+    match (chars.next(), chars.next()) {
+        (Some(a), Some(b)) if is_windows_separator(a) && is_windows_separator(b) => {
+            Some(chars.as_str())
+        }
+        _ => None,
+    }
+}
+
+/// Splits a file URL into its host, with `localhost` mapped to empty,
+/// and its path. The scheme is optional.
+pub(crate) fn split_authority(file_url: &str) -> (&str, &str) {
+    let rest = match file_url.get(..5) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("file:") => &file_url[5..],
+        _ => file_url,
+    };
+    if let Some(legacy) = legacy_unc(rest) {
+        return legacy;
+    }
+    match rest.strip_prefix("//") {
+        Some(auth_and_path) => {
+            let end = auth_and_path.find('/').unwrap_or(auth_and_path.len());
+            let host = &auth_and_path[..end];
+            let host = if host.eq_ignore_ascii_case("localhost") {
+                ""
+            } else {
+                host
+            };
+            (host, &auth_and_path[end..])
+        }
+        None => ("", rest),
+    }
+}
+
+fn is_drive(seg: &str) -> bool {
+    let bytes = seg.as_bytes();
+    bytes.len() == 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_paths_to_urls() {
+        let win = Platform::Windows;
+        assert_eq!(win.path_to_file_url(r"C:\"), "file:///C:/");
+        assert_eq!(win.path_to_file_url("C:/a/b c"), "file:///C:/a/b%20c");
+        assert_eq!(win.path_to_file_url(r"\a\b"), "file:///a/b");
+        assert_eq!(
+            win.path_to_file_url(r"\\my server\my share\a"),
+            "file://my%20server/my%20share/a"
+        );
+        assert_eq!(win.path_to_file_url(r"\\host\share"), "file://host/share");
+        assert_eq!(win.path_to_file_url(r"C:\a#b"), "file:///C:/a%23b");
+    }
+
+    #[test]
+    fn windows_urls_to_paths() {
+        let win = Platform::Windows;
+        assert_eq!(win.file_url_to_path("file:///C:").unwrap(), r"C:\");
+        assert_eq!(win.file_url_to_path("file:///C:/").unwrap(), r"C:\");
+        assert_eq!(
+            win.file_url_to_path("file://localhost/d:/x").unwrap(),
+            r"d:\x"
+        );
+        assert_eq!(win.file_url_to_path("file:///a/b").unwrap(), r"\a\b");
+        assert_eq!(
+            win.file_url_to_path("file:///c%3A/Users/x").unwrap(),
+            r"c:\Users\x"
+        );
+        for legacy in ["file:////srv/share/x", "file://///srv/share/x"].iter() {
+            assert_eq!(win.file_url_to_path(legacy).unwrap(), r"\\srv\share\x");
+        }
+        let err = win.file_url_to_path("file:///C:/ok/b%E4d/x").unwrap_err();
+        assert_eq!(err.as_bytes(), b"b\xe4d");
+    }
+
+    #[test]
+    fn posix_keeps_backslashes() {
+        let posix = Platform::Posix;
+        assert_eq!(posix.path_to_file_url("/"), "file:///");
+        assert_eq!(posix.path_to_file_url(r"/a\b"), "file:///a%5Cb");
+        assert_eq!(posix.file_url_to_path("file:///a%5Cb").unwrap(), r"/a\b");
+        assert_eq!(posix.file_url_to_path("file://host/x").unwrap(), "//host/x");
+        assert_eq!(posix.file_url_to_path("file:///").unwrap(), "/");
+    }
+
+    #[test]
+    fn round_trips() {
+        for (platform, path) in [
+            (Platform::Windows, r"C:\Program Files\x%41.txt"),
+            (Platform::Windows, r"\\srv\share\a b"),
+            (Platform::Windows, r"\\my srv\my share\a"),
+            (Platform::Posix, "/home/me/100% done?.txt"),
+        ]
+        .iter()
+        {
+            let url = platform.path_to_file_url(path);
+            assert_eq!(&platform.file_url_to_path(&url).unwrap(), path);
+        }
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Platform::from_name("Win32"), Some(Platform::Windows));
+        assert_eq!(Platform::from_name("posix"), Some(Platform::Posix));
+        assert_eq!(Platform::from_name("unix"), Some(Platform::Posix));
+        assert_eq!(Platform::from_name("plan9"), None);
+    }
+}
@@ -0,0 +1,74 @@
+//! Python bindings via PyO3, behind the `python` feature. Build the
+//! extension module with `maturin build`, which picks up the features
+//! listed in `pyproject.toml`.
+//!
+//! ```python
+//! import file_url
+//!
+//! file_url.path_to_file_url("/tmp/a b.txt")    # 'file:///tmp/a%20b.txt'
+//! file_url.file_url_to_path("file:///tmp/a%20b.txt")
+//! ```
+use std::path::PathBuf;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::{file_url_to_pathbuf, PathFileUrlExt};
+
+/// Converts a path (a `str` or any `os.PathLike`) to a file URL.
+/// Raises `ValueError` if the path isn't valid UTF-8.
+#[pyfunction]
+fn path_to_file_url(path: PathBuf) -> PyResult<String> {
+    path.to_file_url()
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Converts a file URL to a path. Raises `ValueError` if the URL
+/// doesn't decode to UTF-8.
+#[pyfunction]
+fn file_url_to_path(file_url: &str) -> PyResult<PathBuf> {
+    file_url_to_pathbuf(file_url).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// The `file_url` Python module.
+#[pymodule]
+#[pyo3(name = "file_url")]
+pub fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(path_to_file_url, m)?)?;
+    m.add_function(wrap_pyfunction!(file_url_to_path, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn callable_from_python() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "file_url").unwrap();
+            python_module(&module).unwrap();
+
+            let url: String = module
+                .getattr("path_to_file_url")
+                .unwrap()
+                .call1(("/tmp/a b.txt",))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(url, "file:///tmp/a%20b.txt");
+
+            let to_path = module.getattr("file_url_to_path").unwrap();
+            let path: PathBuf = to_path
+                .call1(("file:///tmp/a%20b.txt",))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(path, PathBuf::from("/tmp/a b.txt"));
+
+            let err = to_path.call1(("file:///%FF",)).unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+        });
+    }
+}
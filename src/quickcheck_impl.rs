@@ -0,0 +1,125 @@
+//! `quickcheck::Arbitrary` implementations, behind the `quickcheck`
+//! feature.
+use std::path::PathBuf;
+
+use quickcheck::{Arbitrary, Gen};
+
+use crate::{encode_file_component, FileUrlBuf};
+
+/// An absolute path that survives a trip through `to_file_url` and
+/// `file_url_to_pathbuf` unchanged, for property tests of code built
+/// on both directions.
+///
+/// # Example:
+/// ```
+/// use file_url::{file_url_to_pathbuf, PathFileUrlExt, RoundTripPath};
+/// use quickcheck::{Arbitrary, Gen};
+///
+/// let RoundTripPath(path) = RoundTripPath::arbitrary(&mut Gen::new(10));
+/// let url = path.to_file_url().unwrap();
+/// assert_eq!(file_url_to_pathbuf(&url).unwrap(), path);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundTripPath(pub PathBuf);
+
+/// A segment that is a single path component: no separators (file
+/// URLs treat `\` as one too), no NUL, and not a dot segment.
+fn arbitrary_segment(g: &mut Gen) -> String {
+    loop {
+        let seg: String = String::arbitrary(g)
+            .chars()
+            .filter(|c| !matches!(c, '/' | '\\' | '\0'))
+            .collect();
+        if !matches!(seg.as_str(), "" | "." | "..") {
+            return seg;
+        }
+    }
+}
+
+impl Arbitrary for RoundTripPath {
+    fn arbitrary(g: &mut Gen) -> RoundTripPath {
+        let count = usize::arbitrary(g) % 8;
+        let mut path = PathBuf::from("/");
+        for _ in 0..count {
+            path.push(arbitrary_segment(g));
+        }
+        RoundTripPath(path)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = RoundTripPath>> {
+        let parents: Vec<RoundTripPath> = self
+            .0
+            .ancestors()
+            .skip(1)
+            .map(|p| RoundTripPath(p.to_path_buf()))
+            .collect();
+        Box::new(parents.into_iter())
+    }
+}
+
+const HOSTS: &[&str] = &["", "", "localhost", "server", "[::1]", "h%C3%BCst"];
+const DRIVES: &[&str] = &["", "", "", "C:", "c:", "z:"];
+
+impl Arbitrary for FileUrlBuf {
+    fn arbitrary(g: &mut Gen) -> FileUrlBuf {
+        let mut url = format!("file://{}/", g.choose(HOSTS).unwrap());
+        let drive = g.choose(DRIVES).unwrap();
+        if !drive.is_empty() {
+            url.push_str(drive);
+            url.push('/');
+        }
+
+        let count = usize::arbitrary(g) % 8;
+        for i in 0..count {
+            if i > 0 {
+                url.push('/');
+            }
+            url.push_str(&encode_file_component(&arbitrary_segment(g)));
+        }
+        if count > 0 && bool::arbitrary(g) {
+            url.push('/');
+        }
+        FileUrlBuf::from_string_unchecked(url)
+    }
+
+    /// Shrinks towards the root by dropping trailing segments.
+    fn shrink(&self) -> Box<dyn Iterator<Item = FileUrlBuf>> {
+        let url = self.as_str().trim_end_matches('/');
+        let prefix_len = url.find("//").map_or(0, |i| i + 2);
+        let shrunk: Vec<FileUrlBuf> = url[prefix_len..]
+            .match_indices('/')
+            .map(|(i, _)| FileUrlBuf::from_string_unchecked(url[..prefix_len + i + 1].to_string()))
+            .filter(|parent| parent.as_str() != self.as_str())
+            .collect();
+        Box::new(shrunk.into_iter().rev())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{file_url_to_pathbuf, FileUrl, PathFileUrlExt};
+    use quickcheck::quickcheck;
+
+    quickcheck! {
+        fn round_trip_paths_round_trip(path: RoundTripPath) -> bool {
+            let url = path.0.to_file_url().unwrap();
+            file_url_to_pathbuf(&url).unwrap() == path.0
+        }
+
+        fn generated_urls_validate(url: FileUrlBuf) -> bool {
+            FileUrl::new(url.as_str()).is_ok()
+                && url.shrink().all(|s| FileUrl::new(s.as_str()).is_ok())
+        }
+    }
+
+    #[test]
+    fn shrinks_to_parents() {
+        let url: FileUrlBuf = "file://host/a/b/c".parse().unwrap();
+        let shrunk: Vec<String> = url.shrink().map(|u| u.into_string()).collect();
+        assert_eq!(
+            shrunk,
+            vec!["file://host/a/b/", "file://host/a/", "file://host/"]
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//! Parallel batch conversions, behind the `rayon` feature.
+use std::path::{Path, PathBuf};
+use std::string::FromUtf8Error;
+
+use rayon::prelude::*;
+
+use crate::{file_url_to_pathbuf, PathFileUrlExt, UTFDecodeError};
+
+/// Converts a batch of paths to file URLs on the rayon thread pool.
+/// Results are in the same order as the input, and a path that isn't
+/// valid UTF-8 fails on its own without affecting the rest.
+///
+/// # Example:
+/// ```
+/// use std::path::PathBuf;
+/// use file_url::par_paths_to_file_urls;
+///
+/// let paths = vec![PathBuf::from("/a b"), PathBuf::from("/c")];
+/// let urls: Vec<String> = par_paths_to_file_urls(&paths)
+///     .into_iter()
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(urls, vec!["file:///a%20b", "file:///c"]);
+/// ```
+pub fn par_paths_to_file_urls<P>(paths: &[P]) -> Vec<Result<String, UTFDecodeError>>
+where
+    P: AsRef<Path> + Sync,
+{
+    paths.par_iter().map(|p| p.as_ref().to_file_url()).collect()
+}
+
+/// Decodes a batch of file URLs on the rayon thread pool, preserving
+/// input order.
+///
+/// # Example:
+/// ```
+/// use std::path::PathBuf;
+/// use file_url::par_file_urls_to_paths;
+///
+/// let paths = par_file_urls_to_paths(&["file:///a%20b", "file:///%FF"]);
+/// assert_eq!(paths[0].as_ref().unwrap(), &PathBuf::from("/a b"));
+/// assert!(paths[1].is_err());
+/// ```
+pub fn par_file_urls_to_paths<S>(urls: &[S]) -> Vec<Result<PathBuf, FromUtf8Error>>
+where
+    S: AsRef<str> + Sync,
+{
+    urls.par_iter()
+        .map(|u| file_url_to_pathbuf(u.as_ref()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_serial_conversion() {
+        let paths: Vec<PathBuf> = (0..1000)
+            .map(|i| PathBuf::from(format!("/dir {}/file#{}.txt", i % 7, i)))
+            .collect();
+        let urls: Vec<String> = par_paths_to_file_urls(&paths)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        for (path, url) in paths.iter().zip(urls.iter()) {
+            assert_eq!(&path.to_file_url().unwrap(), url);
+        }
+
+        let back: Vec<PathBuf> = par_file_urls_to_paths(&urls)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(back, paths);
+    }
+}
@@ -0,0 +1,262 @@
+//! Relative references between file URLs.
+use crate::classify::legacy_unc;
+use crate::percent::decode;
+
+use crate::{FileUrl, FileUrlBuf, Segments};
+
+/// A file URL broken into its authority and its (still encoded)
+/// path segments, with dot segments already resolved.
+pub(crate) struct UrlParts<'a> {
+    pub(crate) host: &'a str,
+    pub(crate) segments: Segments<&'a str>,
+}
+
+impl<'a> UrlParts<'a> {
+    /// Splits an absolute file URL. Returns `None` for anything that
+    /// isn't one, e.g. another scheme or `file:foo`.
+    pub(crate) fn parse(file_url: &'a str) -> Option<UrlParts<'a>> {
+        let scheme = file_url.get(..5)?;
+        if !scheme.eq_ignore_ascii_case("file:") {
+            return None;
+        }
+
+        let rest = &file_url[5..];
+        let (host, path) = match (legacy_unc(rest), rest.strip_prefix("//")) {
+            (Some((host, "")), _) => (host, "/"),
+            (Some(legacy), _) => legacy,
+            (None, Some(auth_and_path)) => match auth_and_path.find('/') {
+                Some(i) => auth_and_path.split_at(i),
+                None => (auth_and_path, "/"),
+            },
+            (None, None) => ("", rest),
+        };
+
+        let path = path.strip_prefix('/')?;
+        let mut segments: Segments<&str> = Segments::new();
+        let mut pieces = path.split('/').peekable();
+        while let Some(piece) = pieces.next() {
+            let last = pieces.peek().is_none();
+            match piece {
+                "." | ".." => {
+                    if piece == ".." {
+                        segments.pop();
+                    }
+                    // A trailing dot segment still names a directory.
+                    if last {
+                        segments.push("");
+                    }
+                }
+                _ => segments.push(piece),
+            }
+        }
+
+        Some(UrlParts {
+            host: if host.eq_ignore_ascii_case("localhost") {
+                ""
+            } else {
+                host
+            },
+            segments,
+        })
+    }
+
+    pub(crate) fn same_host(&self, other: &UrlParts) -> bool {
+        self.host.eq_ignore_ascii_case(other.host)
+    }
+}
+
+/// Compares two encoded segments by their decoded value, so that
+/// e.g. `a%20b` and `a b` are treated the same.
+pub(crate) fn segments_eq(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    match (decode(a), decode(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Like `segments_eq`, but ignoring case the way NTFS does, by
+/// comparing the uppercased segments.
+pub(crate) fn segments_eq_ignore_case(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    match (decode(a), decode(b)) {
+        (Ok(a), Ok(b)) => a
+            .chars()
+            .flat_map(char::to_uppercase)
+            .eq(b.chars().flat_map(char::to_uppercase)),
+        _ => false,
+    }
+}
+
+/// Computes the relative URL that, resolved against `from`, yields
+/// `to`. As with any URL reference the base is the *directory* of
+/// `from`, so `from` should end in a `/` if it names a directory.
+/// Returns `None` if either argument isn't an absolute file URL or
+/// if they point at different hosts.
+///
+/// # Examples:
+/// ```
+/// use file_url::relative_url;
+///
+/// let rel = relative_url("file:///site/docs/a/index.html", "file:///site/img/logo.png");
+/// assert_eq!(rel.unwrap(), "../../img/logo.png");
+///
+/// let rel = relative_url("file:///site/docs/", "file:///site/docs/b/page.html");
+/// assert_eq!(rel.unwrap(), "b/page.html");
+/// ```
+pub fn relative_url(from: &str, to: &str) -> Option<String> {
+    let from = UrlParts::parse(from)?;
+    let to = UrlParts::parse(to)?;
+    if !from.same_host(&to) {
+        return None;
+    }
+
+    // The last segment is the file name (or empty for a directory).
+    let base_dir = &from.segments[..from.segments.len() - 1];
+    let (to_dir, _) = to.segments.split_at(to.segments.len() - 1);
+
+    let shared = base_dir
+        .iter()
+        .zip(to_dir.iter())
+        .take_while(|(a, b)| segments_eq(a, b))
+        .count();
+
+    let mut rel = "../".repeat(base_dir.len() - shared);
+    rel.push_str(&to.segments[shared..].join("/"));
+
+    // An empty reference means "this document", and a leading segment
+    // with a colon would be read as a scheme, so anchor both with `./`.
+    let first = to.segments[shared];
+    if rel.is_empty() || (shared == base_dir.len() && first.contains(':')) {
+        rel.insert_str(0, "./");
+    }
+
+    Some(rel)
+}
+
+/// Finds the deepest directory URL containing every one of `urls`.
+/// Each URL's last segment is taken to be a file name, so pass
+/// directories with a trailing `/`. Returns `None` for an empty
+/// iterator or if the URLs are on different hosts.
+///
+/// # Example:
+/// ```
+/// use file_url::{common_ancestor, FileUrl};
+///
+/// let urls = vec![
+///     FileUrl::new("file:///data/2021/jan.csv").unwrap(),
+///     FileUrl::new("file:///data/2020/dec.csv").unwrap(),
+/// ];
+/// let root = common_ancestor(urls).unwrap();
+/// assert_eq!(root.as_str(), "file:///data/");
+/// ```
+pub fn common_ancestor<'a, I>(urls: I) -> Option<FileUrlBuf>
+where
+    I: IntoIterator<Item = &'a FileUrl>,
+{
+    let mut urls = urls.into_iter();
+    let first = urls.next()?.parts();
+    let mut shared = &first.segments[..first.segments.len() - 1];
+
+    for url in urls {
+        let parts = url.parts();
+        if !first.same_host(&parts) {
+            return None;
+        }
+        let dir = &parts.segments[..parts.segments.len() - 1];
+        let len = shared
+            .iter()
+            .zip(dir.iter())
+            .take_while(|(a, b)| segments_eq(a, b))
+            .count();
+        shared = &shared[..len];
+    }
+
+    let mut ancestor = format!("file://{}/", first.host);
+    for seg in shared {
+        ancestor.push_str(seg);
+        ancestor.push('/');
+    }
+    Some(FileUrlBuf::from_string_unchecked(ancestor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sibling_file() {
+        let rel = relative_url("file:///a/b/c.txt", "file:///a/b/d.txt").unwrap();
+        assert_eq!(rel, "d.txt");
+    }
+
+    #[test]
+    fn up_and_over() {
+        let rel = relative_url("file:///a/b/c/x.html", "file:///a/d/y.txt").unwrap();
+        assert_eq!(rel, "../../d/y.txt");
+    }
+
+    #[test]
+    fn shared_prefix_is_per_segment() {
+        let rel = relative_url("file:///foo/x.txt", "file:///foobar/y.txt").unwrap();
+        assert_eq!(rel, "../foobar/y.txt");
+    }
+
+    #[test]
+    fn encoded_segments_compare_decoded() {
+        let rel = relative_url("file:///my%20dir/a.txt", "file:///my dir/b%20c.txt").unwrap();
+        assert_eq!(rel, "b%20c.txt");
+    }
+
+    #[test]
+    fn target_is_base_directory() {
+        let rel = relative_url("file:///a/b/c.txt", "file:///a/b/").unwrap();
+        assert_eq!(rel, "./");
+    }
+
+    #[test]
+    fn colon_in_first_segment() {
+        let rel = relative_url("file:///a/", "file:///a/C:stuff").unwrap();
+        assert_eq!(rel, "./C:stuff");
+    }
+
+    #[test]
+    fn dot_segments_are_resolved() {
+        let rel = relative_url("file:///a/./b/../c/x.txt", "file:///a/c/y.txt").unwrap();
+        assert_eq!(rel, "y.txt");
+    }
+
+    #[test]
+    fn rejects_mismatched_inputs() {
+        assert!(relative_url("file:///a/b", "http://example.com/a").is_none());
+        assert!(relative_url("file://one/a/b", "file://two/a/c").is_none());
+        assert!(relative_url("file:foo", "file:///foo").is_none());
+        assert!(relative_url("file://localhost/a/b", "file:///a/c").is_some());
+    }
+
+    #[test]
+    fn ancestor_of_several() {
+        let urls: Vec<FileUrlBuf> = ["file:///a/b/c/x", "file:///a/b/y", "file:///a/b/c/d/"]
+            .iter()
+            .map(|u| u.parse().unwrap())
+            .collect();
+        let root = common_ancestor(urls.iter().map(|u| u.as_file_url())).unwrap();
+        assert_eq!(root.as_str(), "file:///a/b/");
+    }
+
+    #[test]
+    fn ancestor_edge_cases() {
+        assert!(common_ancestor(Vec::<&FileUrl>::new()).is_none());
+
+        let a = FileUrl::new("file:///x/1").unwrap();
+        let b = FileUrl::new("file:///y/2").unwrap();
+        assert_eq!(common_ancestor(vec![a, b]).unwrap().as_str(), "file:///");
+
+        let c = FileUrl::new("file://server/x/1").unwrap();
+        assert!(common_ancestor(vec![a, c]).is_none());
+    }
+}
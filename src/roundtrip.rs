@@ -0,0 +1,206 @@
+//! Checking that a path survives a trip through a file URL and back.
+use std::error::Error;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{is_separator, Component, Components, Path};
+use std::string::FromUtf8Error;
+
+use crate::{file_url_to_pathbuf, PathFileUrlExt};
+
+/// Where a path changed on its way through a file URL, as reported
+/// by `verify_roundtrip`. Every variant but `NotUtf8` carries the
+/// URL the path was encoded to.
+#[derive(Debug)]
+pub enum RoundtripError {
+    /// The path isn't UTF-8, so it has no file URL. `lossy` is the
+    /// path with the offending bytes replaced by U+FFFD.
+    NotUtf8 { lossy: String },
+    /// The URL didn't decode back to a UTF-8 path.
+    Decode { url: String, source: FromUtf8Error },
+    /// The prefix and root, like `C:\` or `/`, came back different.
+    PrefixChanged {
+        url: String,
+        original: OsString,
+        decoded: OsString,
+    },
+    /// The component at `index`, counting from the first one after
+    /// the prefix and root, came back different, or was added or
+    /// lost.
+    ComponentChanged {
+        url: String,
+        index: usize,
+        original: Option<OsString>,
+        decoded: Option<OsString>,
+    },
+    /// The path ended in a separator and the decoded one doesn't.
+    TrailingSlashDropped { url: String },
+}
+
+impl fmt::Display for RoundtripError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoundtripError::NotUtf8 { lossy } => {
+                write!(f, "Path {} is not UTF-8 compatible!", lossy)
+            }
+            RoundtripError::Decode { url, .. } => write!(f, "{} does not decode to UTF-8!", url),
+            RoundtripError::PrefixChanged {
+                url,
+                original,
+                decoded,
+            } => write!(
+                f,
+                "{} changed the path prefix from {:?} to {:?}!",
+                url, original, decoded
+            ),
+            RoundtripError::ComponentChanged {
+                url,
+                index,
+                original,
+                decoded,
+            } => write!(
+                f,
+                "{} changed component {} from {:?} to {:?}!",
+                url, index, original, decoded
+            ),
+            RoundtripError::TrailingSlashDropped { url } => {
+                write!(f, "{} dropped the trailing slash!", url)
+            }
+        }
+    }
+}
+
+impl Error for RoundtripError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RoundtripError::Decode { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Encodes `path` with `to_file_url`, decodes the result with
+/// `file_url_to_pathbuf`, and reports the first place the two paths
+/// differ. Paths are compared by component, so differences `Path`
+/// itself ignores, like doubled separators or `.` components, aren't
+/// reported. Meant for test suites checking their own path corpora.
+///
+/// # Example:
+/// ```
+/// use std::path::Path;
+/// use file_url::{verify_roundtrip, RoundtripError};
+///
+/// assert!(verify_roundtrip(Path::new("/srv/a b/ü.txt")).is_ok());
+///
+/// match verify_roundtrip(Path::new("/srv/dir/")) {
+///     Err(RoundtripError::TrailingSlashDropped { url }) => assert_eq!(url, "file:///srv/dir"),
+///     other => panic!("{:?}", other),
+/// }
+/// ```
+pub fn verify_roundtrip(path: &Path) -> Result<(), RoundtripError> {
+    let url = path.to_file_url().map_err(|_| RoundtripError::NotUtf8 {
+        lossy: path.to_string_lossy().into_owned(),
+    })?;
+    let decoded = match file_url_to_pathbuf(&url) {
+        Ok(decoded) => decoded,
+        Err(source) => return Err(RoundtripError::Decode { url, source }),
+    };
+
+    let (original_prefix, mut original) = split_prefix(path);
+    let (decoded_prefix, mut back) = split_prefix(&decoded);
+    if original_prefix != decoded_prefix {
+        return Err(RoundtripError::PrefixChanged {
+            url,
+            original: original_prefix,
+            decoded: decoded_prefix,
+        });
+    }
+    let mut index = 0;
+    loop {
+        match (original.next(), back.next()) {
+            (None, None) => break,
+            (a, b) if a == b => index += 1,
+            (a, b) => {
+                return Err(RoundtripError::ComponentChanged {
+                    url,
+                    index,
+                    original: a.map(|c| c.as_os_str().to_owned()),
+                    decoded: b.map(|c| c.as_os_str().to_owned()),
+                })
+            }
+        }
+    }
+
+    if ends_with_separator(path) && !ends_with_separator(&decoded) {
+        return Err(RoundtripError::TrailingSlashDropped { url });
+    }
+    Ok(())
+}
+
+/// The prefix and root of `path` run together, and the components
+/// after them.
+fn split_prefix(path: &Path) -> (OsString, Components<'_>) {
+    let mut prefix = OsString::new();
+    let mut components = path.components();
+    loop {
+        let mut rest = components.clone();
+        match rest.next() {
+            Some(c @ Component::Prefix(_)) | Some(c @ Component::RootDir) => {
+                prefix.push(c.as_os_str());
+                components = rest;
+            }
+            _ => return (prefix, components),
+        }
+    }
+}
+
+fn ends_with_separator(path: &Path) -> bool {
+    let text = path.as_os_str().to_string_lossy();
+    // A bare root has nothing to drop.
+    path.components().count() > 1 && text.ends_with(is_separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_where_paths_change() {
+        for ok in ["/", "/a/b c", "/a//./b", "/ü/%41/#"].iter() {
+            assert!(verify_roundtrip(Path::new(ok)).is_ok(), "{}", ok);
+        }
+
+        match verify_roundtrip(Path::new("/x/y/")) {
+            Err(RoundtripError::TrailingSlashDropped { url }) => assert_eq!(url, "file:///x/y"),
+            other => panic!("{:?}", other),
+        }
+        if cfg!(unix) {
+            // Relative paths come back rooted.
+            match verify_roundtrip(Path::new("rel/x")) {
+                Err(RoundtripError::PrefixChanged {
+                    original, decoded, ..
+                }) => {
+                    assert_eq!(original, "");
+                    assert_eq!(decoded, "/");
+                }
+                other => panic!("{:?}", other),
+            }
+            // The backslash is escaped on the way out but decodes back
+            // intact, so this one does survive.
+            assert!(verify_roundtrip(Path::new("/a\\b")).is_ok());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_paths_are_reported() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let err = verify_roundtrip(Path::new(OsStr::from_bytes(b"/a\xffb"))).unwrap_err();
+        match &err {
+            RoundtripError::NotUtf8 { lossy } => assert_eq!(lossy, "/a\u{fffd}b"),
+            other => panic!("{:?}", other),
+        }
+        assert!(err.to_string().contains("not UTF-8"));
+    }
+}
@@ -0,0 +1,309 @@
+//! Helpers for handling file URLs from untrusted sources.
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::string::FromUtf8Error;
+
+use crate::percent::decode_binary;
+
+use crate::{file_url_to_pathbuf, scan};
+
+/// Strips the scheme and authority from a file URL, leaving the path.
+/// Anything without a `file:` scheme is taken to be a bare path.
+fn url_path(url: &str) -> &str {
+    match url.get(..5) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("file:") => {
+            let rest = &url[5..];
+            match rest.strip_prefix("//") {
+                Some(auth_and_path) => match auth_and_path.find('/') {
+                    Some(i) => &auth_and_path[i..],
+                    None => "",
+                },
+                None => rest,
+            }
+        }
+        _ => url,
+    }
+}
+
+/// Checks whether the URL contains `..` segments that would climb
+/// above its root once normalized, e.g. `file:///../etc/passwd`.
+/// Percent-encoded dots (`%2e%2E`) and encoded separators (`..%2F..`)
+/// are decoded before checking, since they come out the other side
+/// of decoding as real traversals. A `..` that stays beneath the
+/// root, as in `file:///a/../b`, is not flagged.
+///
+/// # Examples:
+/// ```
+/// use file_url::contains_traversal;
+///
+/// assert!(contains_traversal("file:///../etc/passwd"));
+/// assert!(contains_traversal("file:///a/%2e%2e/%2E%2E/etc/passwd"));
+/// assert!(!contains_traversal("file:///a/../b.txt"));
+/// ```
+pub fn contains_traversal(url: &str) -> bool {
+    let mut depth: usize = 0;
+    for piece in url_path(url).split('/') {
+        let decoded = decode_binary(piece.as_bytes());
+        // A backslash is a separator once decoded on Windows, so split
+        // on that too, whether it was escaped or not.
+        let mut rest = &decoded[..];
+        loop {
+            let end = scan::find_separator(rest).unwrap_or(rest.len());
+            match &rest[..end] {
+                b"" | b"." => {}
+                b".." => match depth.checked_sub(1) {
+                    Some(d) => depth = d,
+                    None => return true,
+                },
+                _ => depth += 1,
+            }
+            if end == rest.len() {
+                break;
+            }
+            rest = &rest[end + 1..];
+        }
+    }
+    false
+}
+
+/// Error for URLs a `SandboxedResolver` refuses to resolve.
+#[derive(Debug)]
+pub enum SandboxError {
+    /// The URL didn't decode to a UTF-8 path.
+    Decode(FromUtf8Error),
+    /// The URL's `..` segments climb above the filesystem root.
+    Traversal,
+    /// The URL names a path outside the sandbox root.
+    OutsideRoot(PathBuf),
+    /// Following symlinks required touching the filesystem, which failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for SandboxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SandboxError::Decode(_) => write!(f, "File URL does not decode to UTF-8!"),
+            SandboxError::Traversal => write!(f, "File URL escapes the filesystem root!"),
+            SandboxError::OutsideRoot(p) => {
+                write!(f, "{} is outside the sandbox root!", p.display())
+            }
+            SandboxError::Io(_) => write!(f, "Could not resolve symlinks!"),
+        }
+    }
+}
+
+impl Error for SandboxError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SandboxError::Decode(e) => Some(e),
+            SandboxError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<SandboxError> for io::Error {
+    /// Undecodable URLs become `InvalidInput` and escapes become
+    /// `PermissionDenied`.
+    fn from(err: SandboxError) -> io::Error {
+        match err {
+            SandboxError::Io(e) => e,
+            SandboxError::Decode(_) => io::Error::new(io::ErrorKind::InvalidInput, err),
+            _ => io::Error::new(io::ErrorKind::PermissionDenied, err),
+        }
+    }
+}
+
+/// Resolves file URLs to paths, refusing any that land outside of a
+/// root directory. Guards against zip-slip style attacks where a
+/// client-supplied URL uses `..` (possibly percent-encoded) or, with
+/// `follow_symlinks`, a symlink to reach files it shouldn't.
+///
+/// # Example:
+/// ```
+/// use std::path::{Path, PathBuf};
+/// use file_url::SandboxedResolver;
+///
+/// let sandbox = SandboxedResolver::new(Path::new("/srv/files"));
+///
+/// let ok = sandbox.resolve("file:///srv/files/a/../b%20c.txt").unwrap();
+/// assert_eq!(ok, PathBuf::from("/srv/files/b c.txt"));
+///
+/// assert!(sandbox.resolve("file:///srv/files/%2E%2E/secret").is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct SandboxedResolver {
+    root: PathBuf,
+    follow_symlinks: bool,
+}
+
+impl SandboxedResolver {
+    /// Creates a resolver confined to `root`, which should be absolute.
+    /// Symlinks are not followed by default, so the check is purely
+    /// lexical and never touches the filesystem.
+    pub fn new(root: &Path) -> SandboxedResolver {
+        SandboxedResolver {
+            root: root.to_path_buf(),
+            follow_symlinks: false,
+        }
+    }
+
+    /// Whether to resolve symlinks (via `std::fs::canonicalize`) in
+    /// both the root and the resolved path before checking
+    /// containment. Parts of the path that don't exist yet are
+    /// appended lexically.
+    pub fn follow_symlinks(mut self, follow: bool) -> SandboxedResolver {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// The directory this resolver is confined to.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Decodes and normalizes `url`, returning the path if it lies
+    /// within the root.
+    pub fn resolve(&self, url: &str) -> Result<PathBuf, SandboxError> {
+        let decoded = file_url_to_pathbuf(url).map_err(SandboxError::Decode)?;
+        let mut path = normalize(&decoded).ok_or(SandboxError::Traversal)?;
+        let mut root = normalize(&self.root).ok_or(SandboxError::Traversal)?;
+
+        if self.follow_symlinks {
+            root = canonicalize_existing(&root).map_err(SandboxError::Io)?;
+            path = canonicalize_existing(&path).map_err(SandboxError::Io)?;
+        }
+
+        if path.starts_with(&root) {
+            Ok(path)
+        } else {
+            Err(SandboxError::OutsideRoot(path))
+        }
+    }
+}
+
+/// Lexically resolves `.` and `..` components. Returns `None` if a `..`
+/// would climb above the start of the path.
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normal = PathBuf::new();
+    let mut depth: usize = 0;
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                depth = depth.checked_sub(1)?;
+                normal.pop();
+            }
+            Component::Normal(part) => {
+                depth += 1;
+                normal.push(part);
+            }
+            root => normal.push(root.as_os_str()),
+        }
+    }
+    Some(normal)
+}
+
+/// Canonicalizes the longest existing ancestor of `path` and appends the
+/// rest, so paths to not-yet-created files can still be checked.
+fn canonicalize_existing(path: &Path) -> io::Result<PathBuf> {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        match existing.canonicalize() {
+            Ok(mut real) => {
+                real.extend(rest.iter().rev());
+                return Ok(real);
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => match existing.parent() {
+                Some(parent) => {
+                    // A path with a parent always has a final component.
+                    rest.push(existing.file_name().unwrap());
+                    existing = parent;
+                }
+                None => return Err(e),
+            },
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_traversal() {
+        assert!(contains_traversal("file:///.."));
+        assert!(contains_traversal("file:///a/../../b"));
+        assert!(contains_traversal("file://host/../b"));
+        assert!(contains_traversal(r"file:///a\..\..\b"));
+    }
+
+    #[test]
+    fn encoded_traversal() {
+        assert!(contains_traversal("file:///.%2E/b"));
+        assert!(contains_traversal("file:///%2e./b"));
+        assert!(contains_traversal("file:///a/..%2F..%2Fb"));
+        assert!(contains_traversal("file:///a/..%5C..%5Cb"));
+    }
+
+    #[test]
+    fn harmless_dots() {
+        assert!(!contains_traversal("file:///a/b/../c"));
+        assert!(!contains_traversal("file:///a/./b"));
+        assert!(!contains_traversal("file:///a/...b/..c"));
+        assert!(!contains_traversal("file:///a/%2e%2e"));
+    }
+
+    #[test]
+    fn resolver_is_lexical_by_default() {
+        let sandbox = SandboxedResolver::new(Path::new("/srv/root"));
+        let inside = sandbox.resolve("file:///srv/root/x/./y/../z").unwrap();
+        assert_eq!(inside, PathBuf::from("/srv/root/x/z"));
+
+        match sandbox.resolve("file:///srv/rootkit/x") {
+            Err(SandboxError::OutsideRoot(p)) => assert_eq!(p, PathBuf::from("/srv/rootkit/x")),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(
+            sandbox.resolve("file:///srv/root/..%2F..%2F..%2Fetc"),
+            Err(SandboxError::Traversal)
+        ));
+        assert!(matches!(
+            sandbox.resolve("file:///srv/root/%FF"),
+            Err(SandboxError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn errors_chain_their_causes() {
+        let sandbox = SandboxedResolver::new(Path::new("/srv/root"));
+        let err = sandbox.resolve("file:///srv/root/%FF").unwrap_err();
+        assert_eq!(err.to_string(), "File URL does not decode to UTF-8!");
+        // The cause is left to the source, so reports don't repeat it.
+        let source = err.source().unwrap();
+        assert!(source.is::<FromUtf8Error>());
+        assert!(!err.to_string().contains(&source.to_string()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolver_follows_symlinks() {
+        let base = std::env::temp_dir().join(format!("file_url_sandbox_{}", std::process::id()));
+        let root = base.join("root");
+        std::fs::create_dir_all(&root).unwrap();
+        std::os::unix::fs::symlink(&base, root.join("escape")).unwrap();
+
+        let url = format!("file://{}/escape/secret.txt", root.display());
+        let lexical = SandboxedResolver::new(&root);
+        assert!(lexical.resolve(&url).is_ok());
+
+        let strict = SandboxedResolver::new(&root).follow_symlinks(true);
+        let result = strict.resolve(&url);
+        std::fs::remove_dir_all(&base).unwrap();
+        assert!(matches!(result, Err(SandboxError::OutsideRoot(_))));
+    }
+}
@@ -0,0 +1,255 @@
+//! Byte scans used to find the first byte that needs encoding or
+//! decoding, so clean inputs can skip the per-byte work. With the
+//! `simd` feature, x86_64 (SSE2) and aarch64 (NEON) classify 16 bytes
+//! at a time; other targets, and the tail of every input, use the
+//! scalar loop. With the `memchr` feature, searches for a single
+//! byte go through `memchr` instead.
+
+/// The bytes left alone when percent-encoding, by value: the RFC 3986
+/// unreserved characters.
+static UNRESERVED: [bool; 256] = unreserved_table();
+
+pub(crate) const fn unreserved_table() -> [bool; 256] {
+    let mut table = [false; 256];
+    let mut b = 0;
+    while b < 256 {
+        let c = b as u8;
+        table[b] = c.is_ascii_alphanumeric() || c == b'-' || c == b'.' || c == b'_' || c == b'~';
+        b += 1;
+    }
+    table
+}
+
+/// Whether `b` is an RFC 3986 unreserved character.
+pub(crate) fn is_unreserved(b: u8) -> bool {
+    UNRESERVED[b as usize]
+}
+
+/// The value of an ASCII hex digit, either case.
+pub(crate) fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// The index of the first byte in `bytes` that would be
+/// percent-encoded, or `bytes.len()` if there isn't one.
+pub(crate) fn first_reserved(bytes: &[u8]) -> usize {
+    let start = simd::first_reserved(bytes);
+    start
+        + bytes[start..]
+            .iter()
+            .position(|&b| !is_unreserved(b))
+            .unwrap_or(bytes.len() - start)
+}
+
+/// The length of `bytes` once percent-encoded: every reserved byte
+/// becomes a three byte escape.
+pub(crate) fn encoded_len(bytes: &[u8]) -> usize {
+    bytes.len() + 2 * bytes.iter().filter(|&&b| !is_unreserved(b)).count()
+}
+
+/// The index of the first `%` in `bytes`, or `bytes.len()` if there
+/// isn't one.
+#[cfg(feature = "memchr")]
+pub(crate) fn first_percent(bytes: &[u8]) -> usize {
+    if bytes.len() < 16 {
+        // Too short for memchr's setup to pay off.
+        return bytes.iter().position(|&b| b == b'%').unwrap_or(bytes.len());
+    }
+    memchr::memchr(b'%', bytes).unwrap_or(bytes.len())
+}
+
+/// The index of the first `%` in `bytes`, or `bytes.len()` if there
+/// isn't one.
+#[cfg(not(feature = "memchr"))]
+pub(crate) fn first_percent(bytes: &[u8]) -> usize {
+    let start = simd::first_percent(bytes);
+    start
+        + bytes[start..]
+            .iter()
+            .position(|&b| b == b'%')
+            .unwrap_or(bytes.len() - start)
+}
+
+/// The index of the first `/` in `bytes`, if there is one.
+#[cfg(feature = "memchr")]
+pub(crate) fn find_slash(bytes: &[u8]) -> Option<usize> {
+    memchr::memchr(b'/', bytes)
+}
+
+/// The index of the first `/` in `bytes`, if there is one.
+#[cfg(not(feature = "memchr"))]
+pub(crate) fn find_slash(bytes: &[u8]) -> Option<usize> {
+    bytes.iter().position(|&b| b == b'/')
+}
+
+/// The index of the first `/` or `\` in `bytes`, if there is one.
+#[cfg(all(feature = "std", feature = "memchr"))]
+pub(crate) fn find_separator(bytes: &[u8]) -> Option<usize> {
+    memchr::memchr2(b'/', b'\\', bytes)
+}
+
+/// The index of the first `/` or `\` in `bytes`, if there is one.
+#[cfg(all(feature = "std", not(feature = "memchr")))]
+pub(crate) fn find_separator(bytes: &[u8]) -> Option<usize> {
+    bytes.iter().position(|&b| b == b'/' || b == b'\\')
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod simd {
+    use core::arch::x86_64::*;
+
+    /// How many leading bytes are known clean: a multiple of 16,
+    /// stopping at the first chunk with a reserved byte in it.
+    pub(super) fn first_reserved(bytes: &[u8]) -> usize {
+        let mut i = 0;
+        while i + 16 <= bytes.len() {
+            // SSE2 is part of the x86_64 baseline, and the load is
+            // unaligned and within bounds.
+            let mask = unsafe {
+                let chunk = _mm_loadu_si128(bytes.as_ptr().add(i) as *const __m128i);
+                // Signed comparisons, so bytes >= 0x80 never match.
+                let in_range = |lo: u8, hi: u8| {
+                    _mm_and_si128(
+                        _mm_cmpgt_epi8(chunk, _mm_set1_epi8(lo as i8 - 1)),
+                        _mm_cmplt_epi8(chunk, _mm_set1_epi8(hi as i8 + 1)),
+                    )
+                };
+                let is = |b: u8| _mm_cmpeq_epi8(chunk, _mm_set1_epi8(b as i8));
+                let ok = _mm_or_si128(
+                    _mm_or_si128(in_range(b'a', b'z'), in_range(b'A', b'Z')),
+                    _mm_or_si128(
+                        _mm_or_si128(in_range(b'0', b'9'), is(b'-')),
+                        _mm_or_si128(_mm_or_si128(is(b'.'), is(b'_')), is(b'~')),
+                    ),
+                );
+                _mm_movemask_epi8(ok)
+            };
+            if mask != 0xFFFF {
+                return i + (!mask).trailing_zeros() as usize;
+            }
+            i += 16;
+        }
+        i
+    }
+
+    /// How many leading bytes are known not to be `%`.
+    #[cfg(not(feature = "memchr"))]
+    pub(super) fn first_percent(bytes: &[u8]) -> usize {
+        let mut i = 0;
+        while i + 16 <= bytes.len() {
+            let mask = unsafe {
+                let chunk = _mm_loadu_si128(bytes.as_ptr().add(i) as *const __m128i);
+                _mm_movemask_epi8(_mm_cmpeq_epi8(chunk, _mm_set1_epi8(b'%' as i8)))
+            };
+            if mask != 0 {
+                return i + mask.trailing_zeros() as usize;
+            }
+            i += 16;
+        }
+        i
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+mod simd {
+    use core::arch::aarch64::*;
+
+    /// How many leading bytes are known clean: a multiple of 16,
+    /// stopping at the first chunk with a reserved byte in it.
+    pub(super) fn first_reserved(bytes: &[u8]) -> usize {
+        let mut i = 0;
+        while i + 16 <= bytes.len() {
+            // NEON is part of the aarch64 baseline, and the load is
+            // within bounds.
+            let all_ok = unsafe {
+                let chunk = vld1q_u8(bytes.as_ptr().add(i));
+                let in_range = |lo: u8, hi: u8| {
+                    vandq_u8(
+                        vcgeq_u8(chunk, vdupq_n_u8(lo)),
+                        vcleq_u8(chunk, vdupq_n_u8(hi)),
+                    )
+                };
+                let is = |b: u8| vceqq_u8(chunk, vdupq_n_u8(b));
+                let ok = vorrq_u8(
+                    vorrq_u8(in_range(b'a', b'z'), in_range(b'A', b'Z')),
+                    vorrq_u8(
+                        vorrq_u8(in_range(b'0', b'9'), is(b'-')),
+                        vorrq_u8(vorrq_u8(is(b'.'), is(b'_')), is(b'~')),
+                    ),
+                );
+                vminvq_u8(ok) == 0xFF
+            };
+            if !all_ok {
+                // The scalar loop finds the byte within this chunk.
+                return i;
+            }
+            i += 16;
+        }
+        i
+    }
+
+    /// How many leading bytes are known not to be `%`.
+    #[cfg(not(feature = "memchr"))]
+    pub(super) fn first_percent(bytes: &[u8]) -> usize {
+        let mut i = 0;
+        while i + 16 <= bytes.len() {
+            let any = unsafe {
+                let chunk = vld1q_u8(bytes.as_ptr().add(i));
+                vmaxvq_u8(vceqq_u8(chunk, vdupq_n_u8(b'%'))) != 0
+            };
+            if any {
+                return i;
+            }
+            i += 16;
+        }
+        i
+    }
+}
+
+#[cfg(not(all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+mod simd {
+    pub(super) fn first_reserved(_bytes: &[u8]) -> usize {
+        0
+    }
+
+    #[cfg(not(feature = "memchr"))]
+    pub(super) fn first_percent(_bytes: &[u8]) -> usize {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_scalar_scan() {
+        let mut inputs: Vec<Vec<u8>> = vec![
+            Vec::new(),
+            b"plain-file_name.v2~".to_vec(),
+            b"a much longer clean-ish name that spans chunks".to_vec(),
+        ];
+        // Every byte value at every offset across a few chunks.
+        for offset in [0, 1, 15, 16, 17, 31, 40].iter() {
+            for b in 0..=255u8 {
+                let mut input = vec![b'q'; 48];
+                input[*offset] = b;
+                inputs.push(input);
+            }
+        }
+        for input in inputs.iter() {
+            let reserved = input
+                .iter()
+                .position(|&b| !is_unreserved(b))
+                .unwrap_or(input.len());
+            let percent = input.iter().position(|&b| b == b'%').unwrap_or(input.len());
+            assert_eq!(first_reserved(input), reserved, "{:?}", input);
+            assert_eq!(first_percent(input), percent, "{:?}", input);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! `JsonSchema` for the URL types, behind the `schemars` feature.
+use std::borrow::Cow;
+
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+
+use crate::{FileUrl, FileUrlBuf};
+
+fn file_url_schema() -> Schema {
+    json_schema!({
+        "type": "string",
+        "format": "uri",
+        "pattern": "^[fF][iI][lL][eE]:",
+        "description": "An absolute file URL, e.g. file:///etc/hosts",
+    })
+}
+
+impl JsonSchema for FileUrl {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        "FileUrl".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        "file_url::FileUrl".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        file_url_schema()
+    }
+}
+
+impl JsonSchema for FileUrlBuf {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        "FileUrl".into()
+    }
+
+    // Same schema as `FileUrl`, so share its ID.
+    fn schema_id() -> Cow<'static, str> {
+        "file_url::FileUrl".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        file_url_schema()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_with_uri_format() {
+        let schema = schemars::schema_for!(FileUrlBuf);
+        assert_eq!(schema.get("type").unwrap(), "string");
+        assert_eq!(schema.get("format").unwrap(), "uri");
+        assert_eq!(schema.get("pattern").unwrap(), "^[fF][iI][lL][eE]:");
+        assert_eq!(<&FileUrl>::schema_id(), FileUrlBuf::schema_id());
+    }
+}
@@ -0,0 +1,137 @@
+//! `Serialize` and `Deserialize` for the URL types and
+//! `OutputProfile`, behind the `serde` feature. URLs are
+//! (de)serialized as plain strings and validated on the way in;
+//! profiles as the list of names `OutputProfile`'s `Display` writes.
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::{FileUrl, FileUrlBuf, OutputProfile};
+
+impl Serialize for FileUrl {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl Serialize for FileUrlBuf {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+struct FileUrlBufVisitor;
+
+impl<'de> Visitor<'de> for FileUrlBufVisitor {
+    type Value = FileUrlBuf;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an absolute file URL")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<FileUrlBuf, E> {
+        v.parse().map_err(E::custom)
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<FileUrlBuf, E> {
+        FileUrlBuf::new(v).map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for FileUrlBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<FileUrlBuf, D::Error> {
+        deserializer.deserialize_string(FileUrlBufVisitor)
+    }
+}
+
+struct FileUrlVisitor;
+
+impl<'de> Visitor<'de> for FileUrlVisitor {
+    type Value = &'de FileUrl;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a borrowed absolute file URL")
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<&'de FileUrl, E> {
+        FileUrl::new(v).map_err(E::custom)
+    }
+}
+
+/// Borrows from the input, so like `&str` this only works with
+/// formats and inputs that don't need unescaping.
+impl<'de: 'a, 'a> Deserialize<'de> for &'a FileUrl {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<&'a FileUrl, D::Error> {
+        deserializer.deserialize_str(FileUrlVisitor)
+    }
+}
+
+impl Serialize for OutputProfile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+struct OutputProfileVisitor;
+
+impl<'de> Visitor<'de> for OutputProfileVisitor {
+    type Value = OutputProfile;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a comma-separated list of output profile options")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<OutputProfile, E> {
+        v.parse().map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for OutputProfile {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<OutputProfile, D::Error> {
+        deserializer.deserialize_str(OutputProfileVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owned_round_trip() {
+        let url: FileUrlBuf = "file:///tmp/a%20b".parse().unwrap();
+        let json = serde_json::to_string(&url).unwrap();
+        assert_eq!(json, r#""file:///tmp/a%20b""#);
+        assert_eq!(serde_json::from_str::<FileUrlBuf>(&json).unwrap(), url);
+    }
+
+    #[test]
+    fn borrowed_round_trip() {
+        let json = r#""file:///tmp/x""#;
+        let url: &FileUrl = serde_json::from_str(json).unwrap();
+        assert_eq!(url.as_str(), "file:///tmp/x");
+        assert_eq!(serde_json::to_string(url).unwrap(), json);
+    }
+
+    #[test]
+    fn validates_on_deserialize() {
+        let err = serde_json::from_str::<FileUrlBuf>(r#""https://example.com""#).unwrap_err();
+        assert!(err.to_string().contains("Not an absolute file URL"));
+        assert!(serde_json::from_str::<&FileUrl>(r#""file:foo""#).is_err());
+    }
+
+    #[test]
+    fn profile_round_trip() {
+        let profile: OutputProfile = "localhost,lower-drive,escape=~".parse().unwrap();
+        let json = serde_json::to_string(&profile).unwrap();
+        assert_eq!(json, r#""localhost,lower-drive,escape=~""#);
+        assert_eq!(
+            serde_json::from_str::<OutputProfile>(&json).unwrap(),
+            profile
+        );
+        let err = serde_json::from_str::<OutputProfile>(r#""loud""#).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unknown output profile option loud!"));
+    }
+}
@@ -0,0 +1,91 @@
+//! (De)serializes `PathBuf` fields as file URLs, for use with
+//! `#[serde(with = "file_url::serde_path")]`.
+//!
+//! # Example:
+//! ```
+//! use std::path::PathBuf;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "file_url::serde_path")]
+//!     output: PathBuf,
+//! }
+//!
+//! let config = Config { output: PathBuf::from("/tmp/out dir") };
+//! let json = serde_json::to_string(&config).unwrap();
+//! assert_eq!(json, r#"{"output":"file:///tmp/out%20dir"}"#);
+//!
+//! let back: Config = serde_json::from_str(&json).unwrap();
+//! assert_eq!(back.output, config.output);
+//! ```
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use serde::de::{Deserialize, Deserializer, Error as DeError};
+use serde::ser::{Error as SerError, Serializer};
+
+use crate::{FileUrl, PathFileUrlExt};
+
+/// Serializes a path as a file URL. Fails for paths that aren't
+/// valid UTF-8.
+pub fn serialize<P, S>(path: &P, serializer: S) -> Result<S::Ok, S::Error>
+where
+    P: AsRef<Path> + ?Sized,
+    S: Serializer,
+{
+    let url = path.as_ref().to_file_url().map_err(S::Error::custom)?;
+    serializer.serialize_str(&url)
+}
+
+/// Deserializes a file URL into a `PathBuf`. Anything `FileUrl::new`
+/// rejects, like another scheme or a bare path, is an error.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<PathBuf, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let url = Cow::<str>::deserialize(deserializer)?;
+    let url = FileUrl::new(&url).map_err(D::Error::custom)?;
+    Ok(url.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use std::path::PathBuf;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Manifest {
+        #[serde(with = "crate::serde_path")]
+        root: PathBuf,
+    }
+
+    #[test]
+    fn field_round_trip() {
+        let manifest = Manifest {
+            root: PathBuf::from("/gi>/some & what"),
+        };
+        let json = serde_json::to_string(&manifest).unwrap();
+        assert_eq!(json, r#"{"root":"file:///gi%3E/some%20%26%20what"}"#);
+        assert_eq!(serde_json::from_str::<Manifest>(&json).unwrap(), manifest);
+    }
+
+    #[test]
+    fn bad_url_is_a_deserialize_error() {
+        let json = r#"{"root":"file:///%FF"}"#;
+        assert!(serde_json::from_str::<Manifest>(json).is_err());
+    }
+
+    #[test]
+    fn non_file_strings_are_rejected() {
+        for root in ["https://x/y", "etc/passwd", "/etc/passwd"].iter() {
+            let json = format!(r#"{{"root":"{}"}}"#, root);
+            let err = serde_json::from_str::<Manifest>(&json).unwrap_err();
+            assert!(
+                err.to_string().contains("Not an absolute file URL"),
+                "{}",
+                err
+            );
+        }
+    }
+}
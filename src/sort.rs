@@ -0,0 +1,270 @@
+//! Comparators for sorting lists of file URLs the way a file picker
+//! would, rather than by their raw percent-encoded bytes.
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+use crate::percent::decode;
+
+use crate::{DriveLetterCase, FileUrl};
+
+/// Configurable ordering over `FileUrl`s. Segments are decoded and
+/// compared one at a time, so `a%20b` sorts next to `a b` and a
+/// parent directory always sorts before its children.
+///
+/// # Example:
+/// ```
+/// use file_url::{FileUrl, UrlOrdering};
+///
+/// let mut urls: Vec<&FileUrl> = ["file:///x/file10", "file:///x/file9", "file:///x/sub/"]
+///     .iter()
+///     .map(|u| FileUrl::new(u).unwrap())
+///     .collect();
+///
+/// let order = UrlOrdering { directories_first: true, ..UrlOrdering::default() };
+/// urls.sort_by(|a, b| order.compare(a, b));
+/// let sorted: Vec<&str> = urls.iter().map(|u| u.as_str()).collect();
+/// assert_eq!(sorted, vec!["file:///x/sub/", "file:///x/file9", "file:///x/file10"]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UrlOrdering {
+    /// Compare runs of digits by numeric value, so `file9` < `file10`.
+    pub natural: bool,
+    /// Within a directory, list subdirectories before files. A URL
+    /// ending in `/` is considered a directory.
+    pub directories_first: bool,
+    /// Ignore case when comparing segments.
+    pub case_insensitive: bool,
+    /// Case to put drive letters in before comparing. `Upper` or
+    /// `Lower` makes `file:///c:/x` and `file:///C:/x` equal.
+    pub drive_case: DriveLetterCase,
+}
+
+impl Default for UrlOrdering {
+    /// Natural, case-sensitive ordering with no special treatment
+    /// of directories or drive letters.
+    fn default() -> UrlOrdering {
+        UrlOrdering {
+            natural: true,
+            directories_first: false,
+            case_insensitive: false,
+            drive_case: DriveLetterCase::Preserve,
+        }
+    }
+}
+
+impl UrlOrdering {
+    /// Natural ordering that is case-insensitive on Windows, matching
+    /// how Explorer lists files, and case-sensitive elsewhere. Drive
+    /// letters are compared uppercased.
+    pub fn platform() -> UrlOrdering {
+        UrlOrdering {
+            case_insensitive: cfg!(windows),
+            drive_case: DriveLetterCase::Upper,
+            ..UrlOrdering::default()
+        }
+    }
+
+    /// Compares two URLs according to this ordering.
+    pub fn compare(&self, a: &FileUrl, b: &FileUrl) -> Ordering {
+        let a_parts = a.parts();
+        let b_parts = b.parts();
+
+        let hosts = a_parts
+            .host
+            .to_ascii_lowercase()
+            .cmp(&b_parts.host.to_ascii_lowercase());
+        if hosts != Ordering::Equal {
+            return hosts;
+        }
+
+        let a_segs = &a_parts.segments;
+        let b_segs = &b_parts.segments;
+        for (i, (a_seg, b_seg)) in a_segs.iter().zip(b_segs.iter()).enumerate() {
+            let a_seg = self.prepare(i, a_seg);
+            let b_seg = self.prepare(i, b_seg);
+            if a_seg == b_seg {
+                continue;
+            }
+            // An empty final segment is the directory itself, which
+            // comes before anything inside it.
+            if a_seg.is_empty() {
+                return Ordering::Less;
+            }
+            if b_seg.is_empty() {
+                return Ordering::Greater;
+            }
+            if self.directories_first {
+                let a_dir = i + 1 < a_segs.len();
+                let b_dir = i + 1 < b_segs.len();
+                if a_dir != b_dir {
+                    return b_dir.cmp(&a_dir);
+                }
+            }
+            return if self.natural {
+                natural_str_cmp(&a_seg, &b_seg)
+            } else {
+                a_seg.cmp(&b_seg)
+            };
+        }
+        a_segs.len().cmp(&b_segs.len())
+    }
+
+    fn prepare<'a>(&self, i: usize, seg: &'a str) -> Cow<'a, str> {
+        // A drive can only be the first segment.
+        let seg = if i == 0 {
+            self.drive_case.apply_to_segment(seg)
+        } else {
+            Cow::Borrowed(seg)
+        };
+        // FileUrl validation already checked every segment decodes.
+        let decoded = match seg {
+            Cow::Borrowed(seg) => decode(seg).unwrap(),
+            Cow::Owned(drive) => Cow::Owned(drive),
+        };
+        if self.case_insensitive {
+            Cow::from(decoded.to_lowercase())
+        } else {
+            decoded
+        }
+    }
+}
+
+/// Natural ordering of URLs by decoded segment, so `file9` sorts
+/// before `file10`.
+pub fn natural_cmp(a: &FileUrl, b: &FileUrl) -> Ordering {
+    UrlOrdering::default().compare(a, b)
+}
+
+/// Natural ordering of URLs that lists subdirectories before files
+/// at each level.
+pub fn directories_first_cmp(a: &FileUrl, b: &FileUrl) -> Ordering {
+    UrlOrdering {
+        directories_first: true,
+        ..UrlOrdering::default()
+    }
+    .compare(a, b)
+}
+
+/// Compares strings treating each run of ASCII digits as a number.
+/// Equal numbers with different zero padding fall back to a plain
+/// comparison of the digits, so `007` and `7` aren't equal.
+fn natural_str_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (a_digits, b_digits) = (leading_digits(a), leading_digits(b));
+        if !a_digits.is_empty() && !b_digits.is_empty() {
+            let a_num = a_digits.trim_start_matches('0');
+            let b_num = b_digits.trim_start_matches('0');
+            let ord = a_num
+                .len()
+                .cmp(&b_num.len())
+                .then_with(|| a_num.cmp(b_num))
+                .then_with(|| a_digits.cmp(b_digits));
+            if ord != Ordering::Equal {
+                return ord;
+            }
+            a = &a[a_digits.len()..];
+            b = &b[b_digits.len()..];
+        } else {
+            let mut a_chars = a.chars();
+            let mut b_chars = b.chars();
+            match (a_chars.next(), b_chars.next()) {
+                (None, None) => return Ordering::Equal,
+                (x, y) if x != y => return x.cmp(&y),
+                _ => {
+                    a = a_chars.as_str();
+                    b = b_chars.as_str();
+                }
+            }
+        }
+    }
+}
+
+fn leading_digits(s: &str) -> &str {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    &s[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(urls: &[&str], order: UrlOrdering) -> Vec<String> {
+        let mut urls: Vec<&FileUrl> = urls.iter().map(|u| FileUrl::new(u).unwrap()).collect();
+        urls.sort_by(|a, b| order.compare(a, b));
+        urls.iter().map(|u| u.as_str().to_string()).collect()
+    }
+
+    #[test]
+    fn natural_numbers() {
+        assert_eq!(natural_str_cmp("a2", "a10"), Ordering::Less);
+        assert_eq!(natural_str_cmp("a007", "a7"), Ordering::Less);
+        assert_eq!(natural_str_cmp("v1.10", "v1.9"), Ordering::Greater);
+        assert_eq!(natural_str_cmp("x", "x"), Ordering::Equal);
+        assert_eq!(natural_str_cmp("x", "x1"), Ordering::Less);
+    }
+
+    #[test]
+    fn decodes_before_comparing() {
+        let list = ["file:///b", "file:///a%20z", "file:///a y"];
+        assert_eq!(
+            sorted(&list, UrlOrdering::default()),
+            vec!["file:///a y", "file:///a%20z", "file:///b"]
+        );
+    }
+
+    #[test]
+    fn directories_first() {
+        let list = [
+            "file:///r/z.txt",
+            "file:///r/a/",
+            "file:///r/",
+            "file:///r/b/c.txt",
+        ];
+        let order = UrlOrdering {
+            directories_first: true,
+            ..UrlOrdering::default()
+        };
+        assert_eq!(
+            sorted(&list, order),
+            vec![
+                "file:///r/",
+                "file:///r/a/",
+                "file:///r/b/c.txt",
+                "file:///r/z.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn case_folding() {
+        let list = ["file:///b", "file:///A", "file:///C"];
+        let order = UrlOrdering {
+            case_insensitive: true,
+            ..UrlOrdering::default()
+        };
+        assert_eq!(
+            sorted(&list, order),
+            vec!["file:///A", "file:///b", "file:///C"]
+        );
+        assert_eq!(
+            sorted(&list, UrlOrdering::default()),
+            vec!["file:///A", "file:///C", "file:///b"]
+        );
+    }
+    #[test]
+    fn drive_letters_compare_by_case_policy() {
+        let lower = FileUrl::new("file:///c:/x").unwrap();
+        let upper = FileUrl::new("file:///C:/x").unwrap();
+        let order = UrlOrdering {
+            drive_case: DriveLetterCase::Upper,
+            ..UrlOrdering::default()
+        };
+        assert_eq!(order.compare(lower, upper), Ordering::Equal);
+        assert_ne!(UrlOrdering::default().compare(lower, upper), Ordering::Equal);
+        // Only the first segment is a drive.
+        let deep = FileUrl::new("file:///x/c:").unwrap();
+        let deep_upper = FileUrl::new("file:///x/C:").unwrap();
+        assert_ne!(order.compare(deep, deep_upper), Ordering::Equal);
+    }
+}
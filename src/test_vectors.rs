@@ -0,0 +1,170 @@
+//! Conformance data: paths, the platform whose syntax they're in,
+//! and the file URL both this crate and other implementations give
+//! them. Downstream wrappers can run the same data against their own
+//! conversions.
+//!
+//! Only agreements are listed. Where other implementations differ,
+//! say gio leaving `&` unescaped or Java's `File.toURI` writing
+//! `file:/tmp`, there is no single expected URL to test against.
+//!
+//! # Example:
+//! ```
+//! use file_url::test_vectors::VECTORS;
+//!
+//! for vector in VECTORS {
+//!     assert_eq!(vector.platform.path_to_file_url(vector.path), vector.url);
+//! }
+//! ```
+use crate::Platform;
+
+/// An implementation whose output a vector was checked against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Source {
+    /// Python's `pathlib.PurePath.as_uri`.
+    Python,
+    /// Java's `java.nio.file.Path.toUri`.
+    Java,
+    /// .NET's `System.Uri.AbsoluteUri`, given a path.
+    DotNet,
+    /// The file URL a browser shows for a local file, per the WHATWG
+    /// URL Standard's serialization.
+    Browser,
+    /// GLib's `g_filename_to_uri`, as used by `g_file_get_uri`.
+    Gio,
+}
+
+/// One path and the file URL expected for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TestVector {
+    /// The path, in `platform`'s syntax.
+    pub path: &'static str,
+    /// How to read `path`.
+    pub platform: Platform,
+    /// The URL every source in `sources` gives `path`.
+    pub url: &'static str,
+    /// The implementations that agree on `url`.
+    pub sources: &'static [Source],
+}
+
+const ALL: &[Source] = &[
+    Source::Python,
+    Source::Java,
+    Source::DotNet,
+    Source::Browser,
+    Source::Gio,
+];
+const POSIX: &[Source] = &[Source::Python, Source::Java, Source::Browser, Source::Gio];
+const WINDOWS: &[Source] = &[
+    Source::Python,
+    Source::Java,
+    Source::DotNet,
+    Source::Browser,
+];
+
+/// The vectors, POSIX paths first.
+pub const VECTORS: &[TestVector] = &[
+    TestVector {
+        path: "/",
+        platform: Platform::Posix,
+        url: "file:///",
+        sources: POSIX,
+    },
+    TestVector {
+        path: "/tmp/a.txt",
+        platform: Platform::Posix,
+        url: "file:///tmp/a.txt",
+        sources: ALL,
+    },
+    TestVector {
+        path: "/tmp/a b.txt",
+        platform: Platform::Posix,
+        url: "file:///tmp/a%20b.txt",
+        sources: ALL,
+    },
+    TestVector {
+        path: "/tmp/%41",
+        platform: Platform::Posix,
+        url: "file:///tmp/%2541",
+        sources: &[Source::Python, Source::Java, Source::Gio],
+    },
+    TestVector {
+        path: "/tmp/caf\u{e9}",
+        platform: Platform::Posix,
+        url: "file:///tmp/caf%C3%A9",
+        sources: POSIX,
+    },
+    TestVector {
+        path: "/tmp/\u{65e5}\u{672c}",
+        platform: Platform::Posix,
+        url: "file:///tmp/%E6%97%A5%E6%9C%AC",
+        sources: POSIX,
+    },
+    TestVector {
+        path: "/tmp/a#b",
+        platform: Platform::Posix,
+        url: "file:///tmp/a%23b",
+        sources: &[Source::Python, Source::Java, Source::Gio],
+    },
+    TestVector {
+        path: "/tmp/a?b",
+        platform: Platform::Posix,
+        url: "file:///tmp/a%3Fb",
+        sources: &[Source::Python, Source::Java, Source::Gio],
+    },
+    TestVector {
+        path: "/tmp/~user/x-y_z.v1",
+        platform: Platform::Posix,
+        url: "file:///tmp/~user/x-y_z.v1",
+        sources: POSIX,
+    },
+    TestVector {
+        path: r"C:\Users\a.txt",
+        platform: Platform::Windows,
+        url: "file:///C:/Users/a.txt",
+        sources: WINDOWS,
+    },
+    TestVector {
+        path: r"C:\My Files\a b.txt",
+        platform: Platform::Windows,
+        url: "file:///C:/My%20Files/a%20b.txt",
+        sources: WINDOWS,
+    },
+    TestVector {
+        path: "C:\\caf\u{e9}",
+        platform: Platform::Windows,
+        url: "file:///C:/caf%C3%A9",
+        sources: WINDOWS,
+    },
+    TestVector {
+        path: r"\\server\share\a.txt",
+        platform: Platform::Windows,
+        url: "file://server/share/a.txt",
+        sources: WINDOWS,
+    },
+    TestVector {
+        path: r"\\server\share\a b",
+        platform: Platform::Windows,
+        url: "file://server/share/a%20b",
+        sources: WINDOWS,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vectors_hold_both_ways() {
+        for vector in VECTORS {
+            let platform = vector.platform;
+            assert_eq!(platform.path_to_file_url(vector.path), vector.url);
+            assert_eq!(
+                platform.file_url_to_path(vector.url).unwrap(),
+                vector.path,
+                "{}",
+                vector.url
+            );
+            assert!(!vector.sources.is_empty());
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Async filesystem helpers keyed by file URL, behind the `tokio`
+//! feature. URLs that don't decode, or that aren't file URLs for this
+//! machine, come back as `io::ErrorKind::InvalidInput` errors.
+use std::io;
+
+use tokio::fs::{self, File, ReadDir};
+
+use crate::file_url_to_io_path;
+
+/// Opens the file a URL points to in read-only mode.
+///
+/// # Example:
+/// ```no_run
+/// # async fn run() -> std::io::Result<()> {
+/// let file = file_url::open_file_url("file:///etc/hosts").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn open_file_url(file_url: &str) -> io::Result<File> {
+    File::open(file_url_to_io_path(file_url)?).await
+}
+
+/// Reads the entire contents of the file a URL points to.
+pub async fn read_file_url(file_url: &str) -> io::Result<Vec<u8>> {
+    fs::read(file_url_to_io_path(file_url)?).await
+}
+
+/// Lists the directory a URL points to.
+pub async fn read_dir_file_url(file_url: &str) -> io::Result<ReadDir> {
+    fs::read_dir(file_url_to_io_path(file_url)?).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PathFileUrlExt;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn reads_through_url() {
+        let dir = std::env::temp_dir().join(format!("file_url tokio {}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a b.txt"), b"hello").unwrap();
+
+        let file_url = dir.join("a b.txt").to_file_url().unwrap();
+        let mut contents = String::new();
+        open_file_url(&file_url)
+            .await
+            .unwrap()
+            .read_to_string(&mut contents)
+            .await
+            .unwrap();
+        assert_eq!(contents, "hello");
+        assert_eq!(read_file_url(&file_url).await.unwrap(), b"hello");
+
+        let mut entries = read_dir_file_url(&dir.to_file_url().unwrap())
+            .await
+            .unwrap();
+        let entry = entries.next_entry().await.unwrap().unwrap();
+        assert_eq!(entry.file_name(), "a b.txt");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn bad_url_is_invalid_input() {
+        let err = read_file_url("file:///%FF").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn non_local_urls_are_rejected() {
+        let mut rejected = vec!["http://evil.com/etc/passwd", "/etc/passwd"];
+        if !cfg!(windows) {
+            rejected.push("file://server/share/x");
+        }
+        for url in rejected.iter() {
+            let kinds = [
+                open_file_url(url).await.unwrap_err().kind(),
+                read_file_url(url).await.unwrap_err().kind(),
+                read_dir_file_url(url).await.unwrap_err().kind(),
+            ];
+            assert_eq!(kinds, [io::ErrorKind::InvalidInput; 3], "{}", url);
+        }
+    }
+}
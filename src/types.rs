@@ -0,0 +1,851 @@
+//! Validated file URL types, `FileUrl` and `FileUrlBuf`, which
+//! stand in the same relation as `Path` and `PathBuf`.
+use std::borrow::{Borrow, Cow, ToOwned};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::classify::scheme;
+use crate::percent::decode;
+use crate::relative::{segments_eq, segments_eq_ignore_case, UrlParts};
+use crate::{
+    decode_file_url_in_place, encode_file_component, file_url_to_path, file_url_to_pathbuf,
+    FileUrlParseError, PathFileUrlExt, Platform, UTFDecodeError,
+};
+
+/// A borrowed, validated absolute file URL. This is an unsized
+/// type, always used behind a reference like `&FileUrl`.
+///
+/// # Example:
+/// ```
+/// use std::path::PathBuf;
+/// use file_url::FileUrl;
+///
+/// let url = FileUrl::new("file:///foo/bar%20baz.txt").unwrap();
+/// assert_eq!(url.to_path_buf(), PathBuf::from("/foo/bar baz.txt"));
+///
+/// assert!(FileUrl::new("http://example.com").is_err());
+/// ```
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct FileUrl {
+    inner: str,
+}
+
+impl FileUrl {
+    /// Validates `s` as an absolute file URL whose segments decode
+    /// to UTF-8.
+    pub fn new<S: AsRef<str> + ?Sized>(s: &S) -> Result<&FileUrl, FileUrlParseError> {
+        let s = s.as_ref();
+        validate(s)?;
+        Ok(FileUrl::from_str_unchecked(s))
+    }
+
+    /// Validates a `'static` string as a file URL, panicking if it
+    /// isn't one. This is a `const fn`, so in a const context an
+    /// invalid URL is a compile error; the `file_url!` macro is the
+    /// usual way to call it.
+    pub const fn from_static(s: &'static str) -> &'static FileUrl {
+        match validate_str(s) {
+            Ok(()) => FileUrl::from_str_unchecked(s),
+            Err(msg) => panic!("{}", msg),
+        }
+    }
+
+    pub(crate) const fn from_str_unchecked(s: &str) -> &FileUrl {
+        // FileUrl is a repr(transparent) wrapper around str.
+        unsafe { &*(s as *const str as *const FileUrl) }
+    }
+
+    /// The URL as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.inner
+    }
+
+    /// Decodes the URL to a PathBuf. See `file_url_to_pathbuf` for
+    /// the platform caveats.
+    pub fn to_path_buf(&self) -> PathBuf {
+        // Validation already checked every segment decodes.
+        file_url_to_pathbuf(&self.inner).unwrap()
+    }
+
+    /// Decodes the URL to a path, borrowed from the URL when there's
+    /// nothing to decode. See `file_url_to_path`.
+    pub fn to_path(&self) -> Cow<'_, Path> {
+        // Validation already checked every segment decodes.
+        file_url_to_path(&self.inner).unwrap()
+    }
+
+    /// Copies the URL into an owned `FileUrlBuf`.
+    pub fn to_file_url_buf(&self) -> FileUrlBuf {
+        FileUrlBuf {
+            inner: self.inner.to_string(),
+        }
+    }
+
+    /// Whether this URL is `base` or lies somewhere beneath it. Dot
+    /// segments are resolved first and segments are compared whole
+    /// and decoded, so `file:///foobar` is *not* within `file:///foo`
+    /// and `file:///a/../etc` is not within `file:///a`. The base is
+    /// treated as a directory with or without a trailing slash. A URL
+    /// with a segment that only decodes to `.` or `..`, like `%2e%2e`,
+    /// or to more than one segment, like `a%2Fb`, is never within
+    /// anything, since the path it decodes to could climb out.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::FileUrl;
+    ///
+    /// let base = FileUrl::new("file:///srv/data").unwrap();
+    /// assert!(FileUrl::new("file:///srv/data/a%20b.txt").unwrap().is_within(base));
+    /// assert!(!FileUrl::new("file:///srv/database").unwrap().is_within(base));
+    /// assert!(!FileUrl::new("file:///srv/data/../x").unwrap().is_within(base));
+    /// assert!(!FileUrl::new("file:///srv/data/%2e%2e/x").unwrap().is_within(base));
+    /// ```
+    pub fn is_within(&self, base: &FileUrl) -> bool {
+        self.is_within_by(base, segments_eq, false)
+    }
+
+    /// Like `is_within`, but comparing segments the way `platform`'s
+    /// filesystems do. On Windows that ignores case, drive letters
+    /// included, as NTFS does, and an encoded `\` counts as a
+    /// separator too.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::{FileUrl, Platform};
+    ///
+    /// let base = FileUrl::new("file:///C:/Users/Me").unwrap();
+    /// let url = FileUrl::new("file:///c:/users/me/Desktop").unwrap();
+    /// assert!(url.is_within_on(base, Platform::Windows));
+    /// assert!(!url.is_within_on(base, Platform::Posix));
+    /// ```
+    pub fn is_within_on(&self, base: &FileUrl, platform: Platform) -> bool {
+        match platform {
+            Platform::Posix => self.is_within(base),
+            Platform::Windows => self.is_within_by(base, segments_eq_ignore_case, true),
+        }
+    }
+
+    /// Whether this URL and `other` name the same path on `platform`:
+    /// the same host and the same decoded segments once dot segments
+    /// are resolved, ignoring case on Windows. A trailing slash still
+    /// counts.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::{FileUrl, Platform};
+    ///
+    /// let a = FileUrl::new("file:///C:/Program%20Files/App").unwrap();
+    /// let b = FileUrl::new("file:///c:/program files/./app").unwrap();
+    /// assert!(a.eq_on(b, Platform::Windows));
+    /// assert!(!a.eq_on(b, Platform::Posix));
+    /// ```
+    pub fn eq_on(&self, other: &FileUrl, platform: Platform) -> bool {
+        let eq = match platform {
+            Platform::Posix => segments_eq,
+            Platform::Windows => segments_eq_ignore_case,
+        };
+        let parts = self.parts();
+        let other = other.parts();
+        parts.same_host(&other)
+            && parts.segments.len() == other.segments.len()
+            && parts
+                .segments
+                .iter()
+                .zip(other.segments.iter())
+                .all(|(a, b)| eq(a, b))
+    }
+
+    fn is_within_by(&self, base: &FileUrl, eq: fn(&str, &str) -> bool, windows: bool) -> bool {
+        let parts = self.parts();
+        let base = base.parts();
+        if !parts.same_host(&base) || parts.segments.iter().any(|s| hides_dots(s, windows)) {
+            return false;
+        }
+
+        let mut base_segs = &base.segments[..];
+        if let Some((&"", dir)) = base_segs.split_last() {
+            base_segs = dir;
+        }
+        // Drop any trailing slash on self too, so the base directory
+        // itself counts as within.
+        let mut segs = &parts.segments[..];
+        if let Some((&"", dir)) = segs.split_last() {
+            segs = dir;
+        }
+
+        segs.len() >= base_segs.len()
+            && base_segs
+                .iter()
+                .zip(segs.iter())
+                .all(|(a, b)| eq(a, b))
+    }
+
+    pub(crate) fn parts(&self) -> UrlParts<'_> {
+        // Validation already checked this parses.
+        UrlParts::parse(&self.inner).unwrap()
+    }
+}
+
+/// Whether the segment `segment` only decodes to a dot segment or a
+/// separator, which `UrlParts` can't see to resolve: `%2e%2e`, `.%2E`,
+/// `..%2F`, and on Windows `..%5C`. Windows also splits a path at a
+/// literal `\`, so there a segment like `..\x` hides one too.
+pub(crate) fn hides_dots(segment: &str, windows: bool) -> bool {
+    let backslashed = windows && segment.contains('\\');
+    if !backslashed && !segment.contains('%') {
+        return false;
+    }
+    let decoded = match decode(segment) {
+        Ok(decoded) => decoded,
+        Err(_) => return true,
+    };
+    let is_dots = |piece: &str| piece == "." || piece == "..";
+    is_dots(&decoded)
+        || decoded.contains('/')
+        || (windows
+            && (segment.to_ascii_lowercase().contains("%5c")
+                || decoded.split('\\').any(is_dots)))
+}
+
+/// Whether `s` has the `file:` scheme, in any case, and no control
+/// characters. A cheap check for branching on what kind of input
+/// this is; it allocates nothing and looks at each byte once. It
+/// accepts relative forms like `file:a/b` too, so use
+/// `is_absolute_file_url` to know that `FileUrl::new` will succeed.
+///
+/// # Example:
+/// ```
+/// use file_url::{is_absolute_file_url, is_file_url};
+///
+/// assert!(is_file_url("FILE:///tmp/x"));
+/// assert!(is_file_url("file:notes.txt"));
+/// assert!(!is_file_url("https://example.com/"));
+/// assert!(!is_file_url("/tmp/x"));
+///
+/// assert!(is_absolute_file_url("file:///tmp/x"));
+/// assert!(!is_absolute_file_url("file:notes.txt"));
+/// assert!(!is_absolute_file_url("file:///%FF"));
+/// ```
+pub const fn is_file_url(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() >= 5 && ascii_eq_ignore_case(bytes, b"file:") && !has_controls(bytes)
+}
+
+/// Whether `FileUrl::new` would accept `s`, without allocating: an
+/// absolute file URL whose segments decode to UTF-8.
+pub const fn is_absolute_file_url(s: &str) -> bool {
+    validate_str(s).is_ok()
+}
+
+fn validate(s: &str) -> Result<(), FileUrlParseError> {
+    validate_str(s).map_err(|msg| match scheme(s) {
+        Some(scheme) if !scheme.eq_ignore_ascii_case("file") => other_scheme(scheme),
+        _ => FileUrlParseError::new(msg),
+    })
+}
+
+/// The error for a URL with a scheme other than `file:`, naming the
+/// scheme and, for the common ones, what the caller probably wants
+/// instead.
+pub(crate) fn other_scheme(scheme: &str) -> FileUrlParseError {
+    let hint = match &*scheme.to_ascii_lowercase() {
+        "http" | "https" | "ftp" | "ftps" | "sftp" => "; download it first",
+        "data" => "; its contents are in the URL itself",
+        "content" => "; it's an Android content URI, open it with a ContentResolver",
+        "jar" => "; ArchiveUrl reads the archive's path out of it",
+        _ => "",
+    };
+    let msg = format!("Not an absolute file URL (scheme {}:){}!", scheme, hint);
+    FileUrlParseError::new(&msg)
+}
+
+/// The checks behind `FileUrl::new`, written as a `const fn` so that
+/// `file_url!` can run them at compile time.
+const fn validate_str(s: &str) -> Result<(), &'static str> {
+    let bytes = s.as_bytes();
+    if bytes.len() < 5 || !ascii_eq_ignore_case(bytes, b"file:") {
+        return Err("Not an absolute file URL!");
+    }
+
+    // Either an authority follows, or the path must start at the root.
+    let has_authority = bytes.len() > 6 && bytes[5] == b'/' && bytes[6] == b'/';
+    if !has_authority && (bytes.len() == 5 || bytes[5] != b'/') {
+        return Err("Not an absolute file URL!");
+    }
+
+    if has_controls(bytes) {
+        return Err("File URL contains control characters!");
+    }
+
+    // `to_path_buf` decodes the host along with the path, so it has
+    // to decode cleanly too.
+    if !decodes_to_utf8(bytes, 5) {
+        return Err("File URL does not decode to UTF-8!");
+    }
+    Ok(())
+}
+
+/// Whether `bytes` has a C0 or C1 control character or DEL.
+const fn has_controls(bytes: &[u8]) -> bool {
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        // C1 controls are U+0080 through U+009F, encoded as C2 80..9F.
+        if b < 0x20 || b == 0x7f || (b == 0xc2 && i + 1 < bytes.len() && bytes[i + 1] < 0xa0) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+const fn ascii_eq_ignore_case(bytes: &[u8], lower: &[u8]) -> bool {
+    let mut i = 0;
+    while i < lower.len() {
+        if bytes[i].to_ascii_lowercase() != lower[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Whether percent-decoding `bytes[start..]` yields valid UTF-8.
+/// Malformed escapes are kept literally, as `percent::decode` does.
+const fn decodes_to_utf8(bytes: &[u8], start: usize) -> bool {
+    // Continuation bytes still expected, and the allowed range of
+    // the next one (narrower after some lead bytes, to rule out
+    // overlong forms, surrogates and code points past U+10FFFF).
+    let mut needed = 0;
+    let mut lower = 0x80;
+    let mut upper = 0xbf;
+
+    let mut i = start;
+    while i < bytes.len() {
+        let mut b = bytes[i];
+        i += 1;
+        if b == b'%' && i + 1 < bytes.len() {
+            if let (Some(hi), Some(lo)) = (hex_value(bytes[i]), hex_value(bytes[i + 1])) {
+                b = hi << 4 | lo;
+                i += 2;
+            }
+        }
+
+        if needed > 0 {
+            if b < lower || b > upper {
+                return false;
+            }
+            needed -= 1;
+            lower = 0x80;
+            upper = 0xbf;
+            continue;
+        }
+
+        match b {
+            0x00..=0x7f => {}
+            0xc2..=0xdf => needed = 1,
+            0xe0 => {
+                needed = 2;
+                lower = 0xa0;
+            }
+            0xe1..=0xec | 0xee..=0xef => needed = 2,
+            0xed => {
+                needed = 2;
+                upper = 0x9f;
+            }
+            0xf0 => {
+                needed = 3;
+                lower = 0x90;
+            }
+            0xf1..=0xf3 => needed = 3,
+            0xf4 => {
+                needed = 3;
+                upper = 0x8f;
+            }
+            _ => return false,
+        }
+    }
+    needed == 0
+}
+
+/// Creates a `&'static FileUrl` from a string literal, validated at
+/// compile time.
+///
+/// # Example:
+/// ```
+/// use file_url::{file_url, FileUrl};
+///
+/// const HOSTS: &FileUrl = file_url!("file:///etc/hosts");
+/// assert_eq!(HOSTS.as_str(), "file:///etc/hosts");
+/// ```
+///
+/// An invalid literal fails to build:
+/// ```compile_fail
+/// let url = file_url::file_url!("http://example.com/");
+/// ```
+#[macro_export]
+macro_rules! file_url {
+    ($url:literal) => {{
+        const URL: &$crate::FileUrl = $crate::FileUrl::from_static($url);
+        URL
+    }};
+}
+
+/// Error for paths `FileUrlBuf::from_path` can't convert.
+#[derive(Debug)]
+pub enum FromPathError {
+    /// The path doesn't start from a root.
+    NotAbsolute,
+    /// The path isn't valid UTF-8.
+    Decode(UTFDecodeError),
+}
+
+impl fmt::Display for FromPathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FromPathError::NotAbsolute => write!(f, "Path is not absolute!"),
+            FromPathError::Decode(_) => write!(f, "Path does not decode to UTF-8!"),
+        }
+    }
+}
+
+impl Error for FromPathError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FromPathError::Decode(e) => Some(e),
+            FromPathError::NotAbsolute => None,
+        }
+    }
+}
+
+/// An owned, validated absolute file URL.
+///
+/// # Example:
+/// ```
+/// use std::path::Path;
+/// use file_url::FileUrlBuf;
+///
+/// let url = FileUrlBuf::from_path(Path::new("/foo/bar baz.txt")).unwrap();
+/// assert_eq!(url.as_str(), "file:///foo/bar%20baz.txt");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileUrlBuf {
+    inner: String,
+}
+
+impl FileUrlBuf {
+    /// Parses and validates an owned string as a file URL.
+    pub fn new(s: String) -> Result<FileUrlBuf, FileUrlParseError> {
+        validate(&s)?;
+        Ok(FileUrlBuf { inner: s })
+    }
+
+    /// Converts a UTF-8 path to a file URL. The path must start from a
+    /// root; a relative one like `rel/x` would come out as
+    /// `file://rel/x`, naming a host, so it's an error instead.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<FileUrlBuf, FromPathError> {
+        let path = path.as_ref();
+        if !path.has_root() {
+            return Err(FromPathError::NotAbsolute);
+        }
+        Ok(FileUrlBuf {
+            inner: path.to_file_url().map_err(FromPathError::Decode)?,
+        })
+    }
+
+    pub(crate) fn from_string_unchecked(s: String) -> FileUrlBuf {
+        FileUrlBuf { inner: s }
+    }
+
+    /// Borrows the URL as a `&FileUrl`.
+    pub fn as_file_url(&self) -> &FileUrl {
+        FileUrl::from_str_unchecked(&self.inner)
+    }
+
+    /// Consumes the FileUrlBuf, yielding the underlying String.
+    pub fn into_string(self) -> String {
+        self.inner
+    }
+
+    /// Consumes the FileUrlBuf, decoding it to a PathBuf in the same
+    /// buffer. See `decode_file_url_in_place`.
+    pub fn into_path_buf(self) -> PathBuf {
+        // Validation already checked every segment decodes.
+        decode_file_url_in_place(self.inner).unwrap()
+    }
+
+    /// Appends a percent-encoded segment, adding a `/` first if
+    /// needed. Unlike `PathBuf::push` a leading separator doesn't
+    /// replace the URL, it's simply trimmed, and a segment that is
+    /// only separators is a no-op. Non-UTF-8 segments are converted
+    /// lossily; use `try_push` to refuse them instead.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::FileUrlBuf;
+    ///
+    /// let mut url: FileUrlBuf = "file:///data".parse().unwrap();
+    /// url.push("2021 q1");
+    /// url.push("report#1.csv");
+    /// assert_eq!(url.as_str(), "file:///data/2021%20q1/report%231.csv");
+    /// ```
+    pub fn push<S: AsRef<OsStr>>(&mut self, segment: S) {
+        self.push_str(&segment.as_ref().to_string_lossy());
+    }
+
+    /// Like `push`, but fails on a segment that isn't UTF-8, leaving
+    /// the URL as it was, rather than replacing the bad bytes with
+    /// U+FFFD. A URL with a replacement in it no longer names the
+    /// original file.
+    ///
+    /// # Example:
+    /// ```
+    /// use file_url::FileUrlBuf;
+    ///
+    /// let mut url: FileUrlBuf = "file:///data".parse().unwrap();
+    /// url.try_push("a b").unwrap();
+    /// assert_eq!(url.as_str(), "file:///data/a%20b");
+    /// ```
+    pub fn try_push<S: AsRef<OsStr>>(&mut self, segment: S) -> Result<(), UTFDecodeError> {
+        match segment.as_ref().to_str() {
+            Some(segment) => {
+                self.push_str(segment);
+                Ok(())
+            }
+            None => {
+                let lossy = segment.as_ref().to_string_lossy();
+                let msg = format!("Path segment {} is not UTF-8 compatible!", lossy);
+                Err(UTFDecodeError::new(&msg))
+            }
+        }
+    }
+
+    fn push_str(&mut self, segment: &str) {
+        let segment = segment.trim_matches(|c| c == '/' || c == '\\');
+        if segment.is_empty() {
+            return;
+        }
+        if !self.inner.ends_with('/') {
+            self.inner.push('/');
+        }
+        self.inner.push_str(&encode_file_component(segment));
+    }
+}
+
+/// Builds a URL from the root, pushing each item as a segment.
+///
+/// # Example:
+/// ```
+/// use file_url::FileUrlBuf;
+///
+/// let url: FileUrlBuf = vec!["tmp", "a b.txt"].into_iter().collect();
+/// assert_eq!(url.as_str(), "file:///tmp/a%20b.txt");
+/// ```
+impl<S: AsRef<OsStr>> FromIterator<S> for FileUrlBuf {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> FileUrlBuf {
+        let mut url = FileUrlBuf::from_string_unchecked(String::from("file:///"));
+        url.extend(iter);
+        url
+    }
+}
+
+impl<S: AsRef<OsStr>> Extend<S> for FileUrlBuf {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        for segment in iter {
+            self.push(segment);
+        }
+    }
+}
+
+impl Deref for FileUrlBuf {
+    type Target = FileUrl;
+
+    fn deref(&self) -> &FileUrl {
+        self.as_file_url()
+    }
+}
+
+impl Borrow<FileUrl> for FileUrlBuf {
+    fn borrow(&self) -> &FileUrl {
+        self.as_file_url()
+    }
+}
+
+impl ToOwned for FileUrl {
+    type Owned = FileUrlBuf;
+
+    fn to_owned(&self) -> FileUrlBuf {
+        self.to_file_url_buf()
+    }
+}
+
+impl AsRef<FileUrl> for FileUrl {
+    fn as_ref(&self) -> &FileUrl {
+        self
+    }
+}
+
+impl AsRef<FileUrl> for FileUrlBuf {
+    fn as_ref(&self) -> &FileUrl {
+        self.as_file_url()
+    }
+}
+
+impl AsRef<str> for FileUrl {
+    fn as_ref(&self) -> &str {
+        &self.inner
+    }
+}
+
+impl AsRef<str> for FileUrlBuf {
+    fn as_ref(&self) -> &str {
+        &self.inner
+    }
+}
+
+impl fmt::Display for FileUrl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.inner)
+    }
+}
+
+impl fmt::Display for FileUrlBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.inner)
+    }
+}
+
+impl FromStr for FileUrlBuf {
+    type Err = FileUrlParseError;
+
+    fn from_str(s: &str) -> Result<FileUrlBuf, FileUrlParseError> {
+        FileUrlBuf::new(s.to_string())
+    }
+}
+
+impl TryFrom<String> for FileUrlBuf {
+    type Error = FileUrlParseError;
+
+    fn try_from(s: String) -> Result<FileUrlBuf, FileUrlParseError> {
+        FileUrlBuf::new(s)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for &'a FileUrl {
+    type Error = FileUrlParseError;
+
+    fn try_from(s: &'a str) -> Result<&'a FileUrl, FileUrlParseError> {
+        FileUrl::new(s)
+    }
+}
+
+impl From<FileUrlBuf> for String {
+    fn from(url: FileUrlBuf) -> String {
+        url.inner
+    }
+}
+
+impl From<&FileUrl> for FileUrlBuf {
+    fn from(url: &FileUrl) -> FileUrlBuf {
+        url.to_file_url_buf()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_input() {
+        assert!(FileUrl::new("file:///a/b.txt").is_ok());
+        assert!(FileUrl::new("FILE://host/share/x").is_ok());
+        assert!(FileUrl::new("file:foo").is_err());
+        assert!(FileUrl::new("https:///a").is_err());
+        assert!(FileUrl::new("file:///a\nb").is_err());
+        assert!(FileUrl::new("file:///%FF").is_err());
+    }
+
+    #[test]
+    fn errors_name_other_schemes() {
+        let cases = [
+            ("https://example.com/a.txt", "(scheme https:); download it first!"),
+            ("data:text/plain,hi", "(scheme data:); its contents are in the URL itself!"),
+            ("content://media/external/1", "(scheme content:); it's an Android"),
+            ("mailto:a@example.com", "(scheme mailto:)!"),
+        ];
+        for (url, msg) in cases.iter() {
+            let err = FileUrl::new(url).unwrap_err().to_string();
+            assert!(err.starts_with("Not an absolute file URL "), "{}", err);
+            assert!(err.contains(msg), "{}", err);
+        }
+        let err = FileUrl::new("file:foo").unwrap_err();
+        assert_eq!(err.to_string(), "Not an absolute file URL!");
+        let err = FileUrl::new(r"C:\x").unwrap_err();
+        assert_eq!(err.to_string(), "Not an absolute file URL!");
+    }
+
+    #[test]
+    fn predicates_agree_with_validation() {
+        let inputs = [
+            "file:///a/b.txt",
+            "FILE://host/share/x",
+            "file:foo",
+            "file:",
+            "https:///a",
+            "file:///a\nb",
+            "file:///%FF",
+            "fil",
+        ];
+        for input in inputs.iter() {
+            assert_eq!(is_absolute_file_url(input), FileUrl::new(input).is_ok());
+            assert!(is_file_url(input) || !is_absolute_file_url(input));
+        }
+        assert!(is_file_url("file:foo"));
+        assert!(!is_file_url("file:///a\tb"));
+    }
+
+    #[test]
+    fn const_validation_matches_decoding() {
+        let cases = [
+            "file://",
+            "file://host",
+            "file:/",
+            "file:",
+            "file:///%zz%2",
+            "file:///%C3%A9",
+            "file:///%C3",
+            "file:///%E0%80%80",
+            "file:///%ED%A0%80",
+            "file:///%F4%90%80%80",
+            "file:///%F0%9F%98%80/x",
+            "file://%FF/x",
+            "file:///\u{85}",
+        ];
+        for case in cases.iter() {
+            let expected = UrlParts::parse(case).is_some_and(|p| {
+                decode(p.host).is_ok() && p.segments.iter().all(|seg| decode(seg).is_ok())
+            }) && !case.chars().any(char::is_control);
+            assert_eq!(validate_str(case).is_ok(), expected, "{}", case);
+        }
+    }
+
+    #[test]
+    fn macro_yields_static_url() {
+        let url: &'static FileUrl = file_url!("file:///tmp/a%20b");
+        assert_eq!(url.to_path_buf(), PathBuf::from("/tmp/a b"));
+    }
+
+    #[test]
+    fn owned_and_borrowed_agree() {
+        let owned: FileUrlBuf = "file:///a/b%20c".parse().unwrap();
+        let borrowed: &FileUrl = &owned;
+        assert_eq!(borrowed.to_owned(), owned);
+        assert_eq!(owned.to_path_buf(), PathBuf::from("/a/b c"));
+        assert_eq!(owned.to_string(), "file:///a/b%20c");
+    }
+
+    #[test]
+    fn collect_from_path_components() {
+        let url: FileUrlBuf = Path::new("/usr/local/my lib").iter().collect();
+        assert_eq!(url.as_str(), "file:///usr/local/my%20lib");
+        assert!(FileUrl::new(url.as_str()).is_ok());
+
+        let mut url = url;
+        url.extend(&["50%", "/"]);
+        assert_eq!(url.as_str(), "file:///usr/local/my%20lib/50%25");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn try_push_refuses_lossy_segments() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let bad = OsStr::from_bytes(b"caf\xe9");
+        let mut url = FileUrlBuf::new("file:///d".to_string()).unwrap();
+        assert!(url.try_push(bad).is_err());
+        assert_eq!(url.as_str(), "file:///d");
+        url.push(bad);
+        assert_eq!(url.as_str(), "file:///d/caf%EF%BF%BD");
+    }
+
+    #[test]
+    fn from_path_needs_a_root() {
+        assert!(matches!(
+            FileUrlBuf::from_path("rel/x"),
+            Err(FromPathError::NotAbsolute)
+        ));
+        assert!(matches!(
+            FileUrlBuf::from_path(""),
+            Err(FromPathError::NotAbsolute)
+        ));
+        assert_eq!(FileUrlBuf::from_path("/x").unwrap().as_str(), "file:///x");
+    }
+
+    #[test]
+    fn within_is_component_wise() {
+        let base = FileUrl::new("file:///foo/").unwrap();
+        let url = |s| FileUrl::new(s).unwrap();
+        assert!(url("file:///foo").is_within(base));
+        assert!(url("file:///foo/bar/baz").is_within(base));
+        assert!(url("file:///%66oo/bar").is_within(base));
+        assert!(!url("file:///foobar").is_within(base));
+        assert!(!url("file:///").is_within(base));
+        assert!(!url("file://other/foo/bar").is_within(base));
+    }
+
+    #[test]
+    fn within_rejects_encoded_dot_segments() {
+        let base = FileUrl::new("file:///srv/data").unwrap();
+        let escapes = [
+            "file:///srv/data/%2e%2e/x",
+            "file:///srv/data/.%2E/x",
+            "file:///srv/data/..%2F..%2Fetc",
+            "file:///srv/data/%2E",
+        ];
+        for s in escapes.iter() {
+            let url = FileUrl::new(s).unwrap();
+            assert!(!url.is_within(base), "{}", s);
+            assert!(!url.is_within_on(base, Platform::Windows), "{}", s);
+        }
+        let backslash = FileUrl::new("file:///srv/data/..%5Cx").unwrap();
+        assert!(!backslash.is_within_on(base, Platform::Windows));
+        assert!(backslash.is_within(base));
+        let literal = FileUrl::new(r"file:///srv/data/..\..\x").unwrap();
+        assert!(!literal.is_within_on(base, Platform::Windows));
+        assert!(literal.is_within_on(base, Platform::Posix));
+        let named = FileUrl::new(r"file:///srv/data/a\b").unwrap();
+        assert!(named.is_within_on(base, Platform::Windows));
+        assert!(FileUrl::new("file:///srv/data/a%2Eb").unwrap().is_within(base));
+    }
+
+    #[test]
+    fn windows_comparisons_ignore_case() {
+        let url = |s| FileUrl::new(s).unwrap();
+        let base = url("file://Server/Share/Dir%C3%A9/");
+        let inside = url("file://server/SHARE/DIR%C3%89/x");
+        assert!(inside.is_within_on(base, Platform::Windows));
+        assert!(!inside.is_within_on(base, Platform::Posix));
+        assert!(!url("file://server/share/dire/x").is_within_on(base, Platform::Windows));
+
+        assert!(url("file:///C:/A/../b").eq_on(url("file:///c:/B"), Platform::Windows));
+        assert!(url("file:///a%20b").eq_on(url("file:///a b"), Platform::Posix));
+        assert!(!url("file:///a/").eq_on(url("file:///a"), Platform::Windows));
+        assert!(!url("file:///a/b").eq_on(url("file:///a"), Platform::Windows));
+    }
+}
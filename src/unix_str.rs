@@ -0,0 +1,159 @@
+//! String-to-string conversions with Unix path semantics. They only
+//! need `alloc`, so they're available without the `std` feature.
+use alloc::string::{FromUtf8Error, String};
+use alloc::vec::Vec;
+use core::str;
+
+use crate::percent::decode_within;
+use crate::{file_url_len, push_file_component, scan, UrlBuf};
+
+/// Converts a Unix path to a file URL, giving the same result as
+/// `PathFileUrlExt::to_file_url` does on Unix-like systems. Empty
+/// and `.` components are dropped, and everything but the RFC 3986
+/// unreserved characters is percent-encoded.
+///
+/// # Example:
+/// ```
+/// use file_url::encode_unix_path_str;
+///
+/// assert_eq!(encode_unix_path_str("/tmp//a b/./c"), "file:///tmp/a%20b/c");
+/// ```
+pub fn encode_unix_path_str(path: &str) -> String {
+    let mut url = String::new();
+    push_unix_file_url(&mut url, path);
+    url
+}
+
+/// Converts a file URL to a Unix path, giving the same result as
+/// `file_url_to_pathbuf` does on Unix-like systems. Fails if a
+/// segment doesn't decode to UTF-8.
+///
+/// # Example:
+/// ```
+/// use file_url::decode_to_unix_path_string;
+///
+/// let path = decode_to_unix_path_string("file:///tmp/a%20b").unwrap();
+/// assert_eq!(path, "/tmp/a b");
+/// assert!(decode_to_unix_path_string("file:///%FF").is_err());
+/// ```
+pub fn decode_to_unix_path_string(file_url: &str) -> Result<String, FromUtf8Error> {
+    let mut bytes = file_url.as_bytes().to_vec();
+    decode_in_place(&mut bytes)?;
+    String::from_utf8(bytes)
+}
+
+/// Writes the file URL for the Unix path `path` onto the end of
+/// `url`, splitting it the way `Path::components` does on Unix.
+pub(crate) fn push_unix_file_url<B: UrlBuf>(url: &mut B, path: &str) {
+    url.reserve_exact(file_url_len(path, |b| b == b'/'));
+    url.push_str("file://");
+    if is_normalized_absolute(path) {
+        // Nothing to drop, so every piece is a component.
+        for part in path[1..].split('/') {
+            url.push('/');
+            push_file_component(url, part);
+        }
+        return;
+    }
+    let (root, rest) = match path.strip_prefix('/') {
+        Some(rest) => (true, rest),
+        None => (false, path),
+    };
+    let start = url.len();
+    if root {
+        url.push('/');
+    }
+    for (i, part) in rest.split('/').enumerate() {
+        // A `.` only counts at the start of a relative path.
+        if part.is_empty() || (part == "." && (root || i > 0)) {
+            continue;
+        }
+        if url.len() > start && !url.ends_with('/') {
+            url.push('/');
+        }
+        push_file_component(url, part);
+    }
+}
+
+/// Whether `path` is absolute and already in the form
+/// `Path::components` yields on Unix: no empty or `.` components, and
+/// no trailing slash.
+fn is_normalized_absolute(path: &str) -> bool {
+    path.starts_with('/')
+        && (path.len() == 1 || !path.ends_with('/'))
+        && !path.ends_with("/.")
+        && !path.contains("//")
+        && !path.contains("/./")
+}
+
+/// Decodes the file URL in `bytes` where it lies, checking that each
+/// decoded segment is UTF-8.
+pub(crate) fn decode_in_place(bytes: &mut Vec<u8>) -> Result<(), FromUtf8Error> {
+    // Mirrors `PathBuf::push` for each segment. Every segment is
+    // written no further right than it was read from, since its
+    // separator is at most one byte and decoding never grows it.
+    let mut read = 0;
+    let mut write = 0;
+    loop {
+        let end = scan::find_slash(&bytes[read..]).map_or(bytes.len(), |i| read + i);
+        if read == 0 && &bytes[..end] == b"file:" {
+            // File url should always be abspath
+            bytes[0] = b'/';
+            write = 1;
+        } else {
+            let mut start = write;
+            if write > 0 && bytes[write - 1] != b'/' {
+                bytes[write] = b'/';
+                start += 1;
+            }
+            let len = decode_within(bytes, read..end, start);
+            let segment = &bytes[start..start + len];
+            if str::from_utf8(segment).is_err() {
+                // Only a String conversion produces a FromUtf8Error.
+                return Err(String::from_utf8(segment.to_vec()).unwrap_err());
+            }
+            if segment.first() == Some(&b'/') {
+                // An absolute segment replaces the path so far.
+                bytes.copy_within(start..start + len, 0);
+                write = len;
+            } else {
+                write = start + len;
+            }
+        }
+        if end == bytes.len() {
+            break;
+        }
+        read = end + 1;
+    }
+    bytes.truncate(write);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unix_strings_both_ways() {
+        let cases = [
+            ("/", "file:///"),
+            ("/a b/c.txt", "file:///a%20b/c.txt"),
+            ("/a//b/./c/", "file:///a/b/c"),
+            ("./rel/../x", "file://./rel/../x"),
+            ("/C:/x", "file:///C:/x"),
+            ("/back\\slash", "file:///back%5Cslash"),
+        ];
+        for (path, url) in cases.iter() {
+            assert_eq!(encode_unix_path_str(path), *url);
+        }
+        assert_eq!(
+            decode_to_unix_path_string("file:///a%20b/c").unwrap(),
+            "/a b/c"
+        );
+        assert_eq!(
+            decode_to_unix_path_string("file://host/a").unwrap(),
+            "/host/a"
+        );
+        assert!(decode_to_unix_path_string("file:///%C3").is_err());
+    }
+}
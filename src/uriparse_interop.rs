@@ -0,0 +1,210 @@
+//! Conversions to and from `uriparse::URI` and `URIReference`, behind
+//! the `uriparse` feature.
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::string::FromUtf8Error;
+
+use uriparse::{Scheme, URIError, URIReference, URI};
+
+use crate::{
+    file_url_to_pathbuf, FileUrl, FileUrlBuf, FileUrlParseError, PathFileUrlExt, UTFDecodeError,
+};
+
+/// Error converting between paths and `uriparse` URIs.
+#[derive(Debug)]
+pub enum UriparseError {
+    /// The path isn't valid UTF-8.
+    Encode(UTFDecodeError),
+    /// The URI's path doesn't decode to UTF-8.
+    Decode(FromUtf8Error),
+    /// The `uriparse` crate rejected the URI.
+    Invalid(URIError),
+    /// The URI doesn't use the `file:` scheme.
+    NotFileScheme(Option<String>),
+}
+
+impl fmt::Display for UriparseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UriparseError::Encode(_) => write!(f, "Path is not UTF-8 compatible!"),
+            UriparseError::Decode(_) => write!(f, "File URI does not decode to UTF-8!"),
+            UriparseError::Invalid(_) => write!(f, "Invalid URI!"),
+            UriparseError::NotFileScheme(Some(scheme)) => {
+                write!(f, "Expected a file URI, got {}:", scheme)
+            }
+            UriparseError::NotFileScheme(None) => {
+                write!(f, "Expected a file URI, got no scheme!")
+            }
+        }
+    }
+}
+
+impl Error for UriparseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UriparseError::Encode(e) => Some(e),
+            UriparseError::Decode(e) => Some(e),
+            UriparseError::Invalid(e) => Some(e),
+            UriparseError::NotFileScheme(_) => None,
+        }
+    }
+}
+
+/// The file URL named by a URI's scheme, host and path, without any
+/// query or fragment.
+fn file_url_of(
+    scheme: Option<&Scheme>,
+    host: Option<String>,
+    path: String,
+) -> Result<String, UriparseError> {
+    match scheme {
+        Some(Scheme::File) => {}
+        scheme => {
+            return Err(UriparseError::NotFileScheme(
+                scheme.map(|s| s.as_str().to_string()),
+            ))
+        }
+    }
+    let host = match host {
+        Some(host) if !host.eq_ignore_ascii_case("localhost") => host,
+        _ => String::new(),
+    };
+    Ok(format!("file://{}{}", host, path))
+}
+
+fn file_url_of_uri(uri: &URI) -> Result<String, UriparseError> {
+    file_url_of(
+        Some(uri.scheme()),
+        uri.host().map(|h| h.to_string()),
+        uri.path().to_string(),
+    )
+}
+
+fn file_url_of_reference(uri: &URIReference) -> Result<String, UriparseError> {
+    file_url_of(
+        uri.scheme(),
+        uri.host().map(|h| h.to_string()),
+        uri.path().to_string(),
+    )
+}
+
+/// Method for converting `std::path::Path` and `std::path::PathBuf`
+/// to a `uriparse::URI`.
+pub trait PathUriparseExt {
+    /// Encodes the path as a file URI.
+    ///
+    /// # Example:
+    /// ```
+    /// use std::path::Path;
+    /// use file_url::PathUriparseExt;
+    ///
+    /// let uri = Path::new("/srv/a b.txt").to_uriparse().unwrap();
+    /// assert_eq!(uri.path().to_string(), "/srv/a%20b.txt");
+    /// ```
+    fn to_uriparse(&self) -> Result<URI<'static>, UriparseError>;
+}
+
+/// Method for constructing a `std::path::PathBuf` from a `uriparse`
+/// URI or URI reference.
+pub trait PathFromUriparseExt: Sized {
+    /// Decodes the path of a `file:` URI, ignoring any query or
+    /// fragment.
+    ///
+    /// # Example:
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use std::path::PathBuf;
+    /// use file_url::PathFromUriparseExt;
+    ///
+    /// let uri = uriparse::URI::try_from("file:///srv/a%20b.txt").unwrap();
+    /// assert_eq!(PathBuf::from_uriparse(&uri).unwrap(), PathBuf::from("/srv/a b.txt"));
+    /// ```
+    fn from_uriparse(uri: &URI) -> Result<Self, UriparseError>;
+
+    /// Like `from_uriparse`, for a reference that might lack a scheme,
+    /// which is an error.
+    fn from_uriparse_reference(uri: &URIReference) -> Result<Self, UriparseError>;
+}
+
+impl PathUriparseExt for Path {
+    fn to_uriparse(&self) -> Result<URI<'static>, UriparseError> {
+        let file_url = self.to_file_url().map_err(UriparseError::Encode)?;
+        URI::try_from(file_url.as_str())
+            .map(URI::into_owned)
+            .map_err(UriparseError::Invalid)
+    }
+}
+
+impl PathFromUriparseExt for PathBuf {
+    fn from_uriparse(uri: &URI) -> Result<PathBuf, UriparseError> {
+        file_url_to_pathbuf(&file_url_of_uri(uri)?).map_err(UriparseError::Decode)
+    }
+
+    fn from_uriparse_reference(uri: &URIReference) -> Result<PathBuf, UriparseError> {
+        file_url_to_pathbuf(&file_url_of_reference(uri)?).map_err(UriparseError::Decode)
+    }
+}
+
+/// Borrows the URL's string, without copying it.
+impl<'a> TryFrom<&'a FileUrl> for URI<'a> {
+    type Error = URIError;
+
+    fn try_from(file_url: &'a FileUrl) -> Result<URI<'a>, URIError> {
+        URI::try_from(file_url.as_str())
+    }
+}
+
+impl TryFrom<&URI<'_>> for FileUrlBuf {
+    type Error = FileUrlParseError;
+
+    fn try_from(uri: &URI) -> Result<FileUrlBuf, FileUrlParseError> {
+        match file_url_of_uri(uri) {
+            Ok(url) => FileUrlBuf::new(url),
+            Err(_) => Err(FileUrlParseError::new("Not an absolute file URL!")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_round_trip() {
+        let path = Path::new("/gi>/some & what.whtvr");
+        let uri = path.to_uriparse().unwrap();
+        assert_eq!(PathBuf::from_uriparse(&uri).unwrap(), path);
+    }
+
+    #[test]
+    fn file_url_types_round_trip() {
+        let file_url = FileUrl::new("file://server/a/b%20c").unwrap();
+        let uri = URI::try_from(file_url).unwrap();
+        assert_eq!(uri.host().unwrap().to_string(), "server");
+        assert_eq!(FileUrlBuf::try_from(&uri).unwrap().as_file_url(), file_url);
+
+        let local = URI::try_from("file://localhost/x?q#f").unwrap();
+        assert_eq!(FileUrlBuf::try_from(&local).unwrap().as_str(), "file:///x");
+    }
+
+    #[test]
+    fn references_need_a_file_scheme() {
+        let rel = URIReference::try_from("/just/a/path").unwrap();
+        assert!(matches!(
+            PathBuf::from_uriparse_reference(&rel),
+            Err(UriparseError::NotFileScheme(None))
+        ));
+        let abs = URIReference::try_from("file:///a%20b").unwrap();
+        assert_eq!(
+            PathBuf::from_uriparse_reference(&abs).unwrap(),
+            PathBuf::from("/a b")
+        );
+        let https = URI::try_from("https://example.com/a").unwrap();
+        assert!(matches!(
+            PathBuf::from_uriparse(&https),
+            Err(UriparseError::NotFileScheme(Some(ref s))) if s == "https"
+        ));
+    }
+}
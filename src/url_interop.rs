@@ -0,0 +1,187 @@
+//! Conversions to and from `url::Url`, behind the `url` feature.
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::string::FromUtf8Error;
+
+use url::{Position, Url};
+
+use crate::{
+    file_url_to_pathbuf, FileUrl, FileUrlBuf, FileUrlParseError, PathFileUrlExt, UTFDecodeError,
+};
+
+/// Error converting between paths and `url::Url`.
+#[derive(Debug)]
+pub enum UrlError {
+    /// The path isn't valid UTF-8.
+    Encode(UTFDecodeError),
+    /// The URL's path doesn't decode to UTF-8.
+    Decode(FromUtf8Error),
+    /// The `url` crate rejected the URL, e.g. for an invalid host.
+    Parse(url::ParseError),
+    /// The URL doesn't use the `file:` scheme.
+    NotFileScheme(String),
+}
+
+impl fmt::Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UrlError::Encode(_) => write!(f, "Path is not UTF-8 compatible!"),
+            UrlError::Decode(_) => write!(f, "File URL does not decode to UTF-8!"),
+            UrlError::Parse(_) => write!(f, "Invalid URL!"),
+            UrlError::NotFileScheme(scheme) => write!(f, "Expected a file URL, got {}:", scheme),
+        }
+    }
+}
+
+impl Error for UrlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UrlError::Encode(e) => Some(e),
+            UrlError::Decode(e) => Some(e),
+            UrlError::Parse(e) => Some(e),
+            UrlError::NotFileScheme(_) => None,
+        }
+    }
+}
+
+/// Method for converting `std::path::Path` and `std::path::PathBuf`
+/// to a `url::Url`.
+pub trait PathUrlExt {
+    /// Encodes the path with this crate's rules, then parses the
+    /// result as a `url::Url`.
+    ///
+    /// # Example:
+    /// ```
+    /// use std::path::Path;
+    /// use file_url::PathUrlExt;
+    ///
+    /// let url = Path::new("/tmp/50% off.txt").to_url().unwrap();
+    /// assert_eq!(url.path(), "/tmp/50%25%20off.txt");
+    /// ```
+    fn to_url(&self) -> Result<Url, UrlError>;
+}
+
+/// Method for constructing a `std::path::PathBuf` from a `url::Url`.
+pub trait PathFromUrlExt: Sized {
+    /// Decodes the path of a `file:` URL. Any query or fragment is
+    /// ignored.
+    ///
+    /// # Example:
+    /// ```
+    /// use std::path::PathBuf;
+    /// use file_url::PathFromUrlExt;
+    ///
+    /// let url = url::Url::parse("file:///tmp/a%20b.txt#top").unwrap();
+    /// assert_eq!(PathBuf::from_url(&url).unwrap(), PathBuf::from("/tmp/a b.txt"));
+    /// ```
+    fn from_url(url: &Url) -> Result<Self, UrlError>;
+}
+
+impl PathUrlExt for Path {
+    fn to_url(&self) -> Result<Url, UrlError> {
+        let file_url = self.to_file_url().map_err(UrlError::Encode)?;
+        Url::parse(&file_url).map_err(UrlError::Parse)
+    }
+}
+
+impl PathFromUrlExt for PathBuf {
+    fn from_url(url: &Url) -> Result<PathBuf, UrlError> {
+        if url.scheme() != "file" {
+            return Err(UrlError::NotFileScheme(url.scheme().to_string()));
+        }
+        file_url_to_pathbuf(&url[..Position::AfterPath]).map_err(UrlError::Decode)
+    }
+}
+
+/// Fallible because `url` is stricter about hosts than `FileUrl`
+/// validation, e.g. it applies IDNA rules.
+impl TryFrom<&FileUrl> for Url {
+    type Error = url::ParseError;
+
+    fn try_from(file_url: &FileUrl) -> Result<Url, url::ParseError> {
+        Url::parse(file_url.as_str())
+    }
+}
+
+impl TryFrom<FileUrlBuf> for Url {
+    type Error = url::ParseError;
+
+    fn try_from(file_url: FileUrlBuf) -> Result<Url, url::ParseError> {
+        Url::parse(file_url.as_str())
+    }
+}
+
+impl TryFrom<&Url> for FileUrlBuf {
+    type Error = FileUrlParseError;
+
+    fn try_from(url: &Url) -> Result<FileUrlBuf, FileUrlParseError> {
+        FileUrlBuf::new(url[..Position::AfterPath].to_string())
+    }
+}
+
+/// Checks that `url`, just written for `path`, means the same path to
+/// the `url` crate as to this one, panicking if not. Only absolute
+/// paths without `..` are checked: `url` reads the first segment of
+/// a relative path as a host, and resolves `..` where this crate
+/// keeps it.
+#[cfg(feature = "verify-url")]
+pub(crate) fn verify_emitted(path: &Path, url: &str) {
+    use std::path::Component;
+
+    if !path.is_absolute() || path.components().any(|c| c == Component::ParentDir) {
+        return;
+    }
+    let ours = file_url_to_pathbuf(url).ok();
+    let theirs = Url::parse(url).ok().and_then(|u| u.to_file_path().ok());
+    assert!(
+        theirs.is_some() && theirs == ours,
+        "{} was emitted for {:?}, but the url crate reads it as {:?}",
+        url,
+        path,
+        theirs
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_round_trip() {
+        let path = Path::new("/gi>/some & what.whtvr");
+        let url = path.to_url().unwrap();
+        assert_eq!(url.as_str(), "file:///gi%3E/some%20%26%20what.whtvr");
+        assert_eq!(PathBuf::from_url(&url).unwrap(), path);
+    }
+
+    #[test]
+    fn file_url_types_round_trip() {
+        let file_url: FileUrlBuf = "file:///a/b%20c".parse().unwrap();
+        let url = Url::try_from(file_url.clone()).unwrap();
+        assert_eq!(FileUrlBuf::try_from(&url).unwrap(), file_url);
+    }
+
+    #[test]
+    fn rejects_other_schemes() {
+        let url = Url::parse("https://example.com/a").unwrap();
+        assert!(matches!(
+            PathBuf::from_url(&url),
+            Err(UrlError::NotFileScheme(ref s)) if s == "https"
+        ));
+        assert!(FileUrlBuf::try_from(&url).is_err());
+    }
+
+    #[cfg(feature = "verify-url")]
+    #[test]
+    fn emitted_urls_are_verified() {
+        let paths = ["/a b/%41/ü", "/x/./y//z/", "/[brackets]/{}|^`", "/a/\\"];
+        for path in paths.iter() {
+            let url = Path::new(path).to_file_url().unwrap();
+            verify_emitted(Path::new(path), &url);
+        }
+        let caught = std::panic::catch_unwind(|| verify_emitted(Path::new("/b"), "file:///a/../b"));
+        assert!(caught.is_err());
+    }
+}
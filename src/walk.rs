@@ -0,0 +1,198 @@
+//! Recursive directory walking that yields file URLs.
+use std::env;
+use std::fs::{self, ReadDir};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::FileUrlBuf;
+
+/// Walks the tree under `root`, yielding a URL for `root` itself and
+/// for every file and directory beneath it. Directory URLs end in a
+/// `/`, so they can be used directly as bases for `relative_url`.
+/// A relative `root` is resolved against the current directory when
+/// the walk starts. Entries within a directory come out in whatever order the OS
+/// lists them; sort with `UrlOrdering` if that matters.
+///
+/// # Example:
+/// ```no_run
+/// use file_url::walk_as_file_urls;
+///
+/// for url in walk_as_file_urls("/srv/site").max_depth(2) {
+///     println!("{}", url.unwrap());
+/// }
+/// ```
+pub fn walk_as_file_urls<P: AsRef<Path>>(root: P) -> WalkFileUrls {
+    WalkFileUrls {
+        root: Some(root.as_ref().to_path_buf()),
+        follow_symlinks: false,
+        max_depth: usize::MAX,
+        stack: Vec::new(),
+    }
+}
+
+/// Iterator returned by `walk_as_file_urls`. Configure it before the
+/// first call to `next`.
+#[derive(Debug)]
+pub struct WalkFileUrls {
+    root: Option<PathBuf>,
+    follow_symlinks: bool,
+    max_depth: usize,
+    // Open directories, with their canonical paths when following
+    // symlinks so that loops can be caught.
+    stack: Vec<(ReadDir, Option<PathBuf>)>,
+}
+
+impl WalkFileUrls {
+    /// Whether to descend into symlinked directories. Off by default,
+    /// in which case a symlink is yielded but not followed. A symlink
+    /// back to one of its own ancestors is reported as an error
+    /// rather than walked forever.
+    pub fn follow_symlinks(mut self, follow: bool) -> WalkFileUrls {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// How many levels below the root to descend. `0` yields just the
+    /// root, `1` the root and its direct children, and so on.
+    pub fn max_depth(mut self, depth: usize) -> WalkFileUrls {
+        self.max_depth = depth;
+        self
+    }
+
+    fn visit(&mut self, path: PathBuf, depth: usize) -> io::Result<FileUrlBuf> {
+        let metadata = if self.follow_symlinks {
+            fs::metadata(&path)?
+        } else {
+            fs::symlink_metadata(&path)?
+        };
+
+        if metadata.is_dir() && depth < self.max_depth {
+            let canonical = if self.follow_symlinks {
+                let canonical = path.canonicalize()?;
+                if self
+                    .stack
+                    .iter()
+                    .any(|(_, seen)| seen.as_ref() == Some(&canonical))
+                {
+                    return Err(io::Error::other(format!(
+                        "Symlink loop at {}",
+                        path.display()
+                    )));
+                }
+                Some(canonical)
+            } else {
+                None
+            };
+            self.stack.push((fs::read_dir(&path)?, canonical));
+        }
+
+        let url = FileUrlBuf::from_path(&path)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if metadata.is_dir() && !url.as_str().ends_with('/') {
+            let mut url = url.into_string();
+            url.push('/');
+            return Ok(FileUrlBuf::from_string_unchecked(url));
+        }
+        Ok(url)
+    }
+}
+
+impl Iterator for WalkFileUrls {
+    type Item = io::Result<FileUrlBuf>;
+
+    fn next(&mut self) -> Option<io::Result<FileUrlBuf>> {
+        if let Some(root) = self.root.take() {
+            let root = if root.has_root() {
+                Ok(root)
+            } else {
+                env::current_dir().map(|cwd| cwd.join(root))
+            };
+            return Some(root.and_then(|root| self.visit(root, 0)));
+        }
+        while let Some((entries, _)) = self.stack.last_mut() {
+            match entries.next() {
+                None => {
+                    self.stack.pop();
+                }
+                Some(Err(e)) => return Some(Err(e)),
+                Some(Ok(entry)) => {
+                    let depth = self.stack.len();
+                    return Some(self.visit(entry.path(), depth));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PathFileUrlExt;
+
+    fn temp_tree(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("file_url {} {}", name, std::process::id()));
+        fs::create_dir_all(root.join("a b/c")).unwrap();
+        fs::write(root.join("top.txt"), "").unwrap();
+        fs::write(root.join("a b/c/deep.txt"), "").unwrap();
+        root
+    }
+
+    fn walked(walk: WalkFileUrls, root: &Path) -> Vec<String> {
+        let prefix = root.to_file_url().unwrap();
+        let mut urls: Vec<String> = walk
+            .map(|u| u.unwrap().as_str()[prefix.len()..].to_string())
+            .collect();
+        urls.sort();
+        urls
+    }
+
+    #[test]
+    fn walks_whole_tree() {
+        let root = temp_tree("walk");
+        let urls = walked(walk_as_file_urls(&root), &root);
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            urls,
+            vec!["/", "/a%20b/", "/a%20b/c/", "/a%20b/c/deep.txt", "/top.txt"]
+        );
+    }
+
+    #[test]
+    fn respects_max_depth() {
+        let root = temp_tree("walk depth");
+        let urls = walked(walk_as_file_urls(&root).max_depth(1), &root);
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(urls, vec!["/", "/a%20b/", "/top.txt"]);
+    }
+
+    #[test]
+    fn relative_roots_use_the_current_directory() {
+        let name = format!("target/walk relative {}", std::process::id());
+        fs::create_dir_all(Path::new(&name).join("sub")).unwrap();
+        let urls: Vec<_> = walk_as_file_urls(&name).collect();
+        let root = env::current_dir().unwrap().join(&name);
+        fs::remove_dir_all(&name).unwrap();
+
+        let urls: Vec<String> = urls.into_iter().map(|u| u.unwrap().into_string()).collect();
+        let prefix = root.to_file_url().unwrap();
+        assert_eq!(
+            urls,
+            vec![format!("{}/", prefix), format!("{}/sub/", prefix)]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_loops_are_errors() {
+        let root = temp_tree("walk loop");
+        std::os::unix::fs::symlink(&root, root.join("a b/back")).unwrap();
+
+        let plain: Vec<_> = walk_as_file_urls(&root).collect();
+        let followed: Vec<_> = walk_as_file_urls(&root).follow_symlinks(true).collect();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(plain.iter().all(|u| u.is_ok()));
+        assert_eq!(followed.iter().filter(|u| u.is_err()).count(), 1);
+    }
+}
@@ -0,0 +1,30 @@
+//! JavaScript bindings via wasm-bindgen, behind the `wasm` feature.
+//! Build with e.g. `wasm-pack build --features wasm`.
+//!
+//! ```js
+//! import { pathToFileUrl, fileUrlToPath } from "file_url";
+//!
+//! pathToFileUrl("C:\\My Files\\a.txt", "win32");  // "file:///C:/My%20Files/a.txt"
+//! fileUrlToPath("file:///tmp/a%20b", "posix");     // "/tmp/a b"
+//! ```
+use wasm_bindgen::prelude::*;
+
+use crate::Platform;
+
+fn platform(name: &str) -> Result<Platform, JsError> {
+    Platform::from_name(name).ok_or_else(|| JsError::new(&format!("Unknown platform {}!", name)))
+}
+
+/// Converts a path in `platform`'s syntax (`"posix"`, `"windows"` or
+/// `"win32"`) to a file URL.
+#[wasm_bindgen(js_name = pathToFileUrl)]
+pub fn path_to_file_url_js(path: &str, platform_name: &str) -> Result<String, JsError> {
+    Ok(platform(platform_name)?.path_to_file_url(path))
+}
+
+/// Converts a file URL to a path in `platform`'s syntax. Throws if
+/// the URL doesn't decode to UTF-8.
+#[wasm_bindgen(js_name = fileUrlToPath)]
+pub fn file_url_to_path_js(file_url: &str, platform_name: &str) -> Result<String, JsError> {
+    Ok(platform(platform_name)?.file_url_to_path(file_url)?)
+}